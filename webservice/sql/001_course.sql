@@ -0,0 +1,15 @@
+-- course table as used by the teacher service
+-- run the numbered scripts in order: psql tutorial -f sql/001_course.sql
+CREATE TABLE IF NOT EXISTS course (
+    id SERIAL PRIMARY KEY,
+    teacher_id INT NOT NULL,
+    name VARCHAR(140) NOT NULL,
+    time TIMESTAMP DEFAULT now(),
+    description VARCHAR(2000),
+    format VARCHAR(30),
+    structure VARCHAR(200),
+    duration VARCHAR(30),
+    price INT,
+    language VARCHAR(30),
+    level VARCHAR(30)
+);
@@ -0,0 +1,6 @@
+-- deleting a course only stamps deleted_at, the row stays in the teacher's trash
+-- until it is restored or purged after the retention period
+ALTER TABLE course ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS course_teacher_live_idx ON course (teacher_id) WHERE deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS course_deleted_at_idx ON course (deleted_at) WHERE deleted_at IS NOT NULL;
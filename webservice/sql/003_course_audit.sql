@@ -0,0 +1,15 @@
+-- one row per course mutation, written in the same transaction as the mutation
+-- no foreign key, so the history outlives courses purged from the trash
+CREATE TABLE IF NOT EXISTS course_audit (
+    id BIGSERIAL PRIMARY KEY,
+    course_id INT NOT NULL,
+    teacher_id INT NOT NULL,
+    action VARCHAR(20) NOT NULL,
+    actor VARCHAR(100) NOT NULL,
+    request_id VARCHAR(64) NOT NULL,
+    before JSONB,
+    after JSONB,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS course_audit_course_idx ON course_audit (teacher_id, course_id, id);
@@ -0,0 +1,13 @@
+-- numbered snapshots of the editable course content, one per change
+-- revision 1 is the content the course was created with; courses created
+-- before this table existed get their pre-edit content as revision 1, with no actor
+CREATE TABLE IF NOT EXISTS course_revision (
+    course_id INT NOT NULL,
+    revision INT NOT NULL,
+    teacher_id INT NOT NULL,
+    description VARCHAR(2000),
+    structure VARCHAR(200),
+    actor VARCHAR(100),
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (course_id, revision)
+);
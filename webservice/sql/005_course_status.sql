@@ -0,0 +1,8 @@
+-- draft -> in_review -> published -> archived lifecycle, transitions are checked by the service
+-- courses that existed before are already live, so they start out published
+ALTER TABLE course ADD COLUMN IF NOT EXISTS status VARCHAR(20) NOT NULL DEFAULT 'published'
+    CHECK (status IN ('draft', 'in_review', 'published', 'archived'));
+ALTER TABLE course ADD COLUMN IF NOT EXISTS published_at TIMESTAMP;
+ALTER TABLE course ALTER COLUMN status SET DEFAULT 'draft';
+
+UPDATE course SET published_at = time WHERE status = 'published' AND published_at IS NULL;
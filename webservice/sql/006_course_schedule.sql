@@ -0,0 +1,7 @@
+-- publish_at/unpublish_at are picked up by the scheduler in teacher-service,
+-- which applies the transition and clears the column
+ALTER TABLE course ADD COLUMN IF NOT EXISTS publish_at TIMESTAMPTZ;
+ALTER TABLE course ADD COLUMN IF NOT EXISTS unpublish_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS course_publish_at_idx ON course (publish_at) WHERE publish_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS course_unpublish_at_idx ON course (unpublish_at) WHERE unpublish_at IS NOT NULL;
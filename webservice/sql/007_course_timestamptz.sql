@@ -0,0 +1,30 @@
+-- time becomes created_at, and every timestamp is stored with its zone
+-- naive values were written by now() in the server's zone, so they are read in the session zone
+ALTER TABLE course RENAME COLUMN time TO created_at;
+ALTER TABLE course ALTER COLUMN created_at TYPE TIMESTAMPTZ USING COALESCE(created_at::timestamptz, now());
+ALTER TABLE course ALTER COLUMN created_at SET DEFAULT now();
+ALTER TABLE course ALTER COLUMN created_at SET NOT NULL;
+
+ALTER TABLE course ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ;
+UPDATE course SET updated_at = created_at WHERE updated_at IS NULL;
+ALTER TABLE course ALTER COLUMN updated_at SET DEFAULT now();
+ALTER TABLE course ALTER COLUMN updated_at SET NOT NULL;
+
+ALTER TABLE course ALTER COLUMN published_at TYPE TIMESTAMPTZ USING published_at::timestamptz;
+ALTER TABLE course ALTER COLUMN deleted_at TYPE TIMESTAMPTZ USING deleted_at::timestamptz;
+ALTER TABLE course_audit ALTER COLUMN created_at TYPE TIMESTAMPTZ USING created_at::timestamptz;
+ALTER TABLE course_revision ALTER COLUMN created_at TYPE TIMESTAMPTZ USING created_at::timestamptz;
+
+-- any change to a course row moves updated_at, whichever statement makes it
+CREATE OR REPLACE FUNCTION course_touch_updated_at() RETURNS trigger AS $$
+BEGIN
+    NEW.updated_at = now();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS course_touch_updated_at ON course;
+CREATE TRIGGER course_touch_updated_at BEFORE UPDATE ON course
+    FOR EACH ROW EXECUTE FUNCTION course_touch_updated_at();
+
+CREATE INDEX IF NOT EXISTS course_updated_at_idx ON course (updated_at);
@@ -0,0 +1,25 @@
+-- prices become an amount in minor units with an ISO 4217 currency
+-- a free course is stored as 0 without a currency, a course without a price has neither
+ALTER TABLE course RENAME COLUMN price TO price_minor;
+ALTER TABLE course ALTER COLUMN price_minor TYPE BIGINT;
+ALTER TABLE course ADD COLUMN IF NOT EXISTS currency VARCHAR(3);
+
+-- older prices carried no unit and were entered as whole dollars
+-- a 0 was also written for updates that did not send a price, so it is not read as free
+UPDATE course SET price_minor = price_minor * 100, currency = 'USD' WHERE price_minor > 0 AND currency IS NULL;
+UPDATE course SET price_minor = NULL WHERE price_minor <= 0;
+
+ALTER TABLE course ADD CONSTRAINT course_price_currency_check CHECK (
+    (price_minor IS NULL AND currency IS NULL)
+    OR (price_minor = 0 AND currency IS NULL)
+    OR (price_minor > 0 AND currency ~ '^[A-Z]{3}$')
+);
+
+-- the price as the service reads it, kept in step with the two columns above
+ALTER TABLE course ADD COLUMN price JSONB GENERATED ALWAYS AS (
+    CASE
+        WHEN price_minor = 0 THEN '{"kind": "free"}'::jsonb
+        WHEN price_minor > 0 THEN ('{"kind": "paid", "amount_minor": ' || price_minor::text
+            || ', "currency": "' || currency || '"}')::jsonb
+    END
+) STORED;
@@ -0,0 +1,11 @@
+-- exchange rates maintained by an admin, one unit of base buys rate units of quote
+-- used to show course prices in other currencies, the stored price never changes
+CREATE TABLE IF NOT EXISTS exchange_rate (
+    base VARCHAR(3) NOT NULL,
+    quote VARCHAR(3) NOT NULL,
+    rate DOUBLE PRECISION NOT NULL CHECK (rate > 0),
+    -- when the rate was taken, as given by the admin
+    as_of TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (base, quote),
+    CHECK (base <> quote)
+);
@@ -0,0 +1,90 @@
+-- level and format take a fixed set of values, language a BCP 47 tag
+-- existing free text is mapped where the meaning is clear, anything else is cleared
+-- and kept in course_cleanup so it can be fixed by hand
+
+CREATE TABLE IF NOT EXISTS course_cleanup (
+    course_id INT NOT NULL,
+    field VARCHAR(30) NOT NULL,
+    old_value VARCHAR(2000) NOT NULL,
+    cleaned_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- empty strings were written for fields an update did not send
+UPDATE course SET level = NULL WHERE trim(level) = '';
+UPDATE course SET format = NULL WHERE trim(format) = '';
+UPDATE course SET language = NULL WHERE trim(language) = '';
+
+UPDATE course SET level = CASE
+    WHEN lower(trim(level)) IN ('beginner', 'beginners', 'begginer', 'beginer', 'basic', 'intro', 'introductory', 'novice', 'entry')
+        THEN 'beginner'
+    WHEN lower(trim(level)) IN ('intermediate', 'intermidiate', 'intermediat', 'medium', 'mid')
+        THEN 'intermediate'
+    WHEN lower(trim(level)) IN ('advanced', 'advance', 'advanced level', 'expert', 'pro')
+        THEN 'advanced'
+    WHEN lower(trim(level)) IN ('all', 'all levels', 'all_levels', 'all-levels', 'any', 'everyone')
+        THEN 'all_levels'
+    ELSE level
+END
+WHERE level IS NOT NULL;
+
+UPDATE course SET format = CASE
+    WHEN lower(trim(format)) IN ('self_paced', 'self-paced', 'self paced', 'selfpaced', 'online', 'video', 'recorded', 'on demand', 'on-demand')
+        THEN 'self_paced'
+    WHEN lower(trim(format)) IN ('live_online', 'live online', 'live', 'webinar', 'virtual', 'virtual classroom')
+        THEN 'live_online'
+    WHEN lower(trim(format)) IN ('in_person', 'in person', 'in-person', 'offline', 'classroom', 'onsite', 'on-site', 'on site')
+        THEN 'in_person'
+    WHEN lower(trim(format)) IN ('hybrid', 'blended', 'mixed')
+        THEN 'hybrid'
+    ELSE format
+END
+WHERE format IS NOT NULL;
+
+-- language names become their ISO 639-1 code, ll_RR and ll-rr tags get their conventional case
+UPDATE course SET language = CASE lower(trim(language))
+    WHEN 'english' THEN 'en'
+    WHEN 'chinese' THEN 'zh'
+    WHEN 'mandarin' THEN 'zh'
+    WHEN 'spanish' THEN 'es'
+    WHEN 'french' THEN 'fr'
+    WHEN 'german' THEN 'de'
+    WHEN 'japanese' THEN 'ja'
+    WHEN 'korean' THEN 'ko'
+    WHEN 'portuguese' THEN 'pt'
+    WHEN 'italian' THEN 'it'
+    WHEN 'russian' THEN 'ru'
+    WHEN 'arabic' THEN 'ar'
+    WHEN 'hindi' THEN 'hi'
+    WHEN 'dutch' THEN 'nl'
+    ELSE CASE
+        WHEN trim(language) ~* '^[a-z]{2,3}$' THEN lower(trim(language))
+        WHEN trim(language) ~* '^[a-z]{2,3}[-_][a-z]{2}$'
+            THEN lower(substr(trim(language), 1, length(trim(language)) - 3)) || '-' || upper(right(trim(language), 2))
+        ELSE language
+    END
+END
+WHERE language IS NOT NULL;
+
+INSERT INTO course_cleanup (course_id, field, old_value)
+SELECT id, 'level', level FROM course
+WHERE level NOT IN ('beginner', 'intermediate', 'advanced', 'all_levels');
+UPDATE course SET level = NULL WHERE level NOT IN ('beginner', 'intermediate', 'advanced', 'all_levels');
+
+INSERT INTO course_cleanup (course_id, field, old_value)
+SELECT id, 'format', format FROM course
+WHERE format NOT IN ('self_paced', 'live_online', 'in_person', 'hybrid');
+UPDATE course SET format = NULL WHERE format NOT IN ('self_paced', 'live_online', 'in_person', 'hybrid');
+
+-- a rough shape check, the service validates the full BCP 47 grammar
+INSERT INTO course_cleanup (course_id, field, old_value)
+SELECT id, 'language', language FROM course
+WHERE language !~ '^([a-z]{2,3}(-[A-Za-z0-9]{1,8})*|x(-[a-z0-9]{1,8})+)$';
+UPDATE course SET language = NULL WHERE language !~ '^([a-z]{2,3}(-[A-Za-z0-9]{1,8})*|x(-[a-z0-9]{1,8})+)$';
+
+ALTER TABLE course ALTER COLUMN language TYPE VARCHAR(35);
+ALTER TABLE course ADD CONSTRAINT course_level_check
+    CHECK (level IN ('beginner', 'intermediate', 'advanced', 'all_levels'));
+ALTER TABLE course ADD CONSTRAINT course_format_check
+    CHECK (format IN ('self_paced', 'live_online', 'in_person', 'hybrid'));
+ALTER TABLE course ADD CONSTRAINT course_language_check
+    CHECK (language ~ '^([a-z]{2,3}(-[A-Za-z0-9]{1,8})*|x(-[a-z0-9]{1,8})+)$');
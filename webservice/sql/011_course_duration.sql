@@ -0,0 +1,21 @@
+-- duration becomes a total in minutes with an optional weeks and sessions breakdown
+-- the free text is kept in duration_legacy until teacher-service has parsed it at startup,
+-- text it cannot read is moved to course_cleanup
+ALTER TABLE course RENAME COLUMN duration TO duration_legacy;
+UPDATE course SET duration_legacy = NULL WHERE trim(duration_legacy) = '';
+
+ALTER TABLE course ADD COLUMN IF NOT EXISTS duration_minutes INT CHECK (duration_minutes > 0);
+ALTER TABLE course ADD COLUMN IF NOT EXISTS duration_weeks INT CHECK (duration_weeks > 0);
+ALTER TABLE course ADD COLUMN IF NOT EXISTS duration_sessions INT CHECK (duration_sessions > 0);
+ALTER TABLE course ADD CONSTRAINT course_duration_breakdown_check
+    CHECK (duration_minutes IS NOT NULL OR (duration_weeks IS NULL AND duration_sessions IS NULL));
+
+-- the duration as the service reads it, kept in step with the columns above
+ALTER TABLE course ADD COLUMN duration JSONB GENERATED ALWAYS AS (
+    CASE WHEN duration_minutes IS NOT NULL THEN ('{"minutes": ' || duration_minutes::text
+        || ', "weeks": ' || COALESCE(duration_weeks::text, 'null')
+        || ', "sessions": ' || COALESCE(duration_sessions::text, 'null') || '}')::jsonb
+    END
+) STORED;
+
+CREATE INDEX IF NOT EXISTS course_duration_minutes_idx ON course (duration_minutes);
@@ -0,0 +1,34 @@
+-- the syllabus of a course: ordered modules, each with ordered lessons
+-- positions start at 1 and have no gaps, the unique constraints are checked at the end
+-- of each statement so a reorder can move every row in one UPDATE
+CREATE TABLE IF NOT EXISTS module (
+    id SERIAL PRIMARY KEY,
+    course_id INT NOT NULL REFERENCES course (id) ON DELETE CASCADE,
+    position INT NOT NULL CHECK (position > 0),
+    title VARCHAR(140) NOT NULL,
+    description VARCHAR(2000),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT module_position_unique UNIQUE (course_id, position) DEFERRABLE INITIALLY IMMEDIATE
+);
+
+CREATE TABLE IF NOT EXISTS lesson (
+    id SERIAL PRIMARY KEY,
+    module_id INT NOT NULL REFERENCES module (id) ON DELETE CASCADE,
+    position INT NOT NULL CHECK (position > 0),
+    title VARCHAR(140) NOT NULL,
+    content TEXT,
+    -- estimated time to work through the lesson
+    duration_minutes INT CHECK (duration_minutes > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT lesson_position_unique UNIQUE (module_id, position) DEFERRABLE INITIALLY IMMEDIATE
+);
+
+-- course_touch_updated_at from 007 only sets NEW.updated_at, so it fits these tables too
+DROP TRIGGER IF EXISTS module_touch_updated_at ON module;
+CREATE TRIGGER module_touch_updated_at BEFORE UPDATE ON module
+    FOR EACH ROW EXECUTE FUNCTION course_touch_updated_at();
+DROP TRIGGER IF EXISTS lesson_touch_updated_at ON lesson;
+CREATE TRIGGER lesson_touch_updated_at BEFORE UPDATE ON lesson
+    FOR EACH ROW EXECUTE FUNCTION course_touch_updated_at();
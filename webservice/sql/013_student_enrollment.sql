@@ -0,0 +1,31 @@
+-- people who take courses, emails are stored lowercased so the unique constraint ignores case
+CREATE TABLE IF NOT EXISTS student (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(140) NOT NULL,
+    email VARCHAR(254) NOT NULL UNIQUE CHECK (email = lower(email)),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- one row per student and course, withdrawing keeps the row so enrolling again reuses it
+CREATE TABLE IF NOT EXISTS enrollment (
+    id SERIAL PRIMARY KEY,
+    course_id INT NOT NULL REFERENCES course (id) ON DELETE CASCADE,
+    student_id INT NOT NULL REFERENCES student (id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'active',
+    enrolled_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    withdrawn_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT enrollment_status_check CHECK (status IN ('active', 'withdrawn')),
+    CONSTRAINT enrollment_withdrawn_check CHECK ((status = 'withdrawn') = (withdrawn_at IS NOT NULL)),
+    CONSTRAINT enrollment_unique UNIQUE (course_id, student_id)
+);
+
+CREATE INDEX IF NOT EXISTS enrollment_student_idx ON enrollment (student_id);
+
+DROP TRIGGER IF EXISTS student_touch_updated_at ON student;
+CREATE TRIGGER student_touch_updated_at BEFORE UPDATE ON student
+    FOR EACH ROW EXECUTE FUNCTION course_touch_updated_at();
+DROP TRIGGER IF EXISTS enrollment_touch_updated_at ON enrollment;
+CREATE TRIGGER enrollment_touch_updated_at BEFORE UPDATE ON enrollment
+    FOR EACH ROW EXECUTE FUNCTION course_touch_updated_at();
@@ -0,0 +1,35 @@
+-- optional seat limit, NULL for courses without one
+ALTER TABLE course ADD COLUMN IF NOT EXISTS capacity INT;
+ALTER TABLE course DROP CONSTRAINT IF EXISTS course_capacity_check;
+ALTER TABLE course ADD CONSTRAINT course_capacity_check CHECK (capacity > 0);
+
+-- students who asked for a seat in a full course wait in the order of waitlisted_at
+-- enrolled_at is only set once a student holds a seat, so it becomes nullable
+ALTER TABLE enrollment ADD COLUMN IF NOT EXISTS waitlisted_at TIMESTAMPTZ;
+ALTER TABLE enrollment ALTER COLUMN enrolled_at DROP NOT NULL;
+ALTER TABLE enrollment ALTER COLUMN enrolled_at DROP DEFAULT;
+ALTER TABLE enrollment DROP CONSTRAINT IF EXISTS enrollment_status_check;
+ALTER TABLE enrollment ADD CONSTRAINT enrollment_status_check
+    CHECK (status IN ('active', 'waitlisted', 'withdrawn'));
+ALTER TABLE enrollment DROP CONSTRAINT IF EXISTS enrollment_active_check;
+ALTER TABLE enrollment ADD CONSTRAINT enrollment_active_check
+    CHECK (status <> 'active' OR enrolled_at IS NOT NULL);
+ALTER TABLE enrollment DROP CONSTRAINT IF EXISTS enrollment_waitlisted_check;
+ALTER TABLE enrollment ADD CONSTRAINT enrollment_waitlisted_check
+    CHECK (status <> 'waitlisted' OR waitlisted_at IS NOT NULL);
+
+CREATE INDEX IF NOT EXISTS enrollment_waitlist_idx ON enrollment (course_id, waitlisted_at, id)
+    WHERE status = 'waitlisted';
+
+-- events for students, written in the transaction that caused them
+-- so a promotion is never recorded without its event or the other way round
+CREATE TABLE IF NOT EXISTS notification_event (
+    id BIGSERIAL PRIMARY KEY,
+    student_id INT NOT NULL REFERENCES student (id) ON DELETE CASCADE,
+    course_id INT REFERENCES course (id) ON DELETE CASCADE,
+    kind VARCHAR(40) NOT NULL,
+    payload JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS notification_event_student_idx ON notification_event (student_id, id);
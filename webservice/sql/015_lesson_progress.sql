@@ -0,0 +1,28 @@
+-- lessons count towards completion unless marked optional
+ALTER TABLE lesson ADD COLUMN IF NOT EXISTS required BOOLEAN NOT NULL DEFAULT true;
+
+-- an enrollment becomes completed once every required lesson is completed, it keeps its seat
+ALTER TABLE enrollment ADD COLUMN IF NOT EXISTS completed_at TIMESTAMPTZ;
+ALTER TABLE enrollment DROP CONSTRAINT IF EXISTS enrollment_status_check;
+ALTER TABLE enrollment ADD CONSTRAINT enrollment_status_check
+    CHECK (status IN ('active', 'waitlisted', 'completed', 'withdrawn'));
+ALTER TABLE enrollment DROP CONSTRAINT IF EXISTS enrollment_completed_check;
+ALTER TABLE enrollment ADD CONSTRAINT enrollment_completed_check
+    CHECK ((status = 'completed') = (completed_at IS NOT NULL));
+
+-- how far a student got in each lesson of a course they are enrolled in
+-- updated_at moves on every start or completion and gives the last activity
+CREATE TABLE IF NOT EXISTS lesson_progress (
+    enrollment_id INT NOT NULL REFERENCES enrollment (id) ON DELETE CASCADE,
+    lesson_id INT NOT NULL REFERENCES lesson (id) ON DELETE CASCADE,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    completed_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (enrollment_id, lesson_id)
+);
+
+CREATE INDEX IF NOT EXISTS lesson_progress_lesson_idx ON lesson_progress (lesson_id);
+
+DROP TRIGGER IF EXISTS lesson_progress_touch_updated_at ON lesson_progress;
+CREATE TRIGGER lesson_progress_touch_updated_at BEFORE UPDATE ON lesson_progress
+    FOR EACH ROW EXECUTE FUNCTION course_touch_updated_at();
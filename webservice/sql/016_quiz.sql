@@ -0,0 +1,62 @@
+-- quizzes belong to a course, and optionally to one of its lessons
+CREATE TABLE IF NOT EXISTS quiz (
+    id SERIAL PRIMARY KEY,
+    course_id INT NOT NULL REFERENCES course (id) ON DELETE CASCADE,
+    lesson_id INT REFERENCES lesson (id) ON DELETE CASCADE,
+    title VARCHAR(140) NOT NULL,
+    description VARCHAR(2000),
+    -- NULL for no limit
+    max_attempts INT CHECK (max_attempts > 0),
+    time_limit_minutes INT CHECK (time_limit_minutes > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS quiz_course_idx ON quiz (course_id);
+
+-- spec holds the kind, the options and the answer key, {"kind": "single_choice", "options": [...], "correct": 1}
+CREATE TABLE IF NOT EXISTS quiz_question (
+    id SERIAL PRIMARY KEY,
+    quiz_id INT NOT NULL REFERENCES quiz (id) ON DELETE CASCADE,
+    position INT NOT NULL CHECK (position > 0),
+    prompt VARCHAR(2000) NOT NULL,
+    points INT NOT NULL DEFAULT 1 CHECK (points > 0),
+    spec JSONB NOT NULL,
+    kind VARCHAR(20) GENERATED ALWAYS AS (spec ->> 'kind') STORED,
+    CONSTRAINT quiz_question_kind_check
+        CHECK (kind IN ('single_choice', 'multiple_choice', 'numeric', 'short_text')),
+    CONSTRAINT quiz_question_position_unique UNIQUE (quiz_id, position)
+);
+
+-- deadline_at is started_at plus the quiz's time limit, submissions after it are refused
+CREATE TABLE IF NOT EXISTS quiz_attempt (
+    id SERIAL PRIMARY KEY,
+    quiz_id INT NOT NULL REFERENCES quiz (id) ON DELETE CASCADE,
+    enrollment_id INT NOT NULL REFERENCES enrollment (id) ON DELETE CASCADE,
+    attempt_number INT NOT NULL CHECK (attempt_number > 0),
+    status VARCHAR(20) NOT NULL DEFAULT 'in_progress',
+    started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    deadline_at TIMESTAMPTZ,
+    submitted_at TIMESTAMPTZ,
+    score INT,
+    max_score INT NOT NULL,
+    -- some answers wait for the teacher to grade them
+    pending_review BOOLEAN NOT NULL DEFAULT false,
+    CONSTRAINT quiz_attempt_status_check CHECK (status IN ('in_progress', 'submitted', 'expired')),
+    CONSTRAINT quiz_attempt_unique UNIQUE (quiz_id, enrollment_id, attempt_number)
+);
+
+-- one row per question of a submitted attempt, response is NULL for a question left unanswered
+-- correct and points_awarded are NULL while the answer waits for review
+CREATE TABLE IF NOT EXISTS quiz_answer (
+    attempt_id INT NOT NULL REFERENCES quiz_attempt (id) ON DELETE CASCADE,
+    question_id INT NOT NULL REFERENCES quiz_question (id) ON DELETE CASCADE,
+    response JSONB,
+    correct BOOLEAN,
+    points_awarded INT CHECK (points_awarded >= 0),
+    PRIMARY KEY (attempt_id, question_id)
+);
+
+DROP TRIGGER IF EXISTS quiz_touch_updated_at ON quiz;
+CREATE TRIGGER quiz_touch_updated_at BEFORE UPDATE ON quiz
+    FOR EACH ROW EXECUTE FUNCTION course_touch_updated_at();
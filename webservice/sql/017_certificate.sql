@@ -0,0 +1,17 @@
+-- one certificate per completed enrollment, names are copied so the certificate reads the same after later edits
+CREATE TABLE IF NOT EXISTS certificate (
+    id SERIAL PRIMARY KEY,
+    enrollment_id INT NOT NULL UNIQUE REFERENCES enrollment (id) ON DELETE CASCADE,
+    -- what the public verification endpoint is queried with, XXXX-XXXX-XXXX
+    code VARCHAR(14) NOT NULL UNIQUE,
+    student_id INT NOT NULL,
+    course_id INT NOT NULL,
+    teacher_id INT NOT NULL,
+    student_name VARCHAR(140) NOT NULL,
+    course_name VARCHAR(140) NOT NULL,
+    teacher_name VARCHAR(140) NOT NULL,
+    completed_at TIMESTAMPTZ NOT NULL,
+    issued_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    -- the PDF, relative to the certificate directory
+    file_name VARCHAR(200) NOT NULL
+);
@@ -0,0 +1,39 @@
+-- aggregate of the course's ratings, moved by the review endpoints in the same transaction as the review
+ALTER TABLE course ADD COLUMN IF NOT EXISTS review_count INT NOT NULL DEFAULT 0;
+ALTER TABLE course ADD COLUMN IF NOT EXISTS rating_sum INT NOT NULL DEFAULT 0;
+ALTER TABLE course ADD COLUMN IF NOT EXISTS average_rating DOUBLE PRECISION
+    GENERATED ALWAYS AS (CASE WHEN review_count > 0 THEN round(rating_sum::numeric / review_count, 2)::float8 END) STORED;
+
+-- a new rating is not an edit of the course, so updated_at only moves when something else changed
+DROP TRIGGER IF EXISTS course_touch_updated_at ON course;
+CREATE TRIGGER course_touch_updated_at BEFORE UPDATE ON course
+    FOR EACH ROW
+    WHEN ((OLD.review_count, OLD.rating_sum) IS NOT DISTINCT FROM (NEW.review_count, NEW.rating_sum))
+    EXECUTE FUNCTION course_touch_updated_at();
+
+-- one review per student and course, the student can edit it and the teacher can reply
+-- updated_at is the student's last edit, votes and replies leave it alone
+CREATE TABLE IF NOT EXISTS review (
+    id SERIAL PRIMARY KEY,
+    course_id INT NOT NULL REFERENCES course (id) ON DELETE CASCADE,
+    student_id INT NOT NULL REFERENCES student (id) ON DELETE CASCADE,
+    rating INT NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    body VARCHAR(4000),
+    helpful_count INT NOT NULL DEFAULT 0,
+    reply VARCHAR(4000),
+    replied_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT review_unique UNIQUE (course_id, student_id)
+);
+
+CREATE INDEX IF NOT EXISTS review_helpful_idx ON review (course_id, helpful_count DESC, id DESC);
+CREATE INDEX IF NOT EXISTS review_recent_idx ON review (course_id, created_at DESC, id DESC);
+
+-- students who found a review helpful, one vote each
+CREATE TABLE IF NOT EXISTS review_vote (
+    review_id INT NOT NULL REFERENCES review (id) ON DELETE CASCADE,
+    student_id INT NOT NULL REFERENCES student (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (review_id, student_id)
+);
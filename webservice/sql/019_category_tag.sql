@@ -0,0 +1,43 @@
+-- a tree of categories managed by an admin, a category without parent_id is a root
+-- a category with children cannot be deleted, its children have to be moved or deleted first
+CREATE TABLE IF NOT EXISTS category (
+    id SERIAL PRIMARY KEY,
+    parent_id INT REFERENCES category (id) ON DELETE RESTRICT,
+    name VARCHAR(100) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT category_not_own_parent CHECK (parent_id <> id)
+);
+
+-- siblings have different names, whatever their case
+CREATE UNIQUE INDEX IF NOT EXISTS category_sibling_name_unique ON category (COALESCE(parent_id, 0), lower(name));
+CREATE INDEX IF NOT EXISTS category_parent_idx ON category (parent_id);
+
+DROP TRIGGER IF EXISTS category_touch_updated_at ON category;
+CREATE TRIGGER category_touch_updated_at BEFORE UPDATE ON category
+    FOR EACH ROW EXECUTE FUNCTION course_touch_updated_at();
+
+CREATE TABLE IF NOT EXISTS course_category (
+    course_id INT NOT NULL REFERENCES course (id) ON DELETE CASCADE,
+    category_id INT NOT NULL REFERENCES category (id) ON DELETE CASCADE,
+    PRIMARY KEY (course_id, category_id)
+);
+
+CREATE INDEX IF NOT EXISTS course_category_category_idx ON course_category (category_id);
+
+-- free-form tags, stored lowercased so "Rust" and "rust" are one tag
+CREATE TABLE IF NOT EXISTS tag (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(50) NOT NULL UNIQUE CHECK (name = lower(name))
+);
+
+-- text_pattern_ops lets the prefix search of the autocompletion use the index
+CREATE INDEX IF NOT EXISTS tag_name_prefix_idx ON tag (name text_pattern_ops);
+
+CREATE TABLE IF NOT EXISTS course_tag (
+    course_id INT NOT NULL REFERENCES course (id) ON DELETE CASCADE,
+    tag_id INT NOT NULL REFERENCES tag (id) ON DELETE CASCADE,
+    PRIMARY KEY (course_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS course_tag_tag_idx ON course_tag (tag_id);
@@ -0,0 +1,19 @@
+-- courses a student has to complete before enrolling in course_id
+-- the service keeps the graph free of cycles, a course cannot require itself directly or through others
+CREATE TABLE IF NOT EXISTS course_prerequisite (
+    course_id INT NOT NULL REFERENCES course (id) ON DELETE CASCADE,
+    prerequisite_id INT NOT NULL REFERENCES course (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (course_id, prerequisite_id),
+    CONSTRAINT course_prerequisite_not_self CHECK (course_id <> prerequisite_id)
+);
+
+CREATE INDEX IF NOT EXISTS course_prerequisite_prerequisite_idx ON course_prerequisite (prerequisite_id);
+
+-- students the teacher lets enroll without the prerequisites
+CREATE TABLE IF NOT EXISTS prerequisite_override (
+    course_id INT NOT NULL REFERENCES course (id) ON DELETE CASCADE,
+    student_id INT NOT NULL REFERENCES student (id) ON DELETE CASCADE,
+    granted_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (course_id, student_id)
+);
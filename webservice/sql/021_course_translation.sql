@@ -0,0 +1,17 @@
+-- the course's text in other languages, course.language is the language of the course row itself
+-- locale is a BCP 47 tag in its conventional case, as course.language is stored
+-- a missing description or structure falls back to the course's own
+CREATE TABLE IF NOT EXISTS course_translation (
+    course_id INT NOT NULL REFERENCES course (id) ON DELETE CASCADE,
+    locale VARCHAR(35) NOT NULL,
+    name VARCHAR(140) NOT NULL,
+    description VARCHAR(2000),
+    structure VARCHAR(200),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (course_id, locale)
+);
+
+DROP TRIGGER IF EXISTS course_translation_touch_updated_at ON course_translation;
+CREATE TRIGGER course_translation_touch_updated_at BEFORE UPDATE ON course_translation
+    FOR EACH ROW EXECUTE FUNCTION course_touch_updated_at();
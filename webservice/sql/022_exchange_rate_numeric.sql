@@ -0,0 +1,3 @@
+-- rates are exact decimals, so a conversion rounds the same way wherever it is worked out
+-- nine decimals, as many as the service reads, double precision values are rounded to them
+ALTER TABLE exchange_rate ALTER COLUMN rate TYPE NUMERIC(24, 9) USING round(rate::numeric, 9);
@@ -0,0 +1,3 @@
+-- deleting a lesson keeps its quizzes and their graded attempts, they become quizzes of the whole course
+ALTER TABLE quiz DROP CONSTRAINT IF EXISTS quiz_lesson_id_fkey;
+ALTER TABLE quiz ADD CONSTRAINT quiz_lesson_id_fkey FOREIGN KEY (lesson_id) REFERENCES lesson (id) ON DELETE SET NULL;
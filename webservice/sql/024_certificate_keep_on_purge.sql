@@ -0,0 +1,5 @@
+-- a purged course takes its enrollments with it, but certificates already issued stay verifiable
+-- the certificate copies every id and name it shows, the enrollment is only kept while it exists
+ALTER TABLE certificate ALTER COLUMN enrollment_id DROP NOT NULL;
+ALTER TABLE certificate DROP CONSTRAINT IF EXISTS certificate_enrollment_id_fkey;
+ALTER TABLE certificate ADD CONSTRAINT certificate_enrollment_id_fkey FOREIGN KEY (enrollment_id) REFERENCES enrollment (id) ON DELETE SET NULL;
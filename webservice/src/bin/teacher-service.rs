@@ -3,7 +3,9 @@ use std::io;
 use std::sync::Mutex;
 use dotenv::dotenv;
 use std::env;
+use std::time::Duration;
 use sqlx::postgres::PgPoolOptions;
+use env_logger::Env;
 
 #[path = "../db_access/mod.rs"]
 mod db_access;
@@ -17,24 +19,72 @@ mod state;
 mod models;
 #[path = "../errors.rs"]
 mod errors;
+#[path = "../negotiation.rs"]
+mod negotiation;
+#[path = "../jobs.rs"]
+mod jobs;
 
 use routers::*;
 use state::AppState;
+use models::certificate::CertificateSettings;
 
 #[actix_rt::main]
 async fn main() -> io::Result<()> {
     dotenv().ok();
+    env_logger::init_from_env(Env::default().default_filter_or("info"));
 
     //panic in case not able to read url
     let databse_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set.");
     let db_pool = PgPoolOptions::new().connect(&databse_url).await.unwrap();
 
+    //deleted courses stay restorable for this many days
+    let trash_retention_days: i32 = env::var("COURSE_TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(30);
+    jobs::spawn_trash_purge(db_pool.clone(), trash_retention_days);
+
+    //how often scheduled publishing and unpublishing is checked
+    let scheduler_interval_secs: u64 = env::var("COURSE_SCHEDULER_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(30);
+    jobs::spawn_publish_scheduler(db_pool.clone(), Duration::from_secs(scheduler_interval_secs));
+
+    match jobs::migrate_legacy_durations(&db_pool).await {
+        Ok((0, 0)) => {}
+        Ok((parsed, cleared)) => log::info!(
+            "Parsed {} legacy course duration(s), {} could not be read",
+            parsed, cleared
+        ),
+        Err(err) => log::error!("Legacy durations not migrated: {}", err),
+    }
+
+    //exchange rates to start with, an admin can replace them at /v1/exchange-rates
+    if let Ok(path) = env::var("EXCHANGE_RATES_FILE") {
+        match jobs::load_exchange_rates_file(&db_pool, &path).await {
+            Ok(count) => log::info!("Loaded {} exchange rate(s) from {}", count, path),
+            Err(err) => log::error!("Exchange rates not loaded from {}: {}", path, err),
+        }
+    }
+
+    //certificates are written to CERTIFICATE_DIR, laid out by the JSON template in CERTIFICATE_TEMPLATE_FILE
+    let certificate_dir = env::var("CERTIFICATE_DIR").unwrap_or_else(|_| "certificates".into());
+    let certificate_template = env::var("CERTIFICATE_TEMPLATE_FILE").ok();
+    let certificates = match CertificateSettings::load(&certificate_dir, certificate_template.as_deref()) {
+        Ok(settings) => settings,
+        Err(err) => {
+            log::error!("Certificate template not loaded, using the default one: {}", err);
+            CertificateSettings::load(&certificate_dir, None).unwrap_or_default()
+        }
+    };
 
     let shared_data = web::Data::new(AppState {
         health_check_response: "I'm OK.".to_string(),
         visit_count: Mutex::new(0),
         //courses: Mutex::new(vec![]),
         db: db_pool,
+        certificates,
     });
 
     //move forces the closure to take ownership of 
@@ -50,6 +100,7 @@ async fn main() -> io::Result<()> {
         App::new()
         .app_data(shared_data.clone()) // owns shared_data
         .configure(general_routes)
+        .configure(v1_routes)
         .configure(course_routes)
     };
 
@@ -0,0 +1,65 @@
+use crate::models::audit::{AuditAction, AuditContext, CourseAuditEntry};
+use crate::models::course::Course;
+use crate::errors::MyError;
+use sqlx::postgres::{PgExecutor, PgPool};
+
+
+//appends one audit record, call it with the transaction of the mutation
+//so the record and the change are committed (or rolled back) together
+pub async fn insert_course_audit_db<'e, E: PgExecutor<'e>>(
+    executor: E,
+    ctx: &AuditContext,
+    action: AuditAction,
+    before: Option<&Course>,
+    after: Option<&Course>,
+) -> Result<(), MyError> {
+    let course = after.or(before).ok_or_else(|| {
+        MyError::ActixError("Audit record needs a before or after image".into())
+    })?;
+    let to_json = |course: Option<&Course>| {
+        course
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|err| MyError::ActixError(err.to_string()))
+    };
+
+    sqlx::query!(
+        r#"INSERT INTO course_audit (course_id, teacher_id, action, actor, request_id, before, after)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+        course.id,
+        course.teacher_id,
+        action.as_str(),
+        ctx.actor,
+        ctx.request_id,
+        to_json(before)?,
+        to_json(after)?,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_course_history_db(
+    pool: &PgPool,
+    teacher_id: i32,
+    course_id: i32,
+) -> Result<Vec<CourseAuditEntry>, MyError> {
+    let rows = sqlx::query_as!(
+        CourseAuditEntry,
+        r#"SELECT id, course_id, teacher_id, action, actor, request_id,
+           before, after, created_at
+           FROM course_audit
+           WHERE teacher_id = $1 AND course_id = $2
+           ORDER BY id"#,
+        teacher_id,
+        course_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if rows.is_empty() {
+        return Err(MyError::NotFound("No history found for this course".into()));
+    }
+    Ok(rows)
+}
@@ -0,0 +1,63 @@
+use crate::models::certificate::Certificate;
+use crate::errors::MyError;
+use sqlx::postgres::{PgConnection, PgExecutor, PgPool};
+
+pub async fn get_certificate_db<'e, E: PgExecutor<'e>>(executor: E, enrollment_id: i32) -> Result<Option<Certificate>, MyError> {
+    let row = sqlx::query_as!(
+        Certificate,
+        r#"SELECT id, enrollment_id, code, student_id, course_id, teacher_id, student_name, course_name,
+        teacher_name, completed_at, issued_at, file_name
+        FROM certificate WHERE enrollment_id = $1"#,
+        enrollment_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(row)
+}
+
+//code is normalized, XXXX-XXXX-XXXX
+pub async fn get_certificate_by_code_db(pool: &PgPool, code: &str) -> Result<Certificate, MyError> {
+    let row = sqlx::query_as!(
+        Certificate,
+        r#"SELECT id, enrollment_id, code, student_id, course_id, teacher_id, student_name, course_name,
+        teacher_name, completed_at, issued_at, file_name
+        FROM certificate WHERE code = $1"#,
+        code
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.ok_or_else(|| MyError::NotFound("Certificate not found".into()))
+}
+
+//stores the certificate of a completed enrollment with the student's and the course's current names
+//None when the code is already taken, or the enrollment already has a certificate
+pub async fn insert_certificate_db(
+    conn: &mut PgConnection,
+    enrollment_id: i32,
+    code: &str,
+    teacher_name: &str,
+) -> Result<Option<Certificate>, MyError> {
+    let row = sqlx::query_as!(
+        Certificate,
+        r#"INSERT INTO certificate (enrollment_id, code, student_id, course_id, teacher_id,
+            student_name, course_name, teacher_name, completed_at, file_name)
+        SELECT e.id, $2::varchar, e.student_id, e.course_id, course.teacher_id,
+            student.name, course.name, $3, e.completed_at, $2::varchar || '.pdf'
+        FROM enrollment e
+        JOIN student ON student.id = e.student_id
+        JOIN course ON course.id = e.course_id
+        WHERE e.id = $1 AND e.status = 'completed' AND e.completed_at IS NOT NULL
+        ON CONFLICT DO NOTHING
+        RETURNING id, enrollment_id, code, student_id, course_id, teacher_id, student_name, course_name,
+        teacher_name, completed_at, issued_at, file_name"#,
+        enrollment_id,
+        code,
+        teacher_name
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(row)
+}
@@ -1,18 +1,66 @@
-use crate::models::course::{Course, UpdateCourse, CreateCourse};
+use crate::models::course::{Course, CourseFilter, CourseSchedule, CourseStatus, UpdateCourse, CreateCourse, ImportMode, ImportReport, ImportedRow, ImportRowError, TrashedCourse};
+use crate::models::duration::CourseDuration;
+use crate::models::facet::{CourseFormat, CourseLevel, LanguageTag};
+use crate::models::money::Price;
+use crate::models::audit::{AuditAction, AuditContext};
+use crate::db_access::audit::insert_course_audit_db;
+use crate::db_access::revision::create_first_revision_db;
 use crate::errors::MyError;
-use sqlx::postgres::PgPool;
+use sqlx::postgres::{PgConnection, PgExecutor, PgPool};
+use sqlx::Connection;
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, Stream};
 
+//key of the advisory lock that keeps two instances from running the scheduler at once
+const SCHEDULER_LOCK_KEY: i64 = 0x636f_7572_7365;
 
-pub async fn get_courses_for_teacher_db(pool: &PgPool, teacher_id:i32) -> Result<Vec<Course>, MyError> {
+//rows fetched from the export cursor per round trip
+const EXPORT_BATCH_SIZE: usize = 500;
+
+
+//include_unpublished is set when the teacher lists their own courses,
+//everybody else only sees published ones
+//updated_since narrows the list to courses changed since a client's last sync
+//min_minutes and max_minutes bound the total duration, inclusive
+//a category matches the courses in it and in every category under it, tags all have to be on the course
+pub async fn get_courses_for_teacher_db(
+    pool: &PgPool,
+    teacher_id:i32,
+    include_unpublished: bool,
+    filter: &CourseFilter,
+) -> Result<Vec<Course>, MyError> {
     let rows: Vec<Course> = sqlx::query_as!(
         Course,
-        r#"SELECT id, teacher_id, name, 
-           time::timestamp as time,
-           description, format, structure, duration, 
-           price, language, level 
+        r#"WITH RECURSIVE subtree AS (
+               SELECT id FROM category WHERE id = $6
+               UNION ALL
+               SELECT category.id FROM category JOIN subtree ON category.parent_id = subtree.id
+           )
+           SELECT id, teacher_id, name, 
+           created_at as "created_at!", updated_at as "updated_at!",
+           description, format as "format: CourseFormat", structure, duration as "duration: CourseDuration", 
+           price as "price: Price", language as "language: LanguageTag", level as "level: CourseLevel", capacity, review_count, average_rating,
+           status as "status: CourseStatus", published_at, publish_at, unpublish_at
            FROM course
-           WHERE teacher_id = $1"#,
-        teacher_id
+           WHERE teacher_id = $1 AND deleted_at IS NULL
+           AND ($2 OR status = 'published')
+           AND ($3::timestamptz IS NULL OR updated_at >= $3)
+           AND ($4::int IS NULL OR duration_minutes >= $4)
+           AND ($5::int IS NULL OR duration_minutes <= $5)
+           AND ($6::int IS NULL OR EXISTS (
+               SELECT 1 FROM course_category JOIN subtree ON subtree.id = course_category.category_id
+               WHERE course_category.course_id = course.id))
+           AND (cardinality($7::varchar[]) = 0 OR cardinality($7) = (
+               SELECT count(*) FROM course_tag JOIN tag ON tag.id = course_tag.tag_id
+               WHERE course_tag.course_id = course.id AND tag.name = ANY($7)))
+           ORDER BY id"#,
+        teacher_id,
+        include_unpublished,
+        filter.updated_since,
+        filter.min_minutes,
+        filter.max_minutes,
+        filter.category_id,
+        &filter.tags[..]
     )
     .fetch_all(pool)
     .await?;
@@ -21,19 +69,22 @@ pub async fn get_courses_for_teacher_db(pool: &PgPool, teacher_id:i32) -> Result
 }
 
 
-pub async fn get_course_details_db(pool: &PgPool, teacher_id: i32, course_id: i32) -> Result<Course, MyError> {
+pub async fn get_course_details_db<'e, E: PgExecutor<'e>>(executor: E, teacher_id: i32, course_id: i32, include_unpublished: bool) -> Result<Course, MyError> {
     let row = sqlx::query_as!(
         Course,
         r#"SELECT id, teacher_id, name, 
-           time::timestamp as time,
-           description, format, structure, duration, 
-           price, language, level
+           created_at as "created_at!", updated_at as "updated_at!",
+           description, format as "format: CourseFormat", structure, duration as "duration: CourseDuration", 
+           price as "price: Price", language as "language: LanguageTag", level as "level: CourseLevel", capacity, review_count, average_rating,
+           status as "status: CourseStatus", published_at, publish_at, unpublish_at
         FROM course
-        WHERE teacher_id = $1 and id = $2"#,
+        WHERE teacher_id = $1 and id = $2 AND deleted_at IS NULL
+        AND ($3 OR status = 'published')"#,
         teacher_id,
-        course_id
+        course_id,
+        include_unpublished
     )
-    .fetch_optional(pool)
+    .fetch_optional(executor)
     .await?;
     
     if let Some(course) = row {
@@ -43,43 +94,486 @@ pub async fn get_course_details_db(pool: &PgPool, teacher_id: i32, course_id: i3
     }
 }
 
+//reads a live course and locks its row until the end of the transaction,
+//so the before image of an audit record cannot go stale
+pub async fn lock_course_db(conn: &mut PgConnection, teacher_id: i32, course_id: i32) -> Result<Option<Course>, MyError> {
+    let row = sqlx::query_as!(
+        Course,
+        r#"SELECT id, teacher_id, name, 
+           created_at as "created_at!", updated_at as "updated_at!",
+           description, format as "format: CourseFormat", structure, duration as "duration: CourseDuration", 
+           price as "price: Price", language as "language: LanguageTag", level as "level: CourseLevel", capacity, review_count, average_rating,
+           status as "status: CourseStatus", published_at, publish_at, unpublish_at
+        FROM course
+        WHERE teacher_id = $1 and id = $2 AND deleted_at IS NULL
+        FOR UPDATE"#,
+        teacher_id,
+        course_id
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(row)
+}
+
+
+//(facet, value, count) over the published courses, for GET /courses/facets
+//narrowed by the same filter as get_courses_for_teacher_db, so the counts match what a filtered list shows
+pub async fn get_course_facet_counts_db(pool: &PgPool, filter: &CourseFilter) -> Result<Vec<(String, String, i64)>, MyError> {
+    let rows = sqlx::query!(
+        r#"WITH RECURSIVE subtree AS (
+               SELECT id FROM category WHERE id = $4
+               UNION ALL
+               SELECT category.id FROM category JOIN subtree ON category.parent_id = subtree.id
+           )
+        SELECT facet as "facet!", value as "value!", count(*) as "count!"
+        FROM course,
+        LATERAL (VALUES ('level', level), ('format', format), ('language', language)) AS facets (facet, value)
+        WHERE deleted_at IS NULL AND status = 'published' AND value IS NOT NULL
+        AND ($1::timestamptz IS NULL OR updated_at >= $1)
+        AND ($2::int IS NULL OR duration_minutes >= $2)
+        AND ($3::int IS NULL OR duration_minutes <= $3)
+        AND ($4::int IS NULL OR EXISTS (
+            SELECT 1 FROM course_category JOIN subtree ON subtree.id = course_category.category_id
+            WHERE course_category.course_id = course.id))
+        AND (cardinality($5::varchar[]) = 0 OR cardinality($5) = (
+            SELECT count(*) FROM course_tag JOIN tag ON tag.id = course_tag.tag_id
+            WHERE course_tag.course_id = course.id AND tag.name = ANY($5)))
+        GROUP BY facet, value"#,
+        filter.updated_since,
+        filter.min_minutes,
+        filter.max_minutes,
+        filter.category_id,
+        &filter.tags[..]
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| (row.facet, row.value, row.count)).collect())
+}
+
+//courses whose free text duration has not been parsed yet
+pub async fn get_legacy_durations_db(pool: &PgPool) -> Result<Vec<(i32, String)>, MyError> {
+    let rows = sqlx::query!(
+        r#"SELECT id, duration_legacy as "duration_legacy!" FROM course
+        WHERE duration_legacy IS NOT NULL AND duration_minutes IS NULL
+        ORDER BY id"#
+    )
+    .fetch_all(pool)
+    .await?;
 
-pub async fn post_new_course_db(pool: &PgPool, new_course: CreateCourse) -> Result<Course, MyError> {
+    Ok(rows.into_iter().map(|row| (row.id, row.duration_legacy)).collect())
+}
+
+//stores the parsed duration of a course, or moves text that could not be read to course_cleanup
+pub async fn resolve_legacy_duration_db(
+    pool: &PgPool,
+    id: i32,
+    duration: Option<&CourseDuration>,
+) -> Result<(), MyError> {
+    let (minutes, weeks, sessions) = CourseDuration::columns(duration);
+    let mut tx = pool.begin().await?;
+    if duration.is_none() {
+        sqlx::query!(
+            r#"INSERT INTO course_cleanup (course_id, field, old_value)
+            SELECT id, 'duration', duration_legacy FROM course WHERE id = $1 AND duration_legacy IS NOT NULL"#,
+            id
+        )
+        .execute(&mut tx)
+        .await?;
+    }
+    sqlx::query!(
+        r#"UPDATE course SET duration_minutes = $2, duration_weeks = $3, duration_sessions = $4,
+        duration_legacy = NULL
+        WHERE id = $1"#,
+        id,
+        minutes,
+        weeks,
+        sessions
+    )
+    .execute(&mut tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn post_new_course_db<'e, E: PgExecutor<'e>>(executor: E, new_course: CreateCourse) -> Result<Course, MyError> {
+    let (price_minor, currency) = Price::columns(new_course.price.as_ref());
+    let (duration_minutes, duration_weeks, duration_sessions) = CourseDuration::columns(new_course.duration.as_ref());
     let row = sqlx::query_as!(
         Course,
-        r#"INSERT INTO course (teacher_id, name, description, format, structure, duration, price, language, level)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        r#"INSERT INTO course (teacher_id, name, description, format, structure,
+        duration_minutes, duration_weeks, duration_sessions,
+        price_minor, currency, language, level, capacity)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
         RETURNING id, teacher_id, name, 
-        time::timestamp as time,
-        description, format, structure, duration, price, language, level"#,
+        created_at as "created_at!", updated_at as "updated_at!",
+        description, format as "format: CourseFormat", structure, duration as "duration: CourseDuration",
+        price as "price: Price", language as "language: LanguageTag", level as "level: CourseLevel", capacity, review_count, average_rating,
+        status as "status: CourseStatus", published_at, publish_at, unpublish_at"#,
         new_course.teacher_id, new_course.name, new_course.description,
-        new_course.format, new_course.structure, new_course.duration,
-        new_course.price, new_course.language, new_course.level,
+        new_course.format.map(|format| format.as_str()), new_course.structure,
+        duration_minutes, duration_weeks, duration_sessions,
+        price_minor, currency,
+        new_course.language.as_ref().map(LanguageTag::as_str), new_course.level.map(|level| level.as_str()),
+        new_course.capacity,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(row)
+}
+
+//a new draft with the metadata of source, for teacher_id and under a new name
+//no schedule, enrollments or reviews come along, those belong to the original
+pub async fn clone_course_db(conn: &mut PgConnection, source_id: i32, teacher_id: i32, name: &str) -> Result<Course, MyError> {
+    let row = sqlx::query_as!(
+        Course,
+        r#"INSERT INTO course (teacher_id, name, description, format, structure,
+        duration_minutes, duration_weeks, duration_sessions,
+        price_minor, currency, language, level, capacity)
+        SELECT $2, $3, description, format, structure,
+        duration_minutes, duration_weeks, duration_sessions,
+        price_minor, currency, language, level, capacity
+        FROM course WHERE id = $1
+        RETURNING id, teacher_id, name, 
+        created_at as "created_at!", updated_at as "updated_at!",
+        description, format as "format: CourseFormat", structure, duration as "duration: CourseDuration",
+        price as "price: Price", language as "language: LanguageTag", level as "level: CourseLevel", capacity, review_count, average_rating,
+        status as "status: CourseStatus", published_at, publish_at, unpublish_at"#,
+        source_id,
+        teacher_id,
+        name
     )
-    .fetch_one(pool)
+    .fetch_one(conn)
     .await?;
 
     Ok(row)
 }
 
-pub async fn delete_course_db(
+//opens a server-side cursor over the matching courses and yields them in batches
+//only one batch is held in memory at a time, the cursor lives in its own transaction
+//which is committed after the last batch, or rolled back when the stream is dropped early
+pub async fn export_courses_db(
     pool: &PgPool,
+    teacher_id: Option<i32>,
+    include_unpublished: bool,
+    updated_since: Option<DateTime<Utc>>,
+) -> Result<impl Stream<Item = Result<Vec<Course>, MyError>>, MyError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"DECLARE course_export NO SCROLL CURSOR FOR
+        SELECT id, teacher_id, name,
+        created_at, updated_at,
+        description, format, structure, duration,
+        price, language, level, capacity, review_count, average_rating,
+        status, published_at, publish_at, unpublish_at
+        FROM course
+        WHERE deleted_at IS NULL
+        AND ($1::int IS NULL OR teacher_id = $1)
+        AND ($2 OR status = 'published')
+        AND ($3::timestamptz IS NULL OR updated_at >= $3)
+        ORDER BY id"#,
+    )
+    .bind(teacher_id)
+    .bind(include_unpublished)
+    .bind(updated_since)
+    .execute(&mut tx)
+    .await?;
+
+    Ok(stream::try_unfold(Some(tx), |tx| async move {
+        let mut tx = match tx {
+            Some(tx) => tx,
+            None => return Ok(None),
+        };
+        let batch: Vec<Course> = sqlx::query_as(&format!(
+            "FETCH FORWARD {} FROM course_export",
+            EXPORT_BATCH_SIZE
+        ))
+        .fetch_all(&mut tx)
+        .await?;
+
+        if batch.len() < EXPORT_BATCH_SIZE {
+            tx.commit().await?;
+            if batch.is_empty() {
+                return Ok(None);
+            }
+            return Ok(Some((batch, None)));
+        }
+        Ok(Some((batch, Some(tx))))
+    }))
+}
+
+async fn import_course_db(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    new_course: CreateCourse,
+) -> Result<Course, MyError> {
+    let course = post_new_course_db(&mut *conn, new_course).await?;
+    insert_course_audit_db(&mut *conn, ctx, AuditAction::Create, None, Some(&course)).await?;
+    create_first_revision_db(&mut *conn, ctx, &course).await?;
+    Ok(course)
+}
+
+//inserts already validated rows in a single transaction
+//every row runs in a savepoint so a failing row only undoes itself, and the rows after it are still tried
+//nothing is committed for a dry run, or for an atomic import with any error
+pub async fn import_courses_db(
+    pool: &PgPool,
+    ctx: &AuditContext,
+    rows: Vec<(usize, CreateCourse)>,
+    report: &mut ImportReport,
+) -> Result<(), MyError> {
+    let mut tx = pool.begin().await?;
+
+    for (row, new_course) in rows {
+        let name = new_course.name.clone();
+        let mut savepoint = tx.begin().await?;
+        let inserted = import_course_db(&mut savepoint, ctx, new_course).await;
+        if inserted.is_ok() {
+            savepoint.commit().await?;
+        } else {
+            savepoint.rollback().await?;
+        }
+
+        match inserted {
+            Ok(course) => report.accepted.push(ImportedRow {
+                row,
+                name,
+                course_id: Some(course.id),
+            }),
+            Err(err) => report.errors.push(ImportRowError { row, error: err.error_response() }),
+        }
+    }
+
+    let commit = !report.dry_run && (report.mode == ImportMode::PerRow || report.errors.is_empty());
+    if commit {
+        tx.commit().await?;
+        report.committed = true;
+    } else {
+        tx.rollback().await?;
+        for accepted in report.accepted.iter_mut() {
+            accepted.course_id = None;
+        }
+    }
+    Ok(())
+}
+
+//moves a locked course to a new status, the caller checks the transition is allowed
+//published_at is stamped on publish and cleared when the course goes back to draft
+pub async fn set_course_status_db(
+    conn: &mut PgConnection,
+    teacher_id: i32,
+    id: i32,
+    status: CourseStatus,
+) -> Result<Course, MyError> {
+    let row = sqlx::query_as!(
+        Course,
+        r#"UPDATE course SET status = $3::varchar,
+        published_at = CASE
+            WHEN $3::varchar = 'published' THEN now()
+            WHEN $3::varchar = 'draft' THEN NULL
+            ELSE published_at
+        END
+        WHERE teacher_id = $1 and id = $2 AND deleted_at IS NULL
+        RETURNING id, teacher_id, name, 
+        created_at as "created_at!", updated_at as "updated_at!",
+        description, format as "format: CourseFormat", structure, duration as "duration: CourseDuration", 
+        price as "price: Price", language as "language: LanguageTag", level as "level: CourseLevel", capacity, review_count, average_rating,
+        status as "status: CourseStatus", published_at, publish_at, unpublish_at"#,
+        teacher_id,
+        id,
+        status.as_str(),
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    row.ok_or_else(|| MyError::NotFound("Course id not found".into()))
+}
+
+pub async fn set_course_schedule_db(
+    conn: &mut PgConnection,
+    teacher_id: i32,
+    id: i32,
+    schedule: CourseSchedule,
+) -> Result<Course, MyError> {
+    let row = sqlx::query_as!(
+        Course,
+        r#"UPDATE course SET publish_at = $3, unpublish_at = $4
+        WHERE teacher_id = $1 and id = $2 AND deleted_at IS NULL
+        RETURNING id, teacher_id, name, 
+        created_at as "created_at!", updated_at as "updated_at!",
+        description, format as "format: CourseFormat", structure, duration as "duration: CourseDuration", 
+        price as "price: Price", language as "language: LanguageTag", level as "level: CourseLevel", capacity, review_count, average_rating,
+        status as "status: CourseStatus", published_at, publish_at, unpublish_at"#,
+        teacher_id,
+        id,
+        schedule.publish_at,
+        schedule.unpublish_at,
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    row.ok_or_else(|| MyError::NotFound("Course id not found".into()))
+}
+
+//takes the scheduler lock until the end of the transaction,
+//false when another instance is running the scheduler right now
+pub async fn try_lock_scheduler_db(conn: &mut PgConnection) -> Result<bool, MyError> {
+    let locked = sqlx::query_scalar!(
+        r#"SELECT pg_try_advisory_xact_lock($1) as "locked!""#,
+        SCHEDULER_LOCK_KEY
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(locked)
+}
+
+//courses with a publish_at or unpublish_at that has passed, however long ago,
+//so transitions missed while no instance was running are caught up
+pub async fn due_scheduled_courses_db(conn: &mut PgConnection, now: DateTime<Utc>) -> Result<Vec<Course>, MyError> {
+    let rows = sqlx::query_as!(
+        Course,
+        r#"SELECT id, teacher_id, name, 
+           created_at as "created_at!", updated_at as "updated_at!",
+           description, format as "format: CourseFormat", structure, duration as "duration: CourseDuration", 
+           price as "price: Price", language as "language: LanguageTag", level as "level: CourseLevel", capacity, review_count, average_rating,
+           status as "status: CourseStatus", published_at, publish_at, unpublish_at
+        FROM course
+        WHERE deleted_at IS NULL AND (publish_at <= $1 OR unpublish_at <= $1)
+        ORDER BY LEAST(publish_at, unpublish_at)
+        FOR UPDATE"#,
+        now
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(rows)
+}
+
+//clears the parts of the schedule that are due, whether or not they could be applied
+pub async fn clear_due_schedule_db(conn: &mut PgConnection, id: i32, now: DateTime<Utc>) -> Result<Course, MyError> {
+    let row = sqlx::query_as!(
+        Course,
+        r#"UPDATE course SET
+        publish_at = CASE WHEN publish_at <= $2 THEN NULL ELSE publish_at END,
+        unpublish_at = CASE WHEN unpublish_at <= $2 THEN NULL ELSE unpublish_at END
+        WHERE id = $1
+        RETURNING id, teacher_id, name, 
+        created_at as "created_at!", updated_at as "updated_at!",
+        description, format as "format: CourseFormat", structure, duration as "duration: CourseDuration", 
+        price as "price: Price", language as "language: LanguageTag", level as "level: CourseLevel", capacity, review_count, average_rating,
+        status as "status: CourseStatus", published_at, publish_at, unpublish_at"#,
+        id,
+        now
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(row)
+}
+
+//moves the course to the teacher's trash, see restore_course_db and purge_trash_db
+pub async fn delete_course_db<'e, E: PgExecutor<'e>>(
+    executor: E,
     teacher_id: i32,
     id: i32
 ) -> Result<String, MyError> {
     let course_row = sqlx::query!(
-        "DELETE FROM course where teacher_id = $1 and id = $2",
+        "UPDATE course SET deleted_at = now() where teacher_id = $1 and id = $2 AND deleted_at IS NULL",
         teacher_id,
         id,
     )
-    .execute(pool)
+    .execute(executor)
     .await?;
     
     Ok(format!("Deleted {:?} record", course_row))
 }
 
+pub async fn get_trash_for_teacher_db(pool: &PgPool, teacher_id: i32) -> Result<Vec<TrashedCourse>, MyError> {
+    let rows = sqlx::query!(
+        r#"SELECT id, teacher_id, name, 
+           created_at as "created_at!", updated_at as "updated_at!",
+           description, format as "format: CourseFormat", structure, duration as "duration: CourseDuration", 
+           price as "price: Price", language as "language: LanguageTag", level as "level: CourseLevel", capacity, review_count, average_rating,
+           status as "status: CourseStatus", published_at, publish_at, unpublish_at,
+           deleted_at as "deleted_at!"
+           FROM course
+           WHERE teacher_id = $1 AND deleted_at IS NOT NULL
+           ORDER BY deleted_at DESC"#,
+        teacher_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| TrashedCourse {
+            course: Course {
+                teacher_id: row.teacher_id,
+                id: row.id,
+                name: row.name,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+                description: row.description,
+                format: row.format,
+                structure: row.structure,
+                duration: row.duration,
+                price: row.price,
+                language: row.language,
+                level: row.level,
+                capacity: row.capacity,
+                status: row.status,
+                published_at: row.published_at,
+                publish_at: row.publish_at,
+                unpublish_at: row.unpublish_at,
+                review_count: row.review_count,
+                average_rating: row.average_rating,
+            },
+            deleted_at: row.deleted_at,
+        })
+        .collect())
+}
+
+pub async fn restore_course_db<'e, E: PgExecutor<'e>>(executor: E, teacher_id: i32, id: i32) -> Result<Course, MyError> {
+    let row = sqlx::query_as!(
+        Course,
+        r#"UPDATE course SET deleted_at = NULL
+        WHERE teacher_id = $1 and id = $2 AND deleted_at IS NOT NULL
+        RETURNING id, teacher_id, name, 
+        created_at as "created_at!", updated_at as "updated_at!",
+        description, format as "format: CourseFormat", structure, duration as "duration: CourseDuration", 
+        price as "price: Price", language as "language: LanguageTag", level as "level: CourseLevel", capacity, review_count, average_rating,
+        status as "status: CourseStatus", published_at, publish_at, unpublish_at"#,
+        teacher_id,
+        id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    if let Some(course) = row {
+        Ok(course)
+    } else {
+        Err(MyError::NotFound("Course Id not found in trash".into()))
+    }
+}
+
+//hard deletes courses that have been in the trash longer than the retention period
+pub async fn purge_trash_db(pool: &PgPool, retention_days: i32) -> Result<u64, MyError> {
+    let result = sqlx::query!(
+        "DELETE FROM course WHERE deleted_at < now() - make_interval(days => $1)",
+        retention_days
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
 pub async fn update_course_details_db (
-    pool: &PgPool,
+    conn: &mut PgConnection,
     teacher_id: i32,
     id: i32,
     update_course: UpdateCourse,
@@ -87,17 +581,18 @@ pub async fn update_course_details_db (
     let current_course_row = sqlx::query_as!(
         Course,
         r#"SELECT id, teacher_id, name, 
-           time::timestamp as time,
-           description, format, structure, duration, 
-           price, language, level
+           created_at as "created_at!", updated_at as "updated_at!",
+           description, format as "format: CourseFormat", structure, duration as "duration: CourseDuration", 
+           price as "price: Price", language as "language: LanguageTag", level as "level: CourseLevel", capacity, review_count, average_rating,
+           status as "status: CourseStatus", published_at, publish_at, unpublish_at
         FROM course 
-        WHERE teacher_id = $1 and id = $2"#,
+        WHERE teacher_id = $1 and id = $2 AND deleted_at IS NULL"#,
         teacher_id,
         id
     )
-    .fetch_one(pool)
-    .await
-    .map_err(|_err| MyError::NotFound("Course id not found".into()))?;
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| MyError::NotFound("Course id not found".into()))?;
 
     let name: String = if let Some(name) = update_course.name {
         name
@@ -105,77 +600,66 @@ pub async fn update_course_details_db (
         current_course_row.name
     };
 
-    let description: String = if let Some(desc) = update_course.description {
-        desc
-    } else {
-        current_course_row.description.unwrap_or_default()
-    };
-
-    let format: String = if let Some(format) = update_course.format {
-        format
+    //an empty description or structure is stored as none, so a rollback can restore a missing one
+    let description: Option<String> = if let Some(desc) = update_course.description {
+        Some(desc)
     } else {
-        current_course_row.format.unwrap_or_default()
+        current_course_row.description
     };
+    let description = description.filter(|desc| !desc.is_empty());
 
-    let structure: String = if let Some(structure) = update_course.structure {
-        structure
-    } else {
-        current_course_row.structure.unwrap_or_default()
-    };
+    let format = update_course.format.or(current_course_row.format);
 
-    let duration: String = if let Some(duration) = update_course.duration {
-        duration
+    let structure: Option<String> = if let Some(structure) = update_course.structure {
+        Some(structure)
     } else {
-        current_course_row.duration.unwrap_or_default()
+        current_course_row.structure
     };
+    let structure = structure.filter(|structure| !structure.is_empty());
 
-    let level: String = if let Some(level) = update_course.level {
-        level
-    } else {
-        current_course_row.level.unwrap_or_default()
-    };
+    let duration = update_course.duration.or(current_course_row.duration);
+    let (duration_minutes, duration_weeks, duration_sessions) = CourseDuration::columns(duration.as_ref());
 
-    let language: String = if let Some(language) = update_course.language {
-        language
-    } else {
-        current_course_row.language.unwrap_or_default()
-    };
+    //missing level, format, language and duration keep the current value, they have no empty value
+    let level = update_course.level.or(current_course_row.level);
+    let language = update_course.language.or(current_course_row.language);
+    //a missing capacity keeps the current one, null removes it
+    let capacity = update_course.capacity.unwrap_or(current_course_row.capacity);
 
-    let price = if let Some(price) = update_course.price {
-        price
-    } else {
-        current_course_row.price.unwrap_or_default()
-    };
+    //a missing price keeps the current one, free has to be sent as "free"
+    let price = update_course.price.or(current_course_row.price);
+    let (price_minor, currency) = Price::columns(price.as_ref());
 
     let course_row = sqlx::query_as!(
         Course,
         r#"UPDATE course 
         SET name = $1, description = $2, format = $3,
-        structure = $4, duration = $5, price = $6, language = $7,
-        level = $8 
-        WHERE teacher_id = $9 and id = $10
+        structure = $4, duration_minutes = $5, duration_weeks = $6, duration_sessions = $7,
+        price_minor = $8, currency = $9, language = $10, level = $11, capacity = $12
+        WHERE teacher_id = $13 and id = $14 AND deleted_at IS NULL
         RETURNING id, teacher_id, name, 
-        time::timestamp as time,
-        description, format, structure, duration, 
-        price, language, level"#,
+        created_at as "created_at!", updated_at as "updated_at!",
+        description, format as "format: CourseFormat", structure, duration as "duration: CourseDuration", 
+        price as "price: Price", language as "language: LanguageTag", level as "level: CourseLevel", capacity, review_count, average_rating,
+        status as "status: CourseStatus", published_at, publish_at, unpublish_at"#,
         name,
         description,
-        format,
+        format.map(|format| format.as_str()),
         structure,
-        duration,
-        price,
-        language,
-        level,
+        duration_minutes,
+        duration_weeks,
+        duration_sessions,
+        price_minor,
+        currency,
+        language.as_ref().map(LanguageTag::as_str),
+        level.map(|level| level.as_str()),
+        capacity,
         teacher_id,
         id
     )
-    .fetch_one(pool)
-    .await;
-
-    if let Ok(course) = course_row {
-        Ok(course)
-    } else {
-        Err(MyError::NotFound("Course id not found".into()))
-    }
+    .fetch_optional(&mut *conn)
+    .await?;
 
+    //only a missing course is a 404, a rejected value or a lost connection goes through From<sqlx::Error>
+    course_row.ok_or_else(|| MyError::NotFound("Course id not found".into()))
 }
\ No newline at end of file
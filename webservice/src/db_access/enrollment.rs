@@ -0,0 +1,218 @@
+use crate::models::course::Course;
+use crate::models::enrollment::{Enrollment, EnrollmentStatus, RosterEntry, StudentEnrollment};
+use crate::models::notification::NotificationKind;
+use crate::db_access::notification::insert_notification_db;
+use crate::errors::MyError;
+use sqlx::postgres::{PgConnection, PgExecutor, PgPool};
+
+//the student's enrollment in the course, None when they never enrolled
+//waitlist_position counts the waitlisted rows up to and including this one, in (waitlisted_at, id) order
+pub async fn get_enrollment_db<'e, E: PgExecutor<'e>>(
+    executor: E,
+    course_id: i32,
+    student_id: i32,
+) -> Result<Option<Enrollment>, MyError> {
+    let row = sqlx::query_as!(
+        Enrollment,
+        r#"SELECT e.id, e.course_id, e.student_id, e.status as "status: EnrollmentStatus",
+        CASE WHEN e.status = 'waitlisted' THEN
+            (SELECT count(*) FROM enrollment w
+            WHERE w.course_id = e.course_id AND w.status = 'waitlisted'
+            AND (w.waitlisted_at, w.id) <= (e.waitlisted_at, e.id))
+        END as waitlist_position,
+        e.enrolled_at, e.waitlisted_at, e.completed_at, e.withdrawn_at, e.updated_at
+        FROM enrollment e
+        WHERE e.course_id = $1 AND e.student_id = $2"#,
+        course_id,
+        student_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(row)
+}
+
+//the enrollment locked until commit, so two progress updates of one student are applied one after the other
+pub async fn lock_enrollment_db(conn: &mut PgConnection, course_id: i32, student_id: i32) -> Result<Option<Enrollment>, MyError> {
+    sqlx::query!(
+        "SELECT id FROM enrollment WHERE course_id = $1 AND student_id = $2 FOR UPDATE",
+        course_id,
+        student_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    get_enrollment_db(conn, course_id, student_id).await
+}
+
+//(seated, waitlisted) enrollments of a course, students who completed it keep their seat
+pub async fn count_enrollments_db(conn: &mut PgConnection, course_id: i32) -> Result<(i64, i64), MyError> {
+    let row = sqlx::query!(
+        r#"SELECT count(*) FILTER (WHERE status IN ('active', 'completed')) as "active!",
+        count(*) FILTER (WHERE status = 'waitlisted') as "waitlisted!"
+        FROM enrollment WHERE course_id = $1"#,
+        course_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok((row.active, row.waitlisted))
+}
+
+//enrolls the student as active or waitlisted, or enrolls them again after they withdrew
+//the caller holds the course lock and has checked the student is not enrolled yet
+//clock_timestamp() rather than now(), so the waitlist follows the order the locks were taken in
+pub async fn enroll_db(
+    conn: &mut PgConnection,
+    course_id: i32,
+    student_id: i32,
+    status: EnrollmentStatus,
+) -> Result<Enrollment, MyError> {
+    sqlx::query!(
+        r#"INSERT INTO enrollment (course_id, student_id, status, enrolled_at, waitlisted_at)
+        VALUES ($1, $2, $3::varchar,
+            CASE WHEN $3::varchar = 'active' THEN clock_timestamp() END,
+            CASE WHEN $3::varchar = 'waitlisted' THEN clock_timestamp() END)
+        ON CONFLICT (course_id, student_id) DO UPDATE
+        SET status = EXCLUDED.status, enrolled_at = EXCLUDED.enrolled_at,
+        waitlisted_at = EXCLUDED.waitlisted_at, withdrawn_at = NULL
+        WHERE enrollment.status = 'withdrawn'"#,
+        course_id,
+        student_id,
+        status.as_str()
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    get_enrollment_db(&mut *conn, course_id, student_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Enrollment not found".into()))
+}
+
+//withdraws an active or waitlisted student, their place is not kept
+pub async fn withdraw_db(conn: &mut PgConnection, course_id: i32, student_id: i32) -> Result<Enrollment, MyError> {
+    let row = sqlx::query_as!(
+        Enrollment,
+        r#"UPDATE enrollment SET status = 'withdrawn', withdrawn_at = now()
+        WHERE course_id = $1 AND student_id = $2 AND status IN ('active', 'waitlisted')
+        RETURNING id, course_id, student_id, status as "status: EnrollmentStatus",
+        NULL::bigint as "waitlist_position?",
+        enrolled_at, waitlisted_at, completed_at, withdrawn_at, updated_at"#,
+        course_id,
+        student_id
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    row.ok_or_else(|| MyError::Conflict("Only active or waitlisted students can withdraw".into()))
+}
+
+/**
+Moves waitlisted students into the seats that are free, first come first served,
+and writes a waitlist_promoted event for each of them
+the caller holds the course lock, so no enrollment changes in between
+a course without capacity takes the whole waitlist
+**/
+pub async fn promote_waitlisted_db(conn: &mut PgConnection, course: &Course) -> Result<Vec<Enrollment>, MyError> {
+    let (active, _) = count_enrollments_db(conn, course.id).await?;
+    //LIMIT NULL is no limit
+    let free_seats = course.capacity.map(|capacity| (capacity as i64 - active).max(0));
+    if free_seats == Some(0) {
+        return Ok(vec![]);
+    }
+
+    let mut promoted = sqlx::query_as!(
+        Enrollment,
+        r#"WITH next AS (
+            SELECT id FROM enrollment
+            WHERE course_id = $1 AND status = 'waitlisted'
+            ORDER BY waitlisted_at, id
+            LIMIT $2
+        )
+        UPDATE enrollment SET status = 'active', enrolled_at = clock_timestamp()
+        FROM next WHERE enrollment.id = next.id
+        RETURNING enrollment.id, enrollment.course_id, enrollment.student_id,
+        enrollment.status as "status: EnrollmentStatus",
+        NULL::bigint as "waitlist_position?",
+        enrollment.enrolled_at, enrollment.waitlisted_at, enrollment.completed_at,
+        enrollment.withdrawn_at, enrollment.updated_at"#,
+        course.id,
+        free_seats
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    promoted.sort_by_key(|enrollment| (enrollment.waitlisted_at, enrollment.id));
+
+    for enrollment in &promoted {
+        let payload = serde_json::json!({
+            "teacher_id": course.teacher_id,
+            "course_name": course.name,
+            "enrolled_at": enrollment.enrolled_at,
+        });
+        insert_notification_db(
+            &mut *conn,
+            enrollment.student_id,
+            Some(course.id),
+            NotificationKind::WaitlistPromoted,
+            payload,
+        )
+        .await?;
+        log::info!("Student {} promoted from the waitlist of course {}", enrollment.student_id, course.id);
+    }
+
+    Ok(promoted)
+}
+
+//students of a course: seated ones in the order they enrolled, then the waitlist in order, then those who withdrew
+pub async fn get_roster_db(pool: &PgPool, course_id: i32, status: Option<EnrollmentStatus>) -> Result<Vec<RosterEntry>, MyError> {
+    let rows = sqlx::query_as!(
+        RosterEntry,
+        r#"SELECT student.id as student_id, student.name, student.email,
+        e.status as "status: EnrollmentStatus",
+        CASE WHEN e.status = 'waitlisted' THEN
+            (SELECT count(*) FROM enrollment w
+            WHERE w.course_id = e.course_id AND w.status = 'waitlisted'
+            AND (w.waitlisted_at, w.id) <= (e.waitlisted_at, e.id))
+        END as waitlist_position,
+        e.enrolled_at, e.waitlisted_at, e.completed_at, e.withdrawn_at, e.updated_at
+        FROM enrollment e JOIN student ON student.id = e.student_id
+        WHERE e.course_id = $1
+        AND ($2::varchar IS NULL OR e.status = $2)
+        ORDER BY CASE e.status WHEN 'active' THEN 0 WHEN 'completed' THEN 0 WHEN 'waitlisted' THEN 1 ELSE 2 END,
+        e.enrolled_at, e.waitlisted_at, e.id"#,
+        course_id,
+        status.map(|status| status.as_str())
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+//courses a student enrolled in, latest first, courses in the trash are left out
+pub async fn get_student_enrollments_db(
+    pool: &PgPool,
+    student_id: i32,
+    status: Option<EnrollmentStatus>,
+) -> Result<Vec<StudentEnrollment>, MyError> {
+    let rows = sqlx::query_as!(
+        StudentEnrollment,
+        r#"SELECT course.id as course_id, course.teacher_id, course.name as course_name,
+        e.status as "status: EnrollmentStatus",
+        CASE WHEN e.status = 'waitlisted' THEN
+            (SELECT count(*) FROM enrollment w
+            WHERE w.course_id = e.course_id AND w.status = 'waitlisted'
+            AND (w.waitlisted_at, w.id) <= (e.waitlisted_at, e.id))
+        END as waitlist_position,
+        e.enrolled_at, e.waitlisted_at, e.completed_at, e.withdrawn_at, e.updated_at
+        FROM enrollment e JOIN course ON course.id = e.course_id
+        WHERE e.student_id = $1 AND course.deleted_at IS NULL
+        AND ($2::varchar IS NULL OR e.status = $2)
+        ORDER BY e.updated_at DESC, course.id"#,
+        student_id,
+        status.map(|status| status.as_str())
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
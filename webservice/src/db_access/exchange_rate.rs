@@ -0,0 +1,48 @@
+use crate::models::exchange_rate::{ExchangeRate, NewExchangeRate};
+use crate::models::money::Rate;
+use crate::errors::MyError;
+use sqlx::postgres::PgPool;
+
+
+pub async fn get_exchange_rates_db(pool: &PgPool) -> Result<Vec<ExchangeRate>, MyError> {
+    let rows = sqlx::query_as!(
+        ExchangeRate,
+        r#"SELECT base, quote, rate::TEXT as "rate!: Rate", as_of FROM exchange_rate ORDER BY base, quote"#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+//replaces the whole rate table in one transaction, readers see the old or the new one
+pub async fn replace_exchange_rates_db(
+    pool: &PgPool,
+    rates: Vec<NewExchangeRate>,
+) -> Result<Vec<ExchangeRate>, MyError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!("DELETE FROM exchange_rate").execute(&mut tx).await?;
+    for rate in rates {
+        //a pair listed twice keeps its last rate
+        sqlx::query!(
+            r#"INSERT INTO exchange_rate (base, quote, rate, as_of)
+            VALUES ($1, $2, CAST($3::TEXT AS NUMERIC), COALESCE($4, now()))
+            ON CONFLICT (base, quote) DO UPDATE SET rate = EXCLUDED.rate, as_of = EXCLUDED.as_of"#,
+            rate.base.code(),
+            rate.quote.code(),
+            rate.rate.to_string(),
+            rate.as_of,
+        )
+        .execute(&mut tx)
+        .await?;
+    }
+    let rows = sqlx::query_as!(
+        ExchangeRate,
+        r#"SELECT base, quote, rate::TEXT as "rate!: Rate", as_of FROM exchange_rate ORDER BY base, quote"#
+    )
+    .fetch_all(&mut tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(rows)
+}
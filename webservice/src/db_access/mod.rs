@@ -1 +1,15 @@
+pub mod audit;
+pub mod certificate;
 pub mod course;
+pub mod enrollment;
+pub mod exchange_rate;
+pub mod notification;
+pub mod outline;
+pub mod prerequisite;
+pub mod progress;
+pub mod quiz;
+pub mod review;
+pub mod revision;
+pub mod student;
+pub mod taxonomy;
+pub mod translation;
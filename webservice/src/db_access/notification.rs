@@ -0,0 +1,44 @@
+use crate::models::notification::{NotificationEvent, NotificationKind};
+use crate::errors::MyError;
+use sqlx::postgres::{PgExecutor, PgPool};
+
+//takes the caller's transaction, so the event commits with the change it reports
+pub async fn insert_notification_db<'e, E: PgExecutor<'e>>(
+    executor: E,
+    student_id: i32,
+    course_id: Option<i32>,
+    kind: NotificationKind,
+    payload: serde_json::Value,
+) -> Result<NotificationEvent, MyError> {
+    let row = sqlx::query_as!(
+        NotificationEvent,
+        r#"INSERT INTO notification_event (student_id, course_id, kind, payload)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, student_id, course_id, kind as "kind: NotificationKind", payload, created_at"#,
+        student_id,
+        course_id,
+        kind.as_str(),
+        payload
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(row)
+}
+
+//a student's events, oldest first
+pub async fn get_notifications_db(pool: &PgPool, student_id: i32, after: Option<i64>) -> Result<Vec<NotificationEvent>, MyError> {
+    let rows = sqlx::query_as!(
+        NotificationEvent,
+        r#"SELECT id, student_id, course_id, kind as "kind: NotificationKind", payload, created_at
+        FROM notification_event
+        WHERE student_id = $1 AND ($2::bigint IS NULL OR id > $2)
+        ORDER BY id"#,
+        student_id,
+        after
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
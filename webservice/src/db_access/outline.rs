@@ -0,0 +1,282 @@
+use crate::models::outline::{CreateLesson, CreateModule, Lesson, Module, ModuleOutline, UpdateLesson, UpdateModule};
+use crate::errors::MyError;
+use sqlx::postgres::{PgConnection, PgExecutor};
+
+
+//modules and lessons of a course in order
+pub async fn get_outline_db(conn: &mut PgConnection, course_id: i32) -> Result<Vec<ModuleOutline>, MyError> {
+    let modules = sqlx::query_as!(
+        Module,
+        r#"SELECT id, course_id, position, title, description, created_at, updated_at
+        FROM module WHERE course_id = $1 ORDER BY position"#,
+        course_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let lessons = sqlx::query_as!(
+        Lesson,
+        r#"SELECT lesson.id, lesson.module_id, lesson.position, lesson.title, lesson.content,
+        lesson.duration_minutes, lesson.required, lesson.created_at, lesson.updated_at
+        FROM lesson JOIN module ON module.id = lesson.module_id
+        WHERE module.course_id = $1
+        ORDER BY module.position, lesson.position"#,
+        course_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(ModuleOutline::build(modules, lessons))
+}
+
+//one module of the outline with its lessons
+pub async fn get_module_outline_db(conn: &mut PgConnection, course_id: i32, module_id: i32) -> Result<ModuleOutline, MyError> {
+    get_outline_db(conn, course_id)
+        .await?
+        .into_iter()
+        .find(|entry| entry.module.id == module_id)
+        .ok_or_else(|| MyError::NotFound("Module id not found".into()))
+}
+
+//outline edits change what the course detail shows, so they move course.updated_at
+pub async fn touch_course_db<'e, E: PgExecutor<'e>>(executor: E, course_id: i32) -> Result<(), MyError> {
+    sqlx::query!("UPDATE course SET updated_at = now() WHERE id = $1", course_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_module_db<'e, E: PgExecutor<'e>>(executor: E, course_id: i32, module_id: i32) -> Result<Module, MyError> {
+    let row = sqlx::query_as!(
+        Module,
+        r#"SELECT id, course_id, position, title, description, created_at, updated_at
+        FROM module WHERE course_id = $1 AND id = $2"#,
+        course_id,
+        module_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    row.ok_or_else(|| MyError::NotFound("Module id not found".into()))
+}
+
+//inserts at the requested position, moving later modules down, or at the end
+pub async fn insert_module_db(conn: &mut PgConnection, course_id: i32, new_module: CreateModule) -> Result<Module, MyError> {
+    let count = sqlx::query_scalar!(
+        r#"SELECT count(*) as "count!" FROM module WHERE course_id = $1"#,
+        course_id
+    )
+    .fetch_one(&mut *conn)
+    .await? as i32;
+    let position = new_module.position.unwrap_or(count + 1).min(count + 1);
+
+    sqlx::query!(
+        "UPDATE module SET position = position + 1 WHERE course_id = $1 AND position >= $2",
+        course_id,
+        position
+    )
+    .execute(&mut *conn)
+    .await?;
+    let row = sqlx::query_as!(
+        Module,
+        r#"INSERT INTO module (course_id, position, title, description)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, course_id, position, title, description, created_at, updated_at"#,
+        course_id,
+        position,
+        new_module.title,
+        new_module.description
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(row)
+}
+
+pub async fn update_module_db(conn: &mut PgConnection, course_id: i32, module_id: i32, update: UpdateModule) -> Result<Module, MyError> {
+    let current = get_module_db(&mut *conn, course_id, module_id).await?;
+    let row = sqlx::query_as!(
+        Module,
+        r#"UPDATE module SET title = $3, description = $4
+        WHERE course_id = $1 AND id = $2
+        RETURNING id, course_id, position, title, description, created_at, updated_at"#,
+        course_id,
+        module_id,
+        update.title.unwrap_or(current.title),
+        update.description.unwrap_or(current.description)
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(row)
+}
+
+//deletes a module with its lessons and closes the gap it leaves
+pub async fn delete_module_db(conn: &mut PgConnection, course_id: i32, module_id: i32) -> Result<(), MyError> {
+    let module = get_module_db(&mut *conn, course_id, module_id).await?;
+    sqlx::query!("DELETE FROM module WHERE id = $1", module_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!(
+        "UPDATE module SET position = position - 1 WHERE course_id = $1 AND position > $2",
+        course_id,
+        module.position
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_module_ids_db(conn: &mut PgConnection, course_id: i32) -> Result<Vec<i32>, MyError> {
+    let ids = sqlx::query_scalar!("SELECT id FROM module WHERE course_id = $1 ORDER BY position", course_id)
+        .fetch_all(conn)
+        .await?;
+    Ok(ids)
+}
+
+//gives every module the position of its id in ids, in one statement
+//so the unique (course_id, position) constraint only sees the final order
+pub async fn reorder_modules_db(conn: &mut PgConnection, course_id: i32, ids: &[i32]) -> Result<(), MyError> {
+    sqlx::query!(
+        r#"UPDATE module SET position = reordered.position
+        FROM unnest($2::int[]) WITH ORDINALITY AS reordered (id, position)
+        WHERE module.course_id = $1 AND module.id = reordered.id
+        AND module.position <> reordered.position"#,
+        course_id,
+        ids
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_lesson_db<'e, E: PgExecutor<'e>>(executor: E, module_id: i32, lesson_id: i32) -> Result<Lesson, MyError> {
+    let row = sqlx::query_as!(
+        Lesson,
+        r#"SELECT id, module_id, position, title, content, duration_minutes, required, created_at, updated_at
+        FROM lesson WHERE module_id = $1 AND id = $2"#,
+        module_id,
+        lesson_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    row.ok_or_else(|| MyError::NotFound("Lesson id not found".into()))
+}
+
+//inserts at the requested position, moving later lessons down, or at the end
+pub async fn insert_lesson_db(conn: &mut PgConnection, module_id: i32, new_lesson: CreateLesson) -> Result<Lesson, MyError> {
+    let count = sqlx::query_scalar!(
+        r#"SELECT count(*) as "count!" FROM lesson WHERE module_id = $1"#,
+        module_id
+    )
+    .fetch_one(&mut *conn)
+    .await? as i32;
+    let position = new_lesson.position.unwrap_or(count + 1).min(count + 1);
+
+    sqlx::query!(
+        "UPDATE lesson SET position = position + 1 WHERE module_id = $1 AND position >= $2",
+        module_id,
+        position
+    )
+    .execute(&mut *conn)
+    .await?;
+    let row = sqlx::query_as!(
+        Lesson,
+        r#"INSERT INTO lesson (module_id, position, title, content, duration_minutes, required)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, module_id, position, title, content, duration_minutes, required, created_at, updated_at"#,
+        module_id,
+        position,
+        new_lesson.title,
+        new_lesson.content,
+        new_lesson.duration_minutes,
+        new_lesson.required.unwrap_or(true)
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(row)
+}
+
+pub async fn update_lesson_db(conn: &mut PgConnection, module_id: i32, lesson_id: i32, update: UpdateLesson) -> Result<Lesson, MyError> {
+    let current = get_lesson_db(&mut *conn, module_id, lesson_id).await?;
+    let row = sqlx::query_as!(
+        Lesson,
+        r#"UPDATE lesson SET title = $3, content = $4, duration_minutes = $5, required = $6
+        WHERE module_id = $1 AND id = $2
+        RETURNING id, module_id, position, title, content, duration_minutes, required, created_at, updated_at"#,
+        module_id,
+        lesson_id,
+        update.title.unwrap_or(current.title),
+        update.content.unwrap_or(current.content),
+        update.duration_minutes.unwrap_or(current.duration_minutes),
+        update.required.unwrap_or(current.required)
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(row)
+}
+
+pub async fn delete_lesson_db(conn: &mut PgConnection, module_id: i32, lesson_id: i32) -> Result<(), MyError> {
+    let lesson = get_lesson_db(&mut *conn, module_id, lesson_id).await?;
+    sqlx::query!("DELETE FROM lesson WHERE id = $1", lesson_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!(
+        "UPDATE lesson SET position = position - 1 WHERE module_id = $1 AND position > $2",
+        module_id,
+        lesson.position
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_lesson_ids_db(conn: &mut PgConnection, module_id: i32) -> Result<Vec<i32>, MyError> {
+    let ids = sqlx::query_scalar!("SELECT id FROM lesson WHERE module_id = $1 ORDER BY position", module_id)
+        .fetch_all(conn)
+        .await?;
+    Ok(ids)
+}
+
+pub async fn reorder_lessons_db(conn: &mut PgConnection, module_id: i32, ids: &[i32]) -> Result<(), MyError> {
+    sqlx::query!(
+        r#"UPDATE lesson SET position = reordered.position
+        FROM unnest($2::int[]) WITH ORDINALITY AS reordered (id, position)
+        WHERE lesson.module_id = $1 AND lesson.id = reordered.id
+        AND lesson.position <> reordered.position"#,
+        module_id,
+        ids
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+//copies the modules and lessons of one course into another that has none yet, positions are kept
+pub async fn clone_outline_db(conn: &mut PgConnection, from_course_id: i32, to_course_id: i32) -> Result<(), MyError> {
+    sqlx::query!(
+        "INSERT INTO module (course_id, position, title, description)
+        SELECT $2, position, title, description FROM module WHERE course_id = $1",
+        from_course_id,
+        to_course_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "INSERT INTO lesson (module_id, position, title, content, duration_minutes, required)
+        SELECT copy.id, lesson.position, lesson.title, lesson.content, lesson.duration_minutes, lesson.required
+        FROM lesson
+        JOIN module ON module.id = lesson.module_id
+        JOIN module copy ON copy.course_id = $2 AND copy.position = module.position
+        WHERE module.course_id = $1",
+        from_course_id,
+        to_course_id
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use crate::models::course::CourseStatus;
+use crate::models::prerequisite::{PrerequisiteCourse, PrerequisiteOverride};
+use crate::errors::MyError;
+use sqlx::postgres::{PgConnection, PgExecutor};
+
+/**
+Changes to the prerequisite graph lock the table until commit
+two teachers making each other's course a prerequisite at the same time would each
+find no cycle in the graph they read, one after the other the second one sees it
+**/
+pub async fn lock_prerequisites_db(conn: &mut PgConnection) -> Result<(), MyError> {
+    sqlx::query!("LOCK TABLE course_prerequisite IN SHARE ROW EXCLUSIVE MODE")
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+//(course_id, prerequisite_id) of every edge reachable from these courses, deleted courses included
+pub async fn get_prerequisite_edges_db<'e, E: PgExecutor<'e>>(executor: E, course_ids: &[i32]) -> Result<Vec<(i32, i32)>, MyError> {
+    let rows = sqlx::query!(
+        r#"WITH RECURSIVE reachable AS (
+            SELECT course_id, prerequisite_id FROM course_prerequisite WHERE course_id = ANY($1)
+            UNION
+            SELECT course_prerequisite.course_id, course_prerequisite.prerequisite_id
+            FROM course_prerequisite JOIN reachable ON course_prerequisite.course_id = reachable.prerequisite_id
+        )
+        SELECT course_id as "course_id!", prerequisite_id as "prerequisite_id!" FROM reachable"#,
+        course_ids
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| (row.course_id, row.prerequisite_id)).collect())
+}
+
+//the live courses among these ids, by id
+pub async fn get_prerequisite_courses_db<'e, E: PgExecutor<'e>>(executor: E, course_ids: &[i32]) -> Result<Vec<PrerequisiteCourse>, MyError> {
+    let rows = sqlx::query_as!(
+        PrerequisiteCourse,
+        r#"SELECT id, teacher_id, name, status as "status: CourseStatus"
+        FROM course WHERE id = ANY($1) AND deleted_at IS NULL ORDER BY id"#,
+        course_ids
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows)
+}
+
+//the live courses course_id directly requires
+pub async fn get_prerequisites_db<'e, E: PgExecutor<'e>>(executor: E, course_id: i32) -> Result<Vec<PrerequisiteCourse>, MyError> {
+    let rows = sqlx::query_as!(
+        PrerequisiteCourse,
+        r#"SELECT course.id, course.teacher_id, course.name, course.status as "status: CourseStatus"
+        FROM course_prerequisite JOIN course ON course.id = course_prerequisite.prerequisite_id
+        WHERE course_prerequisite.course_id = $1 AND course.deleted_at IS NULL
+        ORDER BY course.id"#,
+        course_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows)
+}
+
+//the caller holds the prerequisite lock and checked the new graph for cycles
+pub async fn set_prerequisites_db(conn: &mut PgConnection, course_id: i32, prerequisite_ids: &[i32]) -> Result<Vec<PrerequisiteCourse>, MyError> {
+    sqlx::query!(
+        "DELETE FROM course_prerequisite WHERE course_id = $1 AND prerequisite_id <> ALL($2)",
+        course_id,
+        prerequisite_ids
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "INSERT INTO course_prerequisite (course_id, prerequisite_id) SELECT $1, unnest($2::int[])
+        ON CONFLICT DO NOTHING",
+        course_id,
+        prerequisite_ids
+    )
+    .execute(&mut *conn)
+    .await?;
+    get_prerequisites_db(conn, course_id).await
+}
+
+//the direct prerequisites the student has not completed
+//deleted and unpublished courses are no longer required, nobody could enroll in them to complete them
+pub async fn get_missing_prerequisites_db<'e, E: PgExecutor<'e>>(
+    executor: E,
+    course_id: i32,
+    student_id: i32,
+) -> Result<Vec<PrerequisiteCourse>, MyError> {
+    let rows = sqlx::query_as!(
+        PrerequisiteCourse,
+        r#"SELECT course.id, course.teacher_id, course.name, course.status as "status: CourseStatus"
+        FROM course_prerequisite JOIN course ON course.id = course_prerequisite.prerequisite_id
+        WHERE course_prerequisite.course_id = $1 AND course.deleted_at IS NULL AND course.status = 'published'
+        AND NOT EXISTS (
+            SELECT 1 FROM enrollment
+            WHERE enrollment.course_id = course.id AND enrollment.student_id = $2 AND enrollment.status = 'completed'
+        )
+        ORDER BY course.id"#,
+        course_id,
+        student_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn get_overrides_db<'e, E: PgExecutor<'e>>(executor: E, course_id: i32) -> Result<Vec<PrerequisiteOverride>, MyError> {
+    let rows = sqlx::query_as!(
+        PrerequisiteOverride,
+        r#"SELECT prerequisite_override.course_id, prerequisite_override.student_id, student.name as student_name,
+        prerequisite_override.granted_at
+        FROM prerequisite_override JOIN student ON student.id = prerequisite_override.student_id
+        WHERE prerequisite_override.course_id = $1
+        ORDER BY prerequisite_override.granted_at, prerequisite_override.student_id"#,
+        course_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn has_override_db<'e, E: PgExecutor<'e>>(executor: E, course_id: i32, student_id: i32) -> Result<bool, MyError> {
+    let granted = sqlx::query!(
+        r#"SELECT EXISTS (SELECT 1 FROM prerequisite_override WHERE course_id = $1 AND student_id = $2) as "granted!""#,
+        course_id,
+        student_id
+    )
+    .fetch_one(executor)
+    .await?
+    .granted;
+
+    Ok(granted)
+}
+
+//granting twice keeps the first grant
+pub async fn grant_override_db(conn: &mut PgConnection, course_id: i32, student_id: i32) -> Result<Vec<PrerequisiteOverride>, MyError> {
+    sqlx::query!(
+        "INSERT INTO prerequisite_override (course_id, student_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        course_id,
+        student_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    get_overrides_db(conn, course_id).await
+}
+
+//a student who already enrolled keeps the seat
+pub async fn revoke_override_db(conn: &mut PgConnection, course_id: i32, student_id: i32) -> Result<Vec<PrerequisiteOverride>, MyError> {
+    let result = sqlx::query!(
+        "DELETE FROM prerequisite_override WHERE course_id = $1 AND student_id = $2",
+        course_id,
+        student_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(MyError::NotFound("Override not found".into()));
+    }
+    get_overrides_db(conn, course_id).await
+}
+
+/**
+Gives a new course the prerequisites of another, as far as its teacher could declare them:
+live courses of the same teacher, or published ones of another
+nothing requires a course that was just created, so no cycle can come of it
+**/
+pub async fn clone_prerequisites_db(conn: &mut PgConnection, from_course_id: i32, to_course_id: i32) -> Result<(), MyError> {
+    sqlx::query!(
+        "INSERT INTO course_prerequisite (course_id, prerequisite_id)
+        SELECT copy.id, course_prerequisite.prerequisite_id
+        FROM course_prerequisite
+        JOIN course prerequisite ON prerequisite.id = course_prerequisite.prerequisite_id
+        JOIN course copy ON copy.id = $2
+        WHERE course_prerequisite.course_id = $1 AND prerequisite.deleted_at IS NULL
+        AND (prerequisite.teacher_id = copy.teacher_id OR prerequisite.status = 'published')",
+        from_course_id,
+        to_course_id
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
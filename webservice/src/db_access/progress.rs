@@ -0,0 +1,168 @@
+use crate::models::enrollment::EnrollmentStatus;
+use crate::models::outline::Lesson;
+use crate::models::progress::{LessonProgress, ProgressCounts, RosterProgress};
+use crate::errors::MyError;
+use sqlx::postgres::{PgConnection, PgExecutor, PgPool};
+
+//a lesson of any module of the course
+pub async fn get_course_lesson_db<'e, E: PgExecutor<'e>>(executor: E, course_id: i32, lesson_id: i32) -> Result<Lesson, MyError> {
+    let row = sqlx::query_as!(
+        Lesson,
+        r#"SELECT lesson.id, lesson.module_id, lesson.position, lesson.title, lesson.content,
+        lesson.duration_minutes, lesson.required, lesson.created_at, lesson.updated_at
+        FROM lesson JOIN module ON module.id = lesson.module_id
+        WHERE module.course_id = $1 AND lesson.id = $2"#,
+        course_id,
+        lesson_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    row.ok_or_else(|| MyError::NotFound("Lesson id not found".into()))
+}
+
+//starting again only moves the last activity
+pub async fn start_lesson_db(conn: &mut PgConnection, enrollment_id: i32, lesson_id: i32) -> Result<(), MyError> {
+    sqlx::query!(
+        r#"INSERT INTO lesson_progress (enrollment_id, lesson_id) VALUES ($1, $2)
+        ON CONFLICT (enrollment_id, lesson_id) DO UPDATE SET updated_at = now()"#,
+        enrollment_id,
+        lesson_id
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+//completing a lesson that was never started starts it too, the first completion time is kept
+pub async fn complete_lesson_db(conn: &mut PgConnection, enrollment_id: i32, lesson_id: i32) -> Result<(), MyError> {
+    sqlx::query!(
+        r#"INSERT INTO lesson_progress (enrollment_id, lesson_id, completed_at) VALUES ($1, $2, now())
+        ON CONFLICT (enrollment_id, lesson_id) DO UPDATE
+        SET completed_at = COALESCE(lesson_progress.completed_at, EXCLUDED.completed_at), updated_at = now()"#,
+        enrollment_id,
+        lesson_id
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+//every lesson of the course in outline order, with the enrollment's progress in it
+pub async fn get_lesson_progress_db<'e, E: PgExecutor<'e>>(
+    executor: E,
+    course_id: i32,
+    enrollment_id: i32,
+) -> Result<Vec<LessonProgress>, MyError> {
+    let rows = sqlx::query_as!(
+        LessonProgress,
+        r#"SELECT lesson.id as lesson_id, lesson.module_id, lesson.title, lesson.required,
+        progress.started_at as "started_at?", progress.completed_at, progress.updated_at as "updated_at?"
+        FROM lesson JOIN module ON module.id = lesson.module_id
+        LEFT JOIN lesson_progress progress
+            ON progress.lesson_id = lesson.id AND progress.enrollment_id = $2
+        WHERE module.course_id = $1
+        ORDER BY module.position, lesson.position"#,
+        course_id,
+        enrollment_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows)
+}
+
+//flips an active enrollment to completed, returns false when it was not active
+pub async fn complete_enrollment_db(conn: &mut PgConnection, enrollment_id: i32) -> Result<bool, MyError> {
+    let result = sqlx::query!(
+        "UPDATE enrollment SET status = 'completed', completed_at = now() WHERE id = $1 AND status = 'active'",
+        enrollment_id
+    )
+    .execute(conn)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/**
+Flips every active enrollment of the course that now meets the completion rule of ProgressCounts::is_complete
+for outline edits that make lessons optional or remove them, returns the ids of the flipped enrollments
+the updated rows stay locked until the caller commits
+**/
+pub async fn complete_finished_enrollments_db(conn: &mut PgConnection, course_id: i32) -> Result<Vec<i32>, MyError> {
+    let rows = sqlx::query!(
+        r#"WITH course_lesson AS (
+            SELECT lesson.id, lesson.required
+            FROM lesson JOIN module ON module.id = lesson.module_id
+            WHERE module.course_id = $1
+        )
+        UPDATE enrollment e SET status = 'completed', completed_at = now()
+        WHERE e.course_id = $1 AND e.status = 'active'
+        AND EXISTS (SELECT 1 FROM course_lesson)
+        AND NOT EXISTS (
+            SELECT 1 FROM course_lesson
+            LEFT JOIN lesson_progress progress
+                ON progress.lesson_id = course_lesson.id AND progress.enrollment_id = e.id
+            WHERE progress.completed_at IS NULL
+            AND (course_lesson.required OR NOT EXISTS (SELECT 1 FROM course_lesson WHERE required))
+        )
+        RETURNING e.id"#,
+        course_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.id).collect())
+}
+
+//progress of every seated student of the course, sorted by RosterProgress::sort
+pub async fn get_roster_progress_db(pool: &PgPool, course_id: i32) -> Result<Vec<RosterProgress>, MyError> {
+    let rows = sqlx::query!(
+        r#"WITH course_lesson AS (
+            SELECT lesson.id, lesson.required
+            FROM lesson JOIN module ON module.id = lesson.module_id
+            WHERE module.course_id = $1
+        )
+        SELECT student.id as student_id, student.name, student.email,
+        e.status as "status: EnrollmentStatus",
+        (SELECT count(*) FROM course_lesson) as "total_lessons!",
+        (SELECT count(*) FROM course_lesson WHERE required) as "required_lessons!",
+        count(course_lesson.id) FILTER (WHERE progress.completed_at IS NOT NULL) as "completed_lessons!",
+        count(course_lesson.id) FILTER (WHERE progress.completed_at IS NOT NULL AND course_lesson.required)
+            as "completed_required_lessons!",
+        max(progress.updated_at) as last_activity_at,
+        e.completed_at
+        FROM enrollment e JOIN student ON student.id = e.student_id
+        LEFT JOIN lesson_progress progress ON progress.enrollment_id = e.id
+        LEFT JOIN course_lesson ON course_lesson.id = progress.lesson_id
+        WHERE e.course_id = $1 AND e.status IN ('active', 'completed')
+        GROUP BY student.id, e.id"#,
+        course_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut roster: Vec<RosterProgress> = rows
+        .into_iter()
+        .map(|row| {
+            let counts = ProgressCounts {
+                total_lessons: row.total_lessons,
+                completed_lessons: row.completed_lessons,
+                required_lessons: row.required_lessons,
+                completed_required_lessons: row.completed_required_lessons,
+            };
+            RosterProgress {
+                student_id: row.student_id,
+                name: row.name,
+                email: row.email,
+                status: row.status,
+                counts,
+                completion_percent: counts.percent(),
+                last_activity_at: row.last_activity_at,
+                completed_at: row.completed_at,
+            }
+        })
+        .collect();
+    RosterProgress::sort(&mut roster);
+
+    Ok(roster)
+}
@@ -0,0 +1,347 @@
+use crate::models::quiz::*;
+use crate::errors::MyError;
+use sqlx::postgres::{PgConnection, PgExecutor, PgPool};
+use sqlx::types::Json;
+
+fn quiz_not_found() -> MyError {
+    MyError::NotFound("Quiz id not found".into())
+}
+
+fn attempt_not_found() -> MyError {
+    MyError::NotFound("Attempt id not found".into())
+}
+
+pub async fn get_quizzes_db(pool: &PgPool, course_id: i32) -> Result<Vec<Quiz>, MyError> {
+    let rows = sqlx::query_as!(
+        Quiz,
+        r#"SELECT id, course_id, lesson_id, title, description, max_attempts, time_limit_minutes, created_at, updated_at
+        FROM quiz WHERE course_id = $1 ORDER BY id"#,
+        course_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn get_quiz_db<'e, E: PgExecutor<'e>>(executor: E, course_id: i32, quiz_id: i32) -> Result<Quiz, MyError> {
+    let row = sqlx::query_as!(
+        Quiz,
+        r#"SELECT id, course_id, lesson_id, title, description, max_attempts, time_limit_minutes, created_at, updated_at
+        FROM quiz WHERE course_id = $1 AND id = $2"#,
+        course_id,
+        quiz_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    row.ok_or_else(quiz_not_found)
+}
+
+//questions of a quiz in position order
+pub async fn get_questions_db<'e, E: PgExecutor<'e>>(executor: E, quiz_id: i32) -> Result<Vec<QuizQuestion>, MyError> {
+    let rows = sqlx::query_as!(
+        QuizQuestion,
+        r#"SELECT id, position, prompt, points, spec as "spec: Json<QuestionSpec>"
+        FROM quiz_question WHERE quiz_id = $1 ORDER BY position"#,
+        quiz_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows)
+}
+
+//the quiz and its questions, numbered in the order they were sent
+pub async fn post_new_quiz_db(conn: &mut PgConnection, course_id: i32, new_quiz: CreateQuiz) -> Result<Quiz, MyError> {
+    let quiz = sqlx::query_as!(
+        Quiz,
+        r#"INSERT INTO quiz (course_id, lesson_id, title, description, max_attempts, time_limit_minutes)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, course_id, lesson_id, title, description, max_attempts, time_limit_minutes, created_at, updated_at"#,
+        course_id,
+        new_quiz.lesson_id,
+        new_quiz.title,
+        new_quiz.description,
+        new_quiz.max_attempts,
+        new_quiz.time_limit_minutes
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    for (index, question) in new_quiz.questions.into_iter().enumerate() {
+        sqlx::query!(
+            "INSERT INTO quiz_question (quiz_id, position, prompt, points, spec) VALUES ($1, $2, $3, $4, $5)",
+            quiz.id,
+            index as i32 + 1,
+            question.prompt,
+            question.points.unwrap_or(1),
+            serde_json::to_value(&question.spec).unwrap_or_default()
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(quiz)
+}
+
+pub async fn update_quiz_db(pool: &PgPool, course_id: i32, quiz_id: i32, update: UpdateQuiz) -> Result<Quiz, MyError> {
+    let current = get_quiz_db(pool, course_id, quiz_id).await?;
+    let row = sqlx::query_as!(
+        Quiz,
+        r#"UPDATE quiz SET title = $3, description = $4, max_attempts = $5, time_limit_minutes = $6
+        WHERE course_id = $1 AND id = $2
+        RETURNING id, course_id, lesson_id, title, description, max_attempts, time_limit_minutes, created_at, updated_at"#,
+        course_id,
+        quiz_id,
+        update.title.unwrap_or(current.title),
+        update.description.or(current.description),
+        update.max_attempts.unwrap_or(current.max_attempts),
+        update.time_limit_minutes.unwrap_or(current.time_limit_minutes)
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.ok_or_else(quiz_not_found)
+}
+
+//attempts and answers go with the quiz
+pub async fn delete_quiz_db(pool: &PgPool, course_id: i32, quiz_id: i32) -> Result<(), MyError> {
+    let result = sqlx::query!("DELETE FROM quiz WHERE course_id = $1 AND id = $2", course_id, quiz_id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(quiz_not_found());
+    }
+    Ok(())
+}
+
+pub async fn get_attempt_db<'e, E: PgExecutor<'e>>(executor: E, quiz_id: i32, attempt_id: i32) -> Result<QuizAttempt, MyError> {
+    let row = sqlx::query_as!(
+        QuizAttempt,
+        r#"SELECT a.id, a.quiz_id, e.student_id, a.attempt_number, a.status as "status: AttemptStatus",
+        a.started_at, a.deadline_at, a.submitted_at, a.score, a.max_score, a.pending_review
+        FROM quiz_attempt a JOIN enrollment e ON e.id = a.enrollment_id
+        WHERE a.quiz_id = $1 AND a.id = $2"#,
+        quiz_id,
+        attempt_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    row.ok_or_else(attempt_not_found)
+}
+
+//the attempt locked until commit, so it is submitted or graded once at a time
+pub async fn lock_attempt_db(conn: &mut PgConnection, quiz_id: i32, attempt_id: i32) -> Result<QuizAttempt, MyError> {
+    sqlx::query!(
+        "SELECT id FROM quiz_attempt WHERE quiz_id = $1 AND id = $2 FOR UPDATE",
+        quiz_id,
+        attempt_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    get_attempt_db(conn, quiz_id, attempt_id).await
+}
+
+//attempts of a quiz, of one enrollment or of everyone, in the order they were started
+pub async fn get_attempts_db<'e, E: PgExecutor<'e>>(
+    executor: E,
+    quiz_id: i32,
+    enrollment_id: Option<i32>,
+) -> Result<Vec<QuizAttempt>, MyError> {
+    let rows = sqlx::query_as!(
+        QuizAttempt,
+        r#"SELECT a.id, a.quiz_id, e.student_id, a.attempt_number, a.status as "status: AttemptStatus",
+        a.started_at, a.deadline_at, a.submitted_at, a.score, a.max_score, a.pending_review
+        FROM quiz_attempt a JOIN enrollment e ON e.id = a.enrollment_id
+        WHERE a.quiz_id = $1 AND ($2::int IS NULL OR a.enrollment_id = $2)
+        ORDER BY a.started_at, a.id"#,
+        quiz_id,
+        enrollment_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows)
+}
+
+//marks the in-progress attempts whose time ran out as expired, of one enrollment or of the whole quiz
+pub async fn expire_overdue_attempts_db<'e, E: PgExecutor<'e>>(
+    executor: E,
+    quiz_id: i32,
+    enrollment_id: Option<i32>,
+) -> Result<u64, MyError> {
+    let result = sqlx::query!(
+        r#"UPDATE quiz_attempt SET status = 'expired'
+        WHERE quiz_id = $1 AND ($2::int IS NULL OR enrollment_id = $2)
+        AND status = 'in_progress' AND deadline_at < now()"#,
+        quiz_id,
+        enrollment_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+//returns false when the attempt still has time left
+pub async fn expire_attempt_db(conn: &mut PgConnection, attempt_id: i32) -> Result<bool, MyError> {
+    let result = sqlx::query!(
+        "UPDATE quiz_attempt SET status = 'expired' WHERE id = $1 AND status = 'in_progress' AND deadline_at < now()",
+        attempt_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+//the deadline is counted from now, no deadline without a time limit
+pub async fn start_attempt_db(
+    conn: &mut PgConnection,
+    quiz: &Quiz,
+    enrollment_id: i32,
+    attempt_number: i32,
+    max_score: i32,
+) -> Result<QuizAttempt, MyError> {
+    let row = sqlx::query!(
+        r#"INSERT INTO quiz_attempt (quiz_id, enrollment_id, attempt_number, deadline_at, max_score)
+        VALUES ($1, $2, $3, now() + make_interval(mins => $4), $5)
+        RETURNING id"#,
+        quiz.id,
+        enrollment_id,
+        attempt_number,
+        quiz.time_limit_minutes,
+        max_score
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    get_attempt_db(conn, quiz.id, row.id).await
+}
+
+//stores one answer per question and the score of the graded ones
+pub async fn submit_attempt_db(conn: &mut PgConnection, attempt_id: i32, answers: &[QuizAnswer]) -> Result<(), MyError> {
+    for answer in answers {
+        sqlx::query!(
+            r#"INSERT INTO quiz_answer (attempt_id, question_id, response, correct, points_awarded)
+            VALUES ($1, $2, $3, $4, $5)"#,
+            attempt_id,
+            answer.question_id,
+            answer.response,
+            answer.correct,
+            answer.points_awarded
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    let (score, pending_review) = QuizAnswer::score(answers);
+    sqlx::query!(
+        r#"UPDATE quiz_attempt SET status = 'submitted', submitted_at = now(), score = $2, pending_review = $3
+        WHERE id = $1"#,
+        attempt_id,
+        score,
+        pending_review
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+//answers of an attempt in question order
+pub async fn get_answers_db<'e, E: PgExecutor<'e>>(executor: E, attempt_id: i32) -> Result<Vec<QuizAnswer>, MyError> {
+    let rows = sqlx::query_as!(
+        QuizAnswer,
+        r#"SELECT answer.question_id, answer.response, answer.correct, answer.points_awarded
+        FROM quiz_answer answer JOIN quiz_question question ON question.id = answer.question_id
+        WHERE answer.attempt_id = $1
+        ORDER BY question.position"#,
+        attempt_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows)
+}
+
+//answers of every submitted attempt of the quiz, what the question statistics are computed from
+pub async fn get_submitted_answers_db(pool: &PgPool, quiz_id: i32) -> Result<Vec<QuizAnswer>, MyError> {
+    let rows = sqlx::query_as!(
+        QuizAnswer,
+        r#"SELECT answer.question_id, answer.response, answer.correct, answer.points_awarded
+        FROM quiz_answer answer JOIN quiz_attempt attempt ON attempt.id = answer.attempt_id
+        WHERE attempt.quiz_id = $1 AND attempt.status = 'submitted'"#,
+        quiz_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+//sets the teacher's grade of one answer, then the attempt's score and whether answers still wait for review
+pub async fn grade_answer_db(
+    conn: &mut PgConnection,
+    attempt_id: i32,
+    question_id: i32,
+    points_awarded: i32,
+    correct: bool,
+) -> Result<(), MyError> {
+    let result = sqlx::query!(
+        "UPDATE quiz_answer SET points_awarded = $3, correct = $4 WHERE attempt_id = $1 AND question_id = $2",
+        attempt_id,
+        question_id,
+        points_awarded,
+        correct
+    )
+    .execute(&mut *conn)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(MyError::NotFound("Answer not found".into()));
+    }
+    sqlx::query!(
+        r#"UPDATE quiz_attempt SET
+        score = (SELECT COALESCE(sum(points_awarded), 0) FROM quiz_answer WHERE attempt_id = $1),
+        pending_review = EXISTS (SELECT 1 FROM quiz_answer WHERE attempt_id = $1 AND points_awarded IS NULL)
+        WHERE id = $1"#,
+        attempt_id
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/**
+Copies the quizzes and questions of one course into another, attempts stay with the original
+call it after clone_outline_db, a quiz attached to a lesson is attached to the lesson
+at the same place in the copied outline
+**/
+pub async fn clone_quizzes_db(conn: &mut PgConnection, from_course_id: i32, to_course_id: i32) -> Result<(), MyError> {
+    let quiz_ids = sqlx::query!("SELECT id FROM quiz WHERE course_id = $1 ORDER BY id", from_course_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    for quiz in quiz_ids {
+        sqlx::query!(
+            "WITH copy AS (
+                INSERT INTO quiz (course_id, lesson_id, title, description, max_attempts, time_limit_minutes)
+                SELECT $2, (
+                    SELECT copy_lesson.id FROM lesson
+                    JOIN module ON module.id = lesson.module_id
+                    JOIN module copy_module ON copy_module.course_id = $2 AND copy_module.position = module.position
+                    JOIN lesson copy_lesson ON copy_lesson.module_id = copy_module.id AND copy_lesson.position = lesson.position
+                    WHERE lesson.id = quiz.lesson_id
+                ), title, description, max_attempts, time_limit_minutes
+                FROM quiz WHERE id = $1
+                RETURNING id
+            )
+            INSERT INTO quiz_question (quiz_id, position, prompt, points, spec)
+            SELECT copy.id, position, prompt, points, spec FROM quiz_question, copy WHERE quiz_question.quiz_id = $1",
+            quiz.id,
+            to_course_id
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use crate::models::review::{CreateReview, Review, ReviewSort};
+use crate::errors::MyError;
+use sqlx::postgres::{PgConnection, PgExecutor, PgPool};
+
+fn review_not_found() -> MyError {
+    MyError::NotFound("Review id not found".into())
+}
+
+pub async fn get_review_db<'e, E: PgExecutor<'e>>(executor: E, course_id: i32, review_id: i32) -> Result<Review, MyError> {
+    let row = sqlx::query_as!(
+        Review,
+        r#"SELECT review.id, review.course_id, review.student_id, student.name as student_name,
+        review.rating, review.body, review.helpful_count, review.reply, review.replied_at,
+        review.created_at, review.updated_at
+        FROM review JOIN student ON student.id = review.student_id
+        WHERE review.course_id = $1 AND review.id = $2"#,
+        course_id,
+        review_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    row.ok_or_else(review_not_found)
+}
+
+//the review locked until commit, so two edits of its rating move the course's sum one after the other
+pub async fn lock_review_db(conn: &mut PgConnection, course_id: i32, review_id: i32) -> Result<Review, MyError> {
+    sqlx::query!(
+        "SELECT id FROM review WHERE course_id = $1 AND id = $2 FOR UPDATE",
+        course_id,
+        review_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    get_review_db(conn, course_id, review_id).await
+}
+
+//(one page of reviews, reviews of the course), ties in helpfulness go to the newest review
+pub async fn get_reviews_db(
+    pool: &PgPool,
+    course_id: i32,
+    sort: ReviewSort,
+    page: i64,
+    per_page: i64,
+) -> Result<(Vec<Review>, i64), MyError> {
+    let rows = sqlx::query_as!(
+        Review,
+        r#"SELECT review.id, review.course_id, review.student_id, student.name as student_name,
+        review.rating, review.body, review.helpful_count, review.reply, review.replied_at,
+        review.created_at, review.updated_at
+        FROM review JOIN student ON student.id = review.student_id
+        WHERE review.course_id = $1
+        ORDER BY CASE WHEN $2::varchar = 'helpful' THEN review.helpful_count END DESC NULLS LAST,
+        review.created_at DESC, review.id DESC
+        LIMIT $3 OFFSET $4"#,
+        course_id,
+        sort.as_str(),
+        per_page,
+        (page - 1) * per_page
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let total = sqlx::query!(r#"SELECT count(*) as "total!" FROM review WHERE course_id = $1"#, course_id)
+        .fetch_one(pool)
+        .await?
+        .total;
+
+    Ok((rows, total))
+}
+
+//moves the course's rating aggregate, average_rating is computed from it by the database
+async fn add_to_course_rating_db(conn: &mut PgConnection, course_id: i32, reviews: i32, rating: i32) -> Result<(), MyError> {
+    sqlx::query!(
+        "UPDATE course SET review_count = review_count + $2, rating_sum = rating_sum + $3 WHERE id = $1",
+        course_id,
+        reviews,
+        rating
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+//a second review of the same student is a conflict, they edit the first one instead
+pub async fn insert_review_db(conn: &mut PgConnection, course_id: i32, new_review: CreateReview) -> Result<Review, MyError> {
+    let row = sqlx::query!(
+        r#"INSERT INTO review (course_id, student_id, rating, body) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (course_id, student_id) DO NOTHING
+        RETURNING id"#,
+        course_id,
+        new_review.student_id,
+        new_review.rating,
+        new_review.body
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| MyError::Conflict("The student already reviewed this course, edit that review instead".into()))?;
+
+    add_to_course_rating_db(conn, course_id, 1, new_review.rating).await?;
+    get_review_db(conn, course_id, row.id).await
+}
+
+//the caller holds the review lock and passes the review as it was
+pub async fn update_review_db(
+    conn: &mut PgConnection,
+    current: &Review,
+    rating: i32,
+    body: Option<String>,
+) -> Result<Review, MyError> {
+    sqlx::query!(
+        "UPDATE review SET rating = $2, body = $3, updated_at = now() WHERE id = $1",
+        current.id,
+        rating,
+        body
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if rating != current.rating {
+        add_to_course_rating_db(conn, current.course_id, 0, rating - current.rating).await?;
+    }
+    get_review_db(conn, current.course_id, current.id).await
+}
+
+pub async fn reply_to_review_db(pool: &PgPool, course_id: i32, review_id: i32, reply: String) -> Result<Review, MyError> {
+    let result = sqlx::query!(
+        "UPDATE review SET reply = $3, replied_at = now() WHERE course_id = $1 AND id = $2",
+        course_id,
+        review_id,
+        reply
+    )
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(review_not_found());
+    }
+    get_review_db(pool, course_id, review_id).await
+}
+
+//voting twice counts once, the count moves only when the vote is new
+pub async fn add_helpful_vote_db(conn: &mut PgConnection, review_id: i32, student_id: i32) -> Result<(), MyError> {
+    let result = sqlx::query!(
+        "INSERT INTO review_vote (review_id, student_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        review_id,
+        student_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    if result.rows_affected() > 0 {
+        sqlx::query!("UPDATE review SET helpful_count = helpful_count + 1 WHERE id = $1", review_id)
+            .execute(conn)
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn remove_helpful_vote_db(conn: &mut PgConnection, review_id: i32, student_id: i32) -> Result<(), MyError> {
+    let result = sqlx::query!(
+        "DELETE FROM review_vote WHERE review_id = $1 AND student_id = $2",
+        review_id,
+        student_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    if result.rows_affected() > 0 {
+        sqlx::query!("UPDATE review SET helpful_count = helpful_count - 1 WHERE id = $1", review_id)
+            .execute(conn)
+            .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use crate::models::audit::AuditContext;
+use crate::models::course::Course;
+use crate::models::revision::{same_content, CourseRevision};
+use crate::errors::MyError;
+use sqlx::postgres::{PgConnection, PgPool};
+
+
+async fn insert_revision_db(
+    conn: &mut PgConnection,
+    course: &Course,
+    actor: Option<&str>,
+) -> Result<CourseRevision, MyError> {
+    let row = sqlx::query_as!(
+        CourseRevision,
+        r#"INSERT INTO course_revision (course_id, revision, teacher_id, description, structure, actor)
+        SELECT $1, COALESCE(MAX(revision), 0) + 1, $2, $3, $4, $5
+        FROM course_revision WHERE course_id = $1
+        RETURNING course_id, revision, teacher_id, description, structure, actor, created_at"#,
+        course.id,
+        course.teacher_id,
+        course.description,
+        course.structure,
+        actor,
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(row)
+}
+
+//stores the content a course was created with as revision 1
+pub async fn create_first_revision_db(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    course: &Course,
+) -> Result<(), MyError> {
+    insert_revision_db(conn, course, Some(&ctx.actor)).await?;
+    Ok(())
+}
+
+//stores the content after an update as the next revision, call it inside the update's
+//transaction with the course row locked so two updates cannot take the same number
+//courses without revisions first get their pre-update content as a baseline
+//an update that leaves description and structure alone adds no revision
+pub async fn record_revision_db(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    before: &Course,
+    after: &Course,
+) -> Result<(), MyError> {
+    if same_content(&before.description, &after.description) && same_content(&before.structure, &after.structure) {
+        return Ok(());
+    }
+
+    let has_revisions = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM course_revision WHERE course_id = $1) as "exists!""#,
+        before.id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if !has_revisions {
+        insert_revision_db(&mut *conn, before, None).await?;
+    }
+
+    insert_revision_db(&mut *conn, after, Some(&ctx.actor)).await?;
+    Ok(())
+}
+
+pub async fn get_course_revisions_db(
+    pool: &PgPool,
+    teacher_id: i32,
+    course_id: i32,
+) -> Result<Vec<CourseRevision>, MyError> {
+    let rows = sqlx::query_as!(
+        CourseRevision,
+        r#"SELECT course_id, revision, teacher_id, description, structure, actor, created_at
+        FROM course_revision
+        WHERE teacher_id = $1 AND course_id = $2
+        ORDER BY revision"#,
+        teacher_id,
+        course_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn get_course_revision_db(
+    pool: &PgPool,
+    teacher_id: i32,
+    course_id: i32,
+    revision: i32,
+) -> Result<CourseRevision, MyError> {
+    let row = sqlx::query_as!(
+        CourseRevision,
+        r#"SELECT course_id, revision, teacher_id, description, structure, actor, created_at
+        FROM course_revision
+        WHERE teacher_id = $1 AND course_id = $2 AND revision = $3"#,
+        teacher_id,
+        course_id,
+        revision
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.ok_or_else(|| MyError::NotFound(format!("Revision {} not found", revision)))
+}
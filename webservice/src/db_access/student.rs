@@ -0,0 +1,64 @@
+use crate::models::student::{CreateStudent, Student, UpdateStudent};
+use crate::errors::MyError;
+use sqlx::postgres::{PgExecutor, PgPool};
+
+fn email_taken(email: &str) -> MyError {
+    MyError::Conflict(format!("A student with email {} already exists", email))
+}
+
+//a concurrent write can take the email after the checks below, the unique index then refuses it
+fn email_conflict(email: &str) -> impl FnOnce(sqlx::Error) -> MyError + '_ {
+    move |err| match err.as_database_error().and_then(|db_err| db_err.code()) {
+        Some(code) if code == "23505" => email_taken(email),
+        _ => err.into(),
+    }
+}
+
+pub async fn get_student_db<'e, E: PgExecutor<'e>>(executor: E, student_id: i32) -> Result<Student, MyError> {
+    let row = sqlx::query_as!(
+        Student,
+        "SELECT id, name, email, created_at, updated_at FROM student WHERE id = $1",
+        student_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    row.ok_or_else(|| MyError::NotFound("Student id not found".into()))
+}
+
+//emails are unique, a second student with the same one is a conflict
+pub async fn post_new_student_db(pool: &PgPool, new_student: CreateStudent) -> Result<Student, MyError> {
+    let row = sqlx::query_as!(
+        Student,
+        r#"INSERT INTO student (name, email) VALUES ($1, $2)
+        ON CONFLICT (email) DO NOTHING
+        RETURNING id, name, email, created_at, updated_at"#,
+        new_student.name,
+        new_student.email
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(email_conflict(&new_student.email))?;
+
+    row.ok_or_else(|| email_taken(&new_student.email))
+}
+
+pub async fn update_student_db(pool: &PgPool, student_id: i32, update: UpdateStudent) -> Result<Student, MyError> {
+    let current = get_student_db(pool, student_id).await?;
+    let email = update.email.unwrap_or(current.email);
+    let row = sqlx::query_as!(
+        Student,
+        r#"UPDATE student SET name = $2, email = $3
+        WHERE id = $1
+        AND NOT EXISTS (SELECT 1 FROM student other WHERE other.email = $3 AND other.id <> $1)
+        RETURNING id, name, email, created_at, updated_at"#,
+        student_id,
+        update.name.unwrap_or(current.name),
+        email
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(email_conflict(&email))?;
+
+    row.ok_or_else(|| email_taken(&email))
+}
@@ -0,0 +1,258 @@
+use crate::models::taxonomy::{Category, CreateCategory, TagCount, UpdateCategory};
+use crate::errors::MyError;
+use sqlx::postgres::{PgConnection, PgExecutor, PgPool};
+
+fn category_not_found() -> MyError {
+    MyError::NotFound("Category id not found".into())
+}
+
+//every category, siblings by name
+pub async fn get_categories_db<'e, E: PgExecutor<'e>>(executor: E) -> Result<Vec<Category>, MyError> {
+    let rows = sqlx::query_as!(
+        Category,
+        "SELECT id, parent_id, name, created_at, updated_at FROM category ORDER BY lower(name), id"
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn get_category_db<'e, E: PgExecutor<'e>>(executor: E, category_id: i32) -> Result<Category, MyError> {
+    let row = sqlx::query_as!(
+        Category,
+        "SELECT id, parent_id, name, created_at, updated_at FROM category WHERE id = $1",
+        category_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    row.ok_or_else(category_not_found)
+}
+
+/**
+Category writes lock the whole table until commit, they are rare and made by an admin
+with the lock held, no other move can slip in between the cycle check and the update,
+and the sibling name check cannot race another insert
+**/
+pub async fn lock_categories_db(conn: &mut PgConnection) -> Result<(), MyError> {
+    sqlx::query!("LOCK TABLE category IN SHARE ROW EXCLUSIVE MODE")
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+//the parent has to exist and must not already hold a sibling of the same name
+async fn check_category_place_db(
+    conn: &mut PgConnection,
+    category_id: Option<i32>,
+    parent_id: Option<i32>,
+    name: &str,
+) -> Result<(), MyError> {
+    if let Some(parent_id) = parent_id {
+        get_category_db(&mut *conn, parent_id).await.map_err(|err| match err {
+            MyError::NotFound(_) => MyError::NotFound("Parent category not found".into()),
+            err => err,
+        })?;
+    }
+    let taken = sqlx::query!(
+        r#"SELECT EXISTS (
+            SELECT 1 FROM category
+            WHERE parent_id IS NOT DISTINCT FROM $1 AND lower(name) = lower($2) AND id IS DISTINCT FROM $3
+        ) as "taken!""#,
+        parent_id,
+        name,
+        category_id
+    )
+    .fetch_one(conn)
+    .await?
+    .taken;
+    if taken {
+        return Err(MyError::Conflict(format!("A category named {} already exists there", name)));
+    }
+    Ok(())
+}
+
+pub async fn insert_category_db(conn: &mut PgConnection, new_category: CreateCategory) -> Result<Category, MyError> {
+    check_category_place_db(conn, None, new_category.parent_id, &new_category.name).await?;
+    let row = sqlx::query_as!(
+        Category,
+        "INSERT INTO category (parent_id, name) VALUES ($1, $2)
+        RETURNING id, parent_id, name, created_at, updated_at",
+        new_category.parent_id,
+        new_category.name
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(row)
+}
+
+//a category cannot move under itself or under one of its own descendants
+pub async fn update_category_db(conn: &mut PgConnection, category_id: i32, update: UpdateCategory) -> Result<Category, MyError> {
+    get_category_db(&mut *conn, category_id).await?;
+    if let Some(parent_id) = update.parent_id {
+        let cycle = sqlx::query!(
+            r#"WITH RECURSIVE ancestors AS (
+                SELECT id, parent_id FROM category WHERE id = $1
+                UNION ALL
+                SELECT category.id, category.parent_id FROM category JOIN ancestors ON category.id = ancestors.parent_id
+            )
+            SELECT EXISTS (SELECT 1 FROM ancestors WHERE id = $2) as "cycle!""#,
+            parent_id,
+            category_id
+        )
+        .fetch_one(&mut *conn)
+        .await?
+        .cycle;
+        if cycle {
+            return Err(MyError::Conflict("A category cannot be moved under itself or one of its subcategories".into()));
+        }
+    }
+    check_category_place_db(conn, Some(category_id), update.parent_id, &update.name).await?;
+    let row = sqlx::query_as!(
+        Category,
+        "UPDATE category SET parent_id = $2, name = $3 WHERE id = $1
+        RETURNING id, parent_id, name, created_at, updated_at",
+        category_id,
+        update.parent_id,
+        update.name
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(row)
+}
+
+//courses lose the category, subcategories have to go first
+pub async fn delete_category_db(conn: &mut PgConnection, category_id: i32) -> Result<(), MyError> {
+    get_category_db(&mut *conn, category_id).await?;
+    let children = sqlx::query!(
+        r#"SELECT count(*) as "count!" FROM category WHERE parent_id = $1"#,
+        category_id
+    )
+    .fetch_one(&mut *conn)
+    .await?
+    .count;
+    if children > 0 {
+        return Err(MyError::Conflict("The category has subcategories, move or delete them first".into()));
+    }
+    sqlx::query!("DELETE FROM category WHERE id = $1", category_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_course_categories_db<'e, E: PgExecutor<'e>>(executor: E, course_id: i32) -> Result<Vec<Category>, MyError> {
+    let rows = sqlx::query_as!(
+        Category,
+        "SELECT category.id, category.parent_id, category.name, category.created_at, category.updated_at
+        FROM course_category JOIN category ON category.id = course_category.category_id
+        WHERE course_category.course_id = $1
+        ORDER BY lower(category.name), category.id",
+        course_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows)
+}
+
+//the key share locks keep the categories from being deleted before the assignment commits
+pub async fn set_course_categories_db(conn: &mut PgConnection, course_id: i32, category_ids: &[i32]) -> Result<Vec<Category>, MyError> {
+    let found = sqlx::query!(
+        r#"SELECT count(*) as "count!" FROM (SELECT id FROM category WHERE id = ANY($1) FOR KEY SHARE) AS found"#,
+        category_ids
+    )
+    .fetch_one(&mut *conn)
+    .await?
+    .count;
+    if found as usize != category_ids.len() {
+        return Err(category_not_found());
+    }
+    sqlx::query!("DELETE FROM course_category WHERE course_id = $1", course_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!(
+        "INSERT INTO course_category (course_id, category_id) SELECT $1, unnest($2::int[])",
+        course_id,
+        category_ids
+    )
+    .execute(&mut *conn)
+    .await?;
+    get_course_categories_db(conn, course_id).await
+}
+
+pub async fn get_course_tags_db<'e, E: PgExecutor<'e>>(executor: E, course_id: i32) -> Result<Vec<String>, MyError> {
+    let rows = sqlx::query!(
+        "SELECT tag.name FROM course_tag JOIN tag ON tag.id = course_tag.tag_id
+        WHERE course_tag.course_id = $1 ORDER BY tag.name",
+        course_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.name).collect())
+}
+
+//tags the course is the first to use are created, tags left unused are kept for the next course
+pub async fn set_course_tags_db(conn: &mut PgConnection, course_id: i32, tags: &[String]) -> Result<Vec<String>, MyError> {
+    sqlx::query!(
+        "INSERT INTO tag (name) SELECT unnest($1::varchar[]) ON CONFLICT (name) DO NOTHING",
+        tags
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!("DELETE FROM course_tag WHERE course_id = $1", course_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!(
+        "INSERT INTO course_tag (course_id, tag_id) SELECT $1, id FROM tag WHERE name = ANY($2)",
+        course_id,
+        tags
+    )
+    .execute(&mut *conn)
+    .await?;
+    get_course_tags_db(conn, course_id).await
+}
+
+//tags starting with the pattern's prefix, most used by published courses first
+//tags only drafts carry are left out, they would tell what unpublished courses are about
+pub async fn get_tag_suggestions_db(pool: &PgPool, pattern: &str, limit: i64) -> Result<Vec<TagCount>, MyError> {
+    let rows = sqlx::query_as!(
+        TagCount,
+        r#"SELECT tag.name, count(*) as "course_count!"
+        FROM tag
+        JOIN course_tag ON course_tag.tag_id = tag.id
+        JOIN course ON course.id = course_tag.course_id
+        WHERE tag.name LIKE $1 AND course.deleted_at IS NULL AND course.status = 'published'
+        GROUP BY tag.name
+        ORDER BY count(*) DESC, tag.name
+        LIMIT $2"#,
+        pattern,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn clone_course_taxonomy_db(conn: &mut PgConnection, from_course_id: i32, to_course_id: i32) -> Result<(), MyError> {
+    sqlx::query!(
+        "INSERT INTO course_category (course_id, category_id)
+        SELECT $2, category_id FROM course_category WHERE course_id = $1",
+        from_course_id,
+        to_course_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "INSERT INTO course_tag (course_id, tag_id) SELECT $2, tag_id FROM course_tag WHERE course_id = $1",
+        from_course_id,
+        to_course_id
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
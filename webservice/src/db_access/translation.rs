@@ -0,0 +1,101 @@
+use crate::models::facet::LanguageTag;
+use crate::models::translation::{CourseTranslation, SaveTranslation};
+use crate::errors::MyError;
+use sqlx::postgres::{PgConnection, PgExecutor};
+
+fn translation_not_found() -> MyError {
+    MyError::NotFound("Translation not found".into())
+}
+
+pub async fn get_translations_db<'e, E: PgExecutor<'e>>(executor: E, course_id: i32) -> Result<Vec<CourseTranslation>, MyError> {
+    let rows = sqlx::query_as!(
+        CourseTranslation,
+        r#"SELECT course_id, locale as "locale: LanguageTag", name, description, structure, created_at, updated_at
+        FROM course_translation WHERE course_id = $1 ORDER BY locale"#,
+        course_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows)
+}
+
+//the translations of a page of courses, for a list read in one query
+pub async fn get_translations_for_courses_db<'e, E: PgExecutor<'e>>(executor: E, course_ids: &[i32]) -> Result<Vec<CourseTranslation>, MyError> {
+    let rows = sqlx::query_as!(
+        CourseTranslation,
+        r#"SELECT course_id, locale as "locale: LanguageTag", name, description, structure, created_at, updated_at
+        FROM course_translation WHERE course_id = ANY($1) ORDER BY course_id, locale"#,
+        course_ids
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn get_translation_db<'e, E: PgExecutor<'e>>(executor: E, course_id: i32, locale: &LanguageTag) -> Result<CourseTranslation, MyError> {
+    let row = sqlx::query_as!(
+        CourseTranslation,
+        r#"SELECT course_id, locale as "locale: LanguageTag", name, description, structure, created_at, updated_at
+        FROM course_translation WHERE course_id = $1 AND locale = $2"#,
+        course_id,
+        locale.as_str()
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    row.ok_or_else(translation_not_found)
+}
+
+pub async fn upsert_translation_db(
+    conn: &mut PgConnection,
+    course_id: i32,
+    locale: &LanguageTag,
+    translation: SaveTranslation,
+) -> Result<CourseTranslation, MyError> {
+    let row = sqlx::query_as!(
+        CourseTranslation,
+        r#"INSERT INTO course_translation (course_id, locale, name, description, structure)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (course_id, locale) DO UPDATE
+        SET name = EXCLUDED.name, description = EXCLUDED.description, structure = EXCLUDED.structure
+        RETURNING course_id, locale as "locale: LanguageTag", name, description, structure, created_at, updated_at"#,
+        course_id,
+        locale.as_str(),
+        translation.name,
+        translation.description,
+        translation.structure
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(row)
+}
+
+pub async fn delete_translation_db(conn: &mut PgConnection, course_id: i32, locale: &LanguageTag) -> Result<(), MyError> {
+    let result = sqlx::query!(
+        "DELETE FROM course_translation WHERE course_id = $1 AND locale = $2",
+        course_id,
+        locale.as_str()
+    )
+    .execute(conn)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(translation_not_found());
+    }
+    Ok(())
+}
+
+//translated names are copied as they are, the copy's new name is only in its own language
+pub async fn clone_translations_db(conn: &mut PgConnection, from_course_id: i32, to_course_id: i32) -> Result<(), MyError> {
+    sqlx::query!(
+        "INSERT INTO course_translation (course_id, locale, name, description, structure)
+        SELECT $2, locale, name, description, structure FROM course_translation WHERE course_id = $1",
+        from_course_id,
+        to_course_id
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
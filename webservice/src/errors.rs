@@ -8,6 +8,11 @@ pub enum MyError {
     DBError(String),
     ActixError(String),
     NotFound(String),
+    InvalidInput(String),
+    NotAcceptable(String),
+    UnsupportedMediaType(String),
+    Conflict(String),
+    Forbidden(String),
 }
 
 #[derive(Debug, Serialize)]
@@ -16,7 +21,7 @@ pub struct MyErrorResponse {
 }
 
 impl MyError {
-    fn error_response(&self) -> String {
+    pub fn error_response(&self) -> String {
 
         match self {
             MyError::DBError(msg) => {
@@ -31,6 +36,26 @@ impl MyError {
                 println!("Not found error occurred: {:?}", msg);
                 msg.into() 
             }
+            MyError::InvalidInput(msg) => {
+                println!("Invalid parameters received: {:?}", msg);
+                msg.into()
+            }
+            MyError::NotAcceptable(msg) => {
+                println!("Not acceptable error occurred: {:?}", msg);
+                msg.into()
+            }
+            MyError::UnsupportedMediaType(msg) => {
+                println!("Unsupported media type error occurred: {:?}", msg);
+                msg.into()
+            }
+            MyError::Conflict(msg) => {
+                println!("Conflict error occurred: {:?}", msg);
+                msg.into()
+            }
+            MyError::Forbidden(msg) => {
+                println!("Forbidden error occurred: {:?}", msg);
+                msg.into()
+            }
         }
     }
 }
@@ -41,6 +66,11 @@ impl error::ResponseError for MyError {
         match self {
             MyError::DBError(_msg) | MyError::ActixError(_msg) => StatusCode::INTERNAL_SERVER_ERROR,
             MyError::NotFound(_msg) => StatusCode::NOT_FOUND,
+            MyError::InvalidInput(_msg) => StatusCode::BAD_REQUEST,
+            MyError::NotAcceptable(_msg) => StatusCode::NOT_ACCEPTABLE,
+            MyError::UnsupportedMediaType(_msg) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            MyError::Conflict(_msg) => StatusCode::CONFLICT,
+            MyError::Forbidden(_msg) => StatusCode::FORBIDDEN,
         }
     }
     fn error_response(&self) -> HttpResponse {
@@ -52,10 +82,13 @@ impl error::ResponseError for MyError {
 
 impl fmt::Display for MyError {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result <(), fmt::Error> {
-        write!(f, "{}", self)
+        write!(f, "{:?}", self)
     }
 }
 
+//lets MyError end a streamed response body
+impl std::error::Error for MyError {}
+
 impl From<actix_web::error::Error> for MyError {
     fn from(err: actix_web::error::Error) -> Self {
         MyError::ActixError(err.to_string() )
@@ -0,0 +1,91 @@
+use crate::state::AppState;
+use actix_web::{http::header, web, HttpResponse};
+use crate::db_access::certificate::*;
+use crate::db_access::course::get_course_details_db;
+use crate::db_access::enrollment::lock_enrollment_db;
+use crate::errors::MyError;
+use crate::models::certificate::*;
+use crate::models::course::Viewer;
+use crate::models::enrollment::EnrollmentStatus;
+use sqlx::postgres::PgConnection;
+
+//a fresh code is drawn when the one drawn is taken, which 60 random bits make unlikely
+const CODE_ATTEMPTS: usize = 5;
+
+//renders the certificate and keeps a copy in the certificate directory, a failed write only costs the copy
+fn render_certificate_file(settings: &CertificateSettings, certificate: &Certificate) -> Result<Vec<u8>, MyError> {
+    let pdf = settings.template.render(certificate)?;
+    let path = settings.path(certificate);
+    if let Err(err) = std::fs::create_dir_all(&settings.dir).and_then(|_| std::fs::write(&path, &pdf)) {
+        log::warn!("Cannot write {}: {}", path.display(), err);
+    }
+    Ok(pdf)
+}
+
+/**
+Issues the certificate of a completed enrollment, or returns the one it already has
+the caller holds the enrollment lock, so a course is never certified twice for a student
+only the row is stored, the PDF is rendered on first download so completing a lesson never waits on the disk
+**/
+pub async fn issue_certificate(
+    conn: &mut PgConnection,
+    settings: &CertificateSettings,
+    teacher_id: i32,
+    enrollment_id: i32,
+) -> Result<Certificate, MyError> {
+    if let Some(certificate) = get_certificate_db(&mut *conn, enrollment_id).await? {
+        return Ok(certificate);
+    }
+    let teacher_name = settings.template.teacher_name(teacher_id);
+    for _ in 0..CODE_ATTEMPTS {
+        let code = new_verification_code();
+        if let Some(certificate) = insert_certificate_db(&mut *conn, enrollment_id, &code, &teacher_name).await? {
+            log::info!("Certificate {} issued to student {} for course {}", certificate.code, certificate.student_id, certificate.course_id);
+            return Ok(certificate);
+        }
+    }
+    Err(MyError::ActixError("No free certificate verification code".into()))
+}
+
+/**
+GET /courses/{teacher_id}/{course_id}/enrollments/{student_id}/certificate, the PDF
+students who completed the course before certificates existed get theirs on first download
+the PDF is rendered from the stored certificate when the certificate directory has no copy of it
+**/
+pub async fn get_certificate_pdf(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, student_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, viewer.owns(teacher_id)).await?;
+    let mut tx = app_state.db.begin().await?;
+    let enrollment = lock_enrollment_db(&mut tx, course_id, student_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Enrollment not found".into()))?;
+    if enrollment.status != EnrollmentStatus::Completed {
+        return Err(MyError::NotFound("The student has not completed this course".into()));
+    }
+    let certificate = issue_certificate(&mut tx, &app_state.certificates, teacher_id, enrollment.id).await?;
+    tx.commit().await?;
+
+    let pdf = match std::fs::read(app_state.certificates.path(&certificate)) {
+        Ok(pdf) => pdf,
+        Err(_) => render_certificate_file(&app_state.certificates, &certificate)?,
+    };
+    Ok(HttpResponse::Ok()
+        .content_type("application/pdf")
+        .insert_header((header::CONTENT_DISPOSITION, format!("inline; filename=\"certificate-{}.pdf\"", certificate.code)))
+        .body(pdf))
+}
+
+//GET /certificates/verify/{code}, public
+pub async fn verify_certificate(
+    app_state: web::Data<AppState>,
+    code: web::Path<String>,
+) -> Result<HttpResponse, MyError> {
+    let code = normalize_verification_code(&code).ok_or_else(|| MyError::NotFound("Certificate not found".into()))?;
+    get_certificate_by_code_db(&app_state.db, &code)
+        .await
+        .map(|certificate| HttpResponse::Ok().json(CertificateVerification::from(certificate)))
+}
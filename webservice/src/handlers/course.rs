@@ -1,56 +1,283 @@
 use crate::state::AppState;
-use actix_web::{web, HttpResponse};
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse};
 use crate::db_access::course::*;
+use crate::db_access::audit::*;
+use crate::db_access::revision::*;
+use crate::db_access::enrollment::promote_waitlisted_db;
+use crate::db_access::exchange_rate::get_exchange_rates_db;
+use crate::db_access::outline::{clone_outline_db, get_outline_db};
+use crate::db_access::prerequisite::clone_prerequisites_db;
+use crate::db_access::quiz::clone_quizzes_db;
+use crate::db_access::taxonomy::clone_course_taxonomy_db;
+use crate::db_access::translation::{clone_translations_db, get_translations_db, get_translations_for_courses_db};
+use crate::handlers::translation::with_content_language;
 use crate::errors::MyError;
-use crate::models::course::{ CreateCourse, UpdateCourse };
+use crate::models::audit::{ AuditAction, AuditContext };
+use crate::models::exchange_rate::ExchangeRates;
+use crate::models::facet::CourseFacets;
+use crate::models::revision::{ DiffQuery, RevisionDiff };
+use crate::models::translation::{CourseTranslation, LocalePreference};
+use crate::models::course::{ CloneCourse, Course, CourseStatus, CourseEmbed, CreateCourse, DetailQuery, ExportQuery, ListQuery, CourseView, ScheduleCourse, StatusChange, Viewer, ImportMode, ImportQuery, ImportReport, ImportRowError, UpdateCourse };
+use crate::negotiation::{ decode_rows, MediaType, Negotiated, StreamFormat };
+use futures_util::StreamExt;
+use std::collections::HashMap;
 
 
 pub async fn post_new_course(
-    new_course: web::Json<CreateCourse>,
+    new_course: Negotiated<CreateCourse>,
     app_state: web::Data<AppState>,
+    accept: MediaType,
+    ctx: AuditContext,
 ) -> Result<HttpResponse, MyError> {
     //calling async function use await, new_course is extractor
     // post_new_course_db is an async DB operation that:
-    // 1. Takes a reference to DB pool (&app_state.db)
-    // 2. Takes ownership of Course data (new_course.into())
-    // 3. Returns the created course
-    
-    post_new_course_db(&app_state.db, new_course.try_into()?)
+    // 1. Takes the open transaction (&mut tx), so the audit record commits with the course
+    // 2. Takes ownership of Course data (decoded from json, csv, msgpack or xml)
+    // 3. Returns the created course, encoded as the client's Accept header asks
+    // the same checks as an imported row run first, so a bad field is a 400 and not a constraint error
+    let new_course = new_course.into_inner();
+    new_course.validate()?;
+    let mut tx = app_state.db.begin().await?;
+    let course = post_new_course_db(&mut tx, new_course).await?;
+    insert_course_audit_db(&mut tx, &ctx, AuditAction::Create, None, Some(&course)).await?;
+    create_first_revision_db(&mut tx, &ctx, &course).await?;
+    tx.commit().await?;
+    accept.respond(&course)
+}
+
+/**
+POST /courses/{teacher_id}/{course_id}/clone, by the course's teacher
+the copy is a draft with the course's metadata, outline, quizzes, categories, tags, prerequisites and translations,
+made in one transaction with the original locked, so it is never a mix of two versions
+**/
+pub async fn clone_course(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    request: web::Json<CloneCourse>,
+    accept: MediaType,
+    viewer: Viewer,
+    ctx: AuditContext,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "clone it")?;
+    let (new_teacher_id, name) = request.into_inner().validate(teacher_id)?;
+    let mut tx = app_state.db.begin().await?;
+    lock_course_db(&mut tx, teacher_id, course_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Course Id not found".into()))?;
+    let course = clone_course_db(&mut tx, course_id, new_teacher_id, &name).await?;
+    clone_outline_db(&mut tx, course_id, course.id).await?;
+    clone_quizzes_db(&mut tx, course_id, course.id).await?;
+    clone_course_taxonomy_db(&mut tx, course_id, course.id).await?;
+    clone_prerequisites_db(&mut tx, course_id, course.id).await?;
+    clone_translations_db(&mut tx, course_id, course.id).await?;
+    insert_course_audit_db(&mut tx, &ctx, AuditAction::Create, None, Some(&course)).await?;
+    create_first_revision_db(&mut tx, &ctx, &course).await?;
+    tx.commit().await?;
+    accept.respond(&course)
+}
+
+//POST /courses/import?dry_run=true&mode=per_row
+//body is CSV (text/csv) or JSON lines (application/x-ndjson) of CreateCourse records
+pub async fn import_courses(
+    req: HttpRequest,
+    body: web::Bytes,
+    query: web::Query<ImportQuery>,
+    app_state: web::Data<AppState>,
+    ctx: AuditContext,
+) -> Result<HttpResponse, MyError> {
+    let query = query.into_inner();
+    let rows = decode_rows::<CreateCourse>(&req, &body)?;
+    let mut report = ImportReport::new(&query, rows.len());
+
+    let mut valid_rows = vec![];
+    for (row, decoded) in rows {
+        match decoded.and_then(|course| course.validate().map(|_| course)) {
+            Ok(course) => valid_rows.push((row, course)),
+            Err(err) => report.errors.push(ImportRowError { row, error: err.error_response() }),
+        }
+    }
+
+    //an atomic import with an invalid row writes nothing, not even the valid rows
+    if report.mode == ImportMode::Atomic && !report.errors.is_empty() {
+        return Ok(HttpResponse::build(StatusCode::UNPROCESSABLE_ENTITY).json(report));
+    }
+
+    import_courses_db(&app_state.db, &ctx, valid_rows, &mut report).await?;
+    report.errors.sort_by_key(|err| err.row);
+
+    //an atomic import with any error wrote nothing
+    let status = if report.mode == ImportMode::Atomic && !report.errors.is_empty() {
+        StatusCode::UNPROCESSABLE_ENTITY
+    } else {
+        StatusCode::OK
+    };
+    Ok(HttpResponse::build(status).json(report))
+}
+
+//GET /courses/export?teacher_id=1&format=csv
+//the body is streamed batch by batch from a database cursor
+//drafts are only exported to their teacher, who asks for their own courses with ?teacher_id=
+pub async fn export_courses(
+    req: HttpRequest,
+    query: web::Query<ExportQuery>,
+    app_state: web::Data<AppState>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let query = query.into_inner();
+    let format = query.format.unwrap_or_else(|| StreamFormat::from_accept(&req));
+    let include_unpublished = query.teacher_id.is_some_and(|teacher_id| viewer.owns(teacher_id));
+    let batches = export_courses_db(&app_state.db, query.teacher_id, include_unpublished, query.updated_since).await?;
+
+    let mut first_batch = true;
+    let body = batches.map(move |batch| {
+        let encoded = batch.and_then(|courses| format.encode_batch(&courses, first_batch));
+        first_batch = false;
+        encoded.map(web::Bytes::from)
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"courses.{}\"", format.extension()),
+        ))
+        .streaming(body))
+}
+
+//GET /courses/facets, allowed level, format and language values with course counts
+pub async fn get_course_facets(
+    app_state: web::Data<AppState>,
+    query: web::Query<ListQuery>, //?category=2&tags=rust, the filters of List Courses
+) -> Result<HttpResponse, MyError> {
+    let filter = query.filter()?;
+    get_course_facet_counts_db(&app_state.db, &filter)
         .await
-        .map(|course| HttpResponse::Ok().json(course))
-    
-    
+        .map(|counts| HttpResponse::Ok().json(CourseFacets::from_counts(counts)))
 }
 
 pub async fn get_courses_for_teacher(
     app_state: web::Data<AppState>,
     params: web::Path<i32>, //xxxx/{teacher_id}
+    query: web::Query<ListQuery>, //?updated_since=2024-11-26T00:00:00Z&currency=EUR&min_duration=PT2H&tags=rust
+    accept: MediaType,
+    viewer: Viewer,
+    locale: LocalePreference,
 ) -> Result<HttpResponse, MyError> {
     let teacher_id = params.into_inner();
-    get_courses_for_teacher_db(&app_state.db, teacher_id)
-    .await
-    .map(|courses| HttpResponse::Ok().json(courses))
+    let filter = query.filter()?;
+    let mut courses = get_courses_for_teacher_db(&app_state.db, teacher_id, viewer.owns(teacher_id), &filter).await?;
+    let mut translations: HashMap<i32, Vec<CourseTranslation>> = HashMap::new();
+    if !locale.is_empty() {
+        let ids: Vec<i32> = courses.iter().map(|course| course.id).collect();
+        for translation in get_translations_for_courses_db(&app_state.db, &ids).await? {
+            translations.entry(translation.course_id).or_default().push(translation);
+        }
+    }
+    let mut served: Vec<String> = vec![];
+    for course in courses.iter_mut() {
+        let own = translations.get(&course.id).map_or(&[][..], Vec::as_slice);
+        if let Some(served_locale) = locale.translate(course, own) {
+            if !served.contains(&served_locale) {
+                served.push(served_locale);
+            }
+        }
+    }
+    let response = match query.currency {
+        Some(currency) => {
+            let rates = ExchangeRates::new(get_exchange_rates_db(&app_state.db).await?);
+            let courses: Vec<CourseView> = courses
+                .into_iter()
+                .map(|course| CourseView::new(course).localized(&rates, currency))
+                .collect();
+            accept.respond(&courses)?
+        }
+        None => accept.respond(&courses)?,
+    };
+    Ok(with_content_language(response, &served))
 }
 
 pub async fn get_course_detail(
     app_state: web::Data<AppState>,
     params: web::Path<(i32, i32)>,
+    query: web::Query<DetailQuery>, //?currency=EUR&embed=outline&locale=de
+    accept: MediaType,
+    viewer: Viewer,
+    locale: LocalePreference,
 ) -> Result<HttpResponse, MyError> {
     let (teacher_id, course_id) = params.into_inner();
-    get_course_details_db(&app_state.db, teacher_id, course_id)
-        .await
-        .map(|course| HttpResponse::Ok().json(course))
-    
+    let mut course = get_course_details_db(&app_state.db, teacher_id, course_id, viewer.owns(teacher_id)).await?;
+    let translations = if locale.is_empty() { vec![] } else { get_translations_db(&app_state.db, course_id).await? };
+    let served = locale.translate(&mut course, &translations);
+    let mut view = CourseView::new(course);
+    if let Some(currency) = query.currency {
+        let rates = ExchangeRates::new(get_exchange_rates_db(&app_state.db).await?);
+        view = view.localized(&rates, currency);
+    }
+    if query.embed == Some(CourseEmbed::Outline) {
+        let mut conn = app_state.db.acquire().await?;
+        view = view.with_outline(get_outline_db(&mut conn, course_id).await?);
+    }
+    Ok(with_content_language(accept.respond(&view)?, served.as_slice()))
 }
 
 pub async fn delete_course(
     app_state: web::Data<AppState>,
     params: web::Path<(i32, i32)>,
+    viewer: Viewer,
+    ctx: AuditContext,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "delete it")?;
+    let mut tx = app_state.db.begin().await?;
+    let before = lock_course_db(&mut tx, teacher_id, course_id).await?;
+    let deleted = delete_course_db(&mut tx, teacher_id, course_id).await?;
+    if let Some(before) = before {
+        insert_course_audit_db(&mut tx, &ctx, AuditAction::Delete, Some(&before), None).await?;
+    }
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(deleted))
+}
+
+pub async fn get_trash_for_teacher(
+    app_state: web::Data<AppState>,
+    params: web::Path<i32>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let teacher_id = params.into_inner();
+    viewer.require_owner(teacher_id, "see its trash")?;
+    get_trash_for_teacher_db(&app_state.db, teacher_id)
+        .await
+        .map(|courses| HttpResponse::Ok().json(courses))
+}
+
+pub async fn restore_course(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    accept: MediaType,
+    viewer: Viewer,
+    ctx: AuditContext,
 ) -> Result<HttpResponse, MyError> {
     let (teacher_id, course_id) = params.into_inner();
-    delete_course_db(&app_state.db, teacher_id, course_id)
+    viewer.require_owner(teacher_id, "restore it")?;
+    let mut tx = app_state.db.begin().await?;
+    let course = restore_course_db(&mut tx, teacher_id, course_id).await?;
+    insert_course_audit_db(&mut tx, &ctx, AuditAction::Restore, None, Some(&course)).await?;
+    tx.commit().await?;
+    accept.respond(&course)
+}
+
+//GET /courses/{teacher_id}/{course_id}/history, oldest change first
+pub async fn get_course_history(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "see its history")?;
+    get_course_history_db(&app_state.db, teacher_id, course_id)
         .await
-        .map(|course| HttpResponse::Ok().json(course))
+        .map(|entries| HttpResponse::Ok().json(entries))
 }
 
 
@@ -58,11 +285,177 @@ pub async fn update_course_details(
     app_state: web::Data<AppState>,
     update_course: web::Json<UpdateCourse>,
     params: web::Path<(i32, i32)>,
+    accept: MediaType,
+    viewer: Viewer,
+    ctx: AuditContext,
 ) -> Result<HttpResponse, MyError> {
     let (teacher_id, course_id ) = params.into_inner();
-    update_course_details_db(&app_state.db, teacher_id, course_id, update_course.try_into()?)
+    viewer.require_owner(teacher_id, "change it")?;
+    let course = apply_course_update(&app_state, &ctx, teacher_id, course_id, update_course.try_into()?).await?;
+    accept.respond(&course)
+}
+
+//the update path shared by PUT and rollback: updates the course, then writes
+//its audit record and content revision in the same transaction
+async fn apply_course_update(
+    app_state: &AppState,
+    ctx: &AuditContext,
+    teacher_id: i32,
+    course_id: i32,
+    update_course: UpdateCourse,
+) -> Result<Course, MyError> {
+    let mut tx = app_state.db.begin().await?;
+    let before = lock_course_db(&mut tx, teacher_id, course_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Course id not found".into()))?;
+    let course = update_course_details_db(&mut tx, teacher_id, course_id, update_course).await?;
+    insert_course_audit_db(&mut tx, ctx, AuditAction::Update, Some(&before), Some(&course)).await?;
+    record_revision_db(&mut tx, ctx, &before, &course).await?;
+    //more seats let waitlisted students in, no capacity at all lets the whole waitlist in
+    if course.capacity != before.capacity {
+        promote_waitlisted_db(&mut tx, &course).await?;
+    }
+    tx.commit().await?;
+    Ok(course)
+}
+
+pub async fn publish_course(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    accept: MediaType,
+    viewer: Viewer,
+    ctx: AuditContext,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "publish it")?;
+    let course = apply_status_change(&app_state, &ctx, teacher_id, course_id, CourseStatus::Published).await?;
+    accept.respond(&course)
+}
+
+pub async fn unpublish_course(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    accept: MediaType,
+    viewer: Viewer,
+    ctx: AuditContext,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "unpublish it")?;
+    let course = apply_status_change(&app_state, &ctx, teacher_id, course_id, CourseStatus::Draft).await?;
+    accept.respond(&course)
+}
+
+//POST /courses/{teacher_id}/{course_id}/status {"status": "in_review"}
+//for the transitions without a dedicated endpoint, like review and archive
+pub async fn change_course_status(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    change: web::Json<StatusChange>,
+    accept: MediaType,
+    viewer: Viewer,
+    ctx: AuditContext,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "change its status")?;
+    let course = apply_status_change(&app_state, &ctx, teacher_id, course_id, change.status).await?;
+    accept.respond(&course)
+}
+
+//PUT /courses/{teacher_id}/{course_id}/schedule
+//the scheduler in teacher-service publishes and unpublishes the course at these times
+pub async fn set_course_schedule(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    schedule: web::Json<ScheduleCourse>,
+    accept: MediaType,
+    viewer: Viewer,
+    ctx: AuditContext,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "schedule it")?;
+    let schedule = schedule.resolve()?;
+    let mut tx = app_state.db.begin().await?;
+    let before = lock_course_db(&mut tx, teacher_id, course_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Course id not found".into()))?;
+    let course = set_course_schedule_db(&mut tx, teacher_id, course_id, schedule).await?;
+    insert_course_audit_db(&mut tx, &ctx, AuditAction::Schedule, Some(&before), Some(&course)).await?;
+    tx.commit().await?;
+    accept.respond(&course)
+}
+
+//checks the transition against the current status of the locked row,
+//so two concurrent changes cannot both pass the check
+async fn apply_status_change(
+    app_state: &AppState,
+    ctx: &AuditContext,
+    teacher_id: i32,
+    course_id: i32,
+    status: CourseStatus,
+) -> Result<Course, MyError> {
+    let mut tx = app_state.db.begin().await?;
+    let before = lock_course_db(&mut tx, teacher_id, course_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Course id not found".into()))?;
+    let status = before.status.transition(status)?;
+    let course = set_course_status_db(&mut tx, teacher_id, course_id, status).await?;
+    insert_course_audit_db(&mut tx, ctx, AuditAction::Status, Some(&before), Some(&course)).await?;
+    tx.commit().await?;
+    Ok(course)
+}
+
+pub async fn get_course_revisions(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "see its revisions")?;
+    get_course_revisions_db(&app_state.db, teacher_id, course_id)
         .await
-        .map(|course| HttpResponse::Ok().json(course))
+        .map(|revisions| HttpResponse::Ok().json(revisions))
+}
+
+//GET /courses/{teacher_id}/{course_id}/revisions/diff?from=1&to=3
+pub async fn diff_course_revisions(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    query: web::Query<DiffQuery>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "see its revisions")?;
+    let from = get_course_revision_db(&app_state.db, teacher_id, course_id, query.from).await?;
+    let to = get_course_revision_db(&app_state.db, teacher_id, course_id, query.to).await?;
+    Ok(HttpResponse::Ok().json(RevisionDiff::between(&from, &to)))
+}
+
+//POST /courses/{teacher_id}/{course_id}/revisions/{revision}/rollback
+//puts the content of an earlier revision back, which is recorded as a new revision
+pub async fn rollback_course_revision(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    accept: MediaType,
+    viewer: Viewer,
+    ctx: AuditContext,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, revision) = params.into_inner();
+    viewer.require_owner(teacher_id, "roll it back")?;
+    let revision = get_course_revision_db(&app_state.db, teacher_id, course_id, revision).await?;
+    let update_course = UpdateCourse {
+        name: None,
+        //a missing value is sent as empty, which the update path stores as none again
+        description: Some(revision.description.unwrap_or_default()),
+        format: None,
+        structure: Some(revision.structure.unwrap_or_default()),
+        duration: None,
+        price: None,
+        language: None,
+        level: None,
+        capacity: None,
+    };
+    let course = apply_course_update(&app_state, &ctx, teacher_id, course_id, update_course).await?;
+    accept.respond(&course)
 }
 
 
@@ -75,7 +468,24 @@ mod tests {
     use dotenv::dotenv;
     use sqlx::postgres::PgPoolOptions;
     use std::env;
-    use std::time::Duration;
+    use crate::db_access::outline::{insert_lesson_db, insert_module_db};
+    use crate::db_access::prerequisite::{get_prerequisites_db, set_prerequisites_db};
+    use crate::db_access::quiz::{get_questions_db, get_quizzes_db, post_new_quiz_db};
+    use crate::db_access::taxonomy::{get_course_tags_db, set_course_tags_db};
+    use crate::models::facet::{CourseLevel, LanguageTag};
+    use crate::models::outline::{CreateLesson, CreateModule};
+    use crate::models::quiz::CreateQuiz;
+
+    fn test_audit_context() -> AuditContext {
+        AuditContext {
+            actor: "test".into(),
+            request_id: "test-request".into(),
+        }
+    }
+
+    fn test_viewer() -> Viewer {
+        Viewer { teacher_id: Some(1) }
+    }
 
     //because the function is async, need to use actix_rt for async test
     #[actix_rt::test]
@@ -91,9 +501,10 @@ mod tests {
             health_check_response: "".to_string(),
             visit_count: Mutex::new(0),
             db:db_pool,
+            certificates: Default::default(),
         });
 
-        let course = web::Json(CreateCourse {
+        let course = CreateCourse {
             teacher_id: 1,
             name: "test course".into(),
             description: Some("this is a course".into()),
@@ -101,12 +512,17 @@ mod tests {
             structure: None,
             duration: None,
             price: None,
-            language: Some("English".into()),
-            level: Some("Beginner".into()),
-        });
+            language: Some(LanguageTag::parse("en").unwrap()),
+            level: Some(CourseLevel::Beginner),
+            capacity: None,
+        };
 
-        let resp = post_new_course(course, app_state).await.unwrap();
+        let resp = post_new_course(Negotiated(course.clone()), app_state.clone(), MediaType::Json, test_audit_context()).await.unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
+
+        let no_seats = Negotiated(CreateCourse { capacity: Some(0), ..course });
+        let resp = post_new_course(no_seats, app_state, MediaType::Json, test_audit_context()).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::BAD_REQUEST);
     }
 
     #[actix_rt::test]
@@ -122,10 +538,11 @@ mod tests {
             health_check_response: "".to_string(),
             visit_count: Mutex::new(0),
             db:db_pool,
+            certificates: Default::default(),
         });
 
         let teacher_id: web::Path<i32> = web::Path::from(1);
-        let resp = get_courses_for_teacher(app_state, teacher_id).await.unwrap();
+        let resp = get_courses_for_teacher(app_state, teacher_id, web::Query(ListQuery::default()), MediaType::Json, test_viewer(), LocalePreference::default()).await.unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
@@ -151,11 +568,12 @@ mod tests {
             health_check_response: "".to_string(),
             visit_count: Mutex::new(0),
             db: db_pool,
+            certificates: Default::default(),
         });
 
         // Use the first available course ID from the database
         let params: web::Path<(i32, i32)> = web::Path::from((1, 2));  // Currently hardcoded to ID 2
-        let resp = get_course_detail(app_state, params).await.unwrap();
+        let resp = get_course_detail(app_state, params, web::Query(DetailQuery::default()), MediaType::Json, test_viewer(), LocalePreference::default()).await.unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
@@ -172,9 +590,10 @@ mod tests {
             health_check_response: "".to_string(),
             visit_count: Mutex::new(0),
             db:db_pool,
+            certificates: Default::default(),
         });
         let params: web::Path<(i32, i32)> = web::Path::from((1, 100));
-        let resp = get_course_detail(app_state, params).await;
+        let resp = get_course_detail(app_state, params, web::Query(DetailQuery::default()), MediaType::Json, test_viewer(), LocalePreference::default()).await;
         match resp {
             Ok(_) => println!("Something wrong..."),
             Err(err) => assert_eq!(err.status_code(), StatusCode::NOT_FOUND),
@@ -193,21 +612,23 @@ mod tests {
             health_check_response: "".to_string(),
             visit_count: Mutex::new(0),
             db:db_pool,
+            certificates: Default::default(),
         });
         let update_course = UpdateCourse {
             name: Some("Course name changed".into()),
             description: Some("This is another test course".into()),
             format: None,
-            level: Some("Intermediate".into()),
+            level: Some(CourseLevel::Intermediate),
+            capacity: None,
             price: None,
             duration: None,
-            language: Some("Chinese".into()),
+            language: Some(LanguageTag::parse("zh").unwrap()),
             structure: None,
         };
 
         let params: web::Path<(i32, i32)> = web::Path::from((1, 2));
         let update_param = web::Json(update_course);
-        let resp = update_course_details(app_state, update_param, params).await.unwrap();
+        let resp = update_course_details(app_state, update_param, params, MediaType::Json, test_viewer(), test_audit_context()).await.unwrap();
         
         assert_eq!(resp.status(), StatusCode::OK);
     }
@@ -224,10 +645,168 @@ mod tests {
             health_check_response: "".to_string(),
             visit_count: Mutex::new(0),
             db:db_pool,
+            certificates: Default::default(),
         });
 
         let params: web::Path<(i32, i32)> = web::Path::from((1, 3));
-        let resp = delete_course(app_state, params).await.unwrap();
+        let resp = delete_course(app_state, params, test_viewer(), test_audit_context()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn deleted_course_can_be_restored() {
+        dotenv().ok();
+
+        let db_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set.");
+        let db_pool = PgPoolOptions::new().connect(&db_url).await.unwrap();
+
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState {
+            health_check_response: "".to_string(),
+            visit_count: Mutex::new(0),
+            db:db_pool,
+            certificates: Default::default(),
+        });
+
+        let course = post_new_course_db(&app_state.db, CreateCourse {
+            teacher_id: 1,
+            name: "course for the trash".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+            capacity: None,
+        }).await.unwrap();
+
+        let params: web::Path<(i32, i32)> = web::Path::from((1, course.id));
+        delete_course(app_state.clone(), params, test_viewer(), test_audit_context()).await.unwrap();
+
+        let params: web::Path<(i32, i32)> = web::Path::from((1, course.id));
+        let resp = get_course_detail(app_state.clone(), params, web::Query(DetailQuery::default()), MediaType::Json, test_viewer(), LocalePreference::default()).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::NOT_FOUND);
+
+        let params: web::Path<(i32, i32)> = web::Path::from((1, course.id));
+        let resp = restore_course(app_state.clone(), params, MediaType::Json, test_viewer(), test_audit_context()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let params: web::Path<(i32, i32)> = web::Path::from((1, course.id));
+        let resp = get_course_detail(app_state, params, web::Query(DetailQuery::default()), MediaType::Json, test_viewer(), LocalePreference::default()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn clone_copies_outline_quizzes_tags_and_prerequisites() {
+        dotenv().ok();
+
+        let db_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set.");
+        let db_pool = PgPoolOptions::new().connect(&db_url).await.unwrap();
+
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState {
+            health_check_response: "".to_string(),
+            visit_count: Mutex::new(0),
+            db:db_pool,
+            certificates: Default::default(),
+        });
+
+        let new_course = |name: &str| CreateCourse {
+            teacher_id: 1,
+            name: name.into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+            capacity: None,
+        };
+        let prerequisite = post_new_course_db(&app_state.db, new_course("course to clone first")).await.unwrap();
+        let course = post_new_course_db(&app_state.db, new_course("course to clone")).await.unwrap();
+
+        let mut conn = app_state.db.acquire().await.unwrap();
+        let module = insert_module_db(&mut conn, course.id, CreateModule { title: "Basics".into(), description: None, position: None })
+            .await
+            .unwrap();
+        for (title, required) in [("Setup", Some(false)), ("Ownership", None)] {
+            let lesson = CreateLesson { title: title.into(), content: None, duration_minutes: Some(30), required, position: None };
+            insert_lesson_db(&mut conn, module.id, lesson).await.unwrap();
+        }
+        let outline = get_outline_db(&mut conn, course.id).await.unwrap();
+        let new_quiz: CreateQuiz = serde_json::from_value(serde_json::json!({
+            "title": "Ownership quiz",
+            "lesson_id": outline[0].lessons[1].id,
+            "questions": [{"prompt": "2 + 2", "kind": "numeric", "answer": 4.0, "tolerance": 0.0}],
+        }))
+        .unwrap();
+        post_new_quiz_db(&mut conn, course.id, new_quiz).await.unwrap();
+        set_course_tags_db(&mut conn, course.id, &["rust".into(), "web dev".into()]).await.unwrap();
+        set_prerequisites_db(&mut conn, course.id, &[prerequisite.id]).await.unwrap();
+
+        let params: web::Path<(i32, i32)> = web::Path::from((1, course.id));
+        let request = web::Json(CloneCourse { name: "cloned course".into(), teacher_id: None });
+        let resp = clone_course(app_state.clone(), params, request, MediaType::Json, test_viewer(), test_audit_context()).await.unwrap();
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let copy_id = serde_json::from_slice::<serde_json::Value>(&body).unwrap()["id"].as_i64().unwrap() as i32;
+
+        let copy = get_outline_db(&mut conn, copy_id).await.unwrap();
+        assert_eq!(copy.len(), 1);
+        let lessons: Vec<(&str, bool)> = copy[0].lessons.iter().map(|lesson| (lesson.title.as_str(), lesson.required)).collect();
+        assert_eq!(lessons, [("Setup", false), ("Ownership", true)]);
+        let quizzes = get_quizzes_db(&app_state.db, copy_id).await.unwrap();
+        assert_eq!(quizzes.len(), 1);
+        assert_eq!(quizzes[0].lesson_id, Some(copy[0].lessons[1].id));
+        assert_eq!(get_questions_db(&app_state.db, quizzes[0].id).await.unwrap().len(), 1);
+        assert_eq!(get_course_tags_db(&app_state.db, copy_id).await.unwrap(), ["rust", "web dev"]);
+        let prerequisites = get_prerequisites_db(&app_state.db, copy_id).await.unwrap();
+        assert_eq!(prerequisites.iter().map(|course| course.id).collect::<Vec<_>>(), [prerequisite.id]);
+    }
+
+    #[actix_rt::test]
+    async fn only_the_teacher_changes_or_inspects_a_course() {
+        dotenv().ok();
+
+        let db_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set.");
+        let db_pool = PgPoolOptions::new().connect(&db_url).await.unwrap();
+
+
+        let app_state: web::Data<AppState> = web::Data::new(AppState {
+            health_check_response: "".to_string(),
+            visit_count: Mutex::new(0),
+            db:db_pool,
+            certificates: Default::default(),
+        });
+
+        let course = post_new_course_db(&app_state.db, CreateCourse {
+            teacher_id: 1,
+            name: "course of teacher 1".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+            capacity: None,
+        }).await.unwrap();
+        let params = || -> web::Path<(i32, i32)> { web::Path::from((1, course.id)) };
+        let stranger = Viewer { teacher_id: Some(2) };
+
+        let resp = publish_course(app_state.clone(), params(), MediaType::Json, stranger, test_audit_context()).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::FORBIDDEN);
+        let resp = delete_course(app_state.clone(), params(), Viewer::default(), test_audit_context()).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::FORBIDDEN);
+        let resp = get_course_history(app_state.clone(), params(), stranger).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::FORBIDDEN);
+        let resp = get_course_revisions(app_state.clone(), params(), stranger).await;
+        assert_eq!(resp.unwrap_err().status_code(), StatusCode::FORBIDDEN);
+
+        let resp = publish_course(app_state.clone(), params(), MediaType::Json, test_viewer(), test_audit_context()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = get_course_history(app_state, params(), test_viewer()).await.unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
@@ -243,10 +822,11 @@ mod tests {
             health_check_response: "".to_string(),
             visit_count: Mutex::new(0),
             db:db_pool,
+            certificates: Default::default(),
         });
 
         let params: web::Path<(i32, i32)> = web::Path::from((1, 101));
-        let resp = delete_course(app_state, params).await;
+        let resp = delete_course(app_state, params, test_viewer(), test_audit_context()).await;
 
         match resp {
             Ok(_) => println!("Something wrong..."),
@@ -0,0 +1,263 @@
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use crate::db_access::course::{get_course_details_db, lock_course_db};
+use crate::db_access::enrollment::*;
+use crate::db_access::prerequisite::{get_missing_prerequisites_db, has_override_db};
+use crate::db_access::student::get_student_db;
+use crate::errors::MyError;
+use crate::models::course::{CourseStatus, Viewer};
+use crate::models::enrollment::{EnrollmentQuery, EnrollmentRequest, EnrollmentStatus};
+
+
+/**
+POST /courses/{teacher_id}/{course_id}/enrollments, only published courses take students
+a student who has not completed the course's prerequisites is refused with 403, unless the teacher granted an override
+a full course puts the student on its waitlist, or refuses with 409 when the request has "waitlist": false
+the course row stays locked until commit, so concurrent requests are counted one after another
+and never take more seats than the capacity
+**/
+pub async fn enroll_in_course(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    request: web::Json<EnrollmentRequest>,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    let mut tx = app_state.db.begin().await?;
+    let course = lock_course_db(&mut tx, teacher_id, course_id)
+        .await?
+        .filter(|course| course.status == CourseStatus::Published)
+        .ok_or_else(|| MyError::NotFound("Course id not found".into()))?;
+    get_student_db(&mut tx, request.student_id).await?;
+    match get_enrollment_db(&mut tx, course_id, request.student_id).await?.map(|enrollment| enrollment.status) {
+        Some(EnrollmentStatus::Active) => {
+            return Err(MyError::Conflict("Student is already enrolled in this course".into()))
+        }
+        Some(EnrollmentStatus::Waitlisted) => {
+            return Err(MyError::Conflict("Student is already on the waitlist of this course".into()))
+        }
+        Some(EnrollmentStatus::Completed) => {
+            return Err(MyError::Conflict("Student has already completed this course".into()))
+        }
+        _ => {}
+    }
+    if !has_override_db(&mut tx, course_id, request.student_id).await? {
+        let missing = get_missing_prerequisites_db(&mut tx, course_id, request.student_id).await?;
+        if !missing.is_empty() {
+            let names: Vec<&str> = missing.iter().map(|course| course.name.as_str()).collect();
+            return Err(MyError::Forbidden(format!(
+                "Student has not completed the prerequisites of this course: {}",
+                names.join(", ")
+            )));
+        }
+    }
+
+    //nobody skips the queue: while students wait, newcomers wait behind them
+    let (active, waitlisted) = count_enrollments_db(&mut tx, course_id).await?;
+    let full = course.capacity.is_some_and(|capacity| active >= capacity as i64) || waitlisted > 0;
+    let status = match (full, request.waitlist.unwrap_or(true)) {
+        (false, _) => EnrollmentStatus::Active,
+        (true, true) => EnrollmentStatus::Waitlisted,
+        (true, false) => return Err(MyError::Conflict("Course is full".into())),
+    };
+    let enrollment = enroll_db(&mut tx, course_id, request.student_id, status).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(enrollment))
+}
+
+//POST /courses/{teacher_id}/{course_id}/enrollments/{student_id}/withdraw
+//a seat freed by an active student goes to the next student on the waitlist in the same transaction
+pub async fn withdraw_from_course(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, student_id) = params.into_inner();
+    let mut tx = app_state.db.begin().await?;
+    let course = lock_course_db(&mut tx, teacher_id, course_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Course id not found".into()))?;
+    let current = get_enrollment_db(&mut tx, course_id, student_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Enrollment not found".into()))?;
+    let enrollment = withdraw_db(&mut tx, course_id, student_id).await?;
+    if current.status == EnrollmentStatus::Active {
+        promote_waitlisted_db(&mut tx, &course).await?;
+    }
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(enrollment))
+}
+
+//GET /courses/{teacher_id}/{course_id}/enrollments?status=waitlisted
+//the roster lists students' emails, so only the course's teacher sees it
+pub async fn get_course_roster(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    query: web::Query<EnrollmentQuery>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "see its roster")?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    get_roster_db(&app_state.db, course_id, query.status)
+        .await
+        .map(|roster| HttpResponse::Ok().json(roster))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_access::course::{post_new_course_db, set_course_status_db};
+    use crate::db_access::student::post_new_student_db;
+    use crate::handlers::course::update_course_details;
+    use crate::models::audit::AuditContext;
+    use crate::models::course::{CreateCourse, UpdateCourse};
+    use crate::models::student::CreateStudent;
+    use crate::negotiation::MediaType;
+    use actix_web::{http::StatusCode, error::ResponseError};
+    use dotenv::dotenv;
+    use futures_util::future::join_all;
+    use sqlx::postgres::PgPoolOptions;
+    use std::env;
+    use std::sync::Mutex;
+
+    async fn test_app_state() -> web::Data<AppState> {
+        dotenv().ok();
+        let db_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set.");
+        let db_pool = PgPoolOptions::new().connect(&db_url).await.unwrap();
+        web::Data::new(AppState {
+            health_check_response: "".to_string(),
+            visit_count: Mutex::new(0),
+            db: db_pool,
+            certificates: Default::default(),
+        })
+    }
+
+    //a published course of teacher 1 with this capacity, and that many new students
+    async fn course_with_students(app_state: &AppState, capacity: Option<i32>, students: usize) -> (i32, Vec<i32>) {
+        let course = post_new_course_db(&app_state.db, CreateCourse {
+            teacher_id: 1,
+            name: "waitlist test".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+            capacity,
+        })
+        .await
+        .unwrap();
+        let mut conn = app_state.db.acquire().await.unwrap();
+        set_course_status_db(&mut conn, 1, course.id, CourseStatus::Published).await.unwrap();
+
+        let mut student_ids = vec![];
+        for _ in 0..students {
+            let student = post_new_student_db(&app_state.db, CreateStudent {
+                name: "waitlist test".into(),
+                email: format!("{}@example.org", uuid::Uuid::new_v4()),
+            })
+            .await
+            .unwrap();
+            student_ids.push(student.id);
+        }
+        (course.id, student_ids)
+    }
+
+    async fn enroll(app_state: &web::Data<AppState>, course_id: i32, student_id: i32, waitlist: Option<bool>) -> Result<HttpResponse, MyError> {
+        enroll_in_course(app_state.clone(), web::Path::from((1, course_id)), web::Json(EnrollmentRequest { student_id, waitlist })).await
+    }
+
+    async fn status_of(app_state: &AppState, course_id: i32, student_id: i32) -> Option<(EnrollmentStatus, Option<i64>)> {
+        get_enrollment_db(&app_state.db, course_id, student_id)
+            .await
+            .unwrap()
+            .map(|enrollment| (enrollment.status, enrollment.waitlist_position))
+    }
+
+    async fn set_capacity(app_state: &web::Data<AppState>, course_id: i32, capacity: Option<i32>) {
+        let update = UpdateCourse {
+            name: None,
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+            capacity: Some(capacity),
+        };
+        let ctx = AuditContext { actor: "test".into(), request_id: "test-request".into() };
+        update_course_details(app_state.clone(), web::Json(update), web::Path::from((1, course_id)), MediaType::Json, Viewer { teacher_id: Some(1) }, ctx)
+            .await
+            .unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn full_course_waitlists_or_refuses() {
+        let app_state = test_app_state().await;
+        let (course_id, students) = course_with_students(&app_state, Some(1), 3).await;
+
+        enroll(&app_state, course_id, students[0], None).await.unwrap();
+        enroll(&app_state, course_id, students[1], None).await.unwrap();
+        let refused = enroll(&app_state, course_id, students[2], Some(false)).await.unwrap_err();
+        assert_eq!(refused.status_code(), StatusCode::CONFLICT);
+
+        assert_eq!(status_of(&app_state, course_id, students[0]).await, Some((EnrollmentStatus::Active, None)));
+        assert_eq!(status_of(&app_state, course_id, students[1]).await, Some((EnrollmentStatus::Waitlisted, Some(1))));
+        assert_eq!(status_of(&app_state, course_id, students[2]).await, None);
+    }
+
+    #[actix_rt::test]
+    async fn withdrawal_promotes_the_next_waitlisted_student() {
+        let app_state = test_app_state().await;
+        let (course_id, students) = course_with_students(&app_state, Some(1), 3).await;
+        for &student_id in &students {
+            enroll(&app_state, course_id, student_id, None).await.unwrap();
+        }
+
+        withdraw_from_course(app_state.clone(), web::Path::from((1, course_id, students[0]))).await.unwrap();
+        assert_eq!(status_of(&app_state, course_id, students[1]).await, Some((EnrollmentStatus::Active, None)));
+        assert_eq!(status_of(&app_state, course_id, students[2]).await, Some((EnrollmentStatus::Waitlisted, Some(1))));
+
+        //a waitlisted student leaving frees no seat
+        withdraw_from_course(app_state.clone(), web::Path::from((1, course_id, students[2]))).await.unwrap();
+        let (active, waitlisted) = count_enrollments_db(&mut app_state.db.acquire().await.unwrap(), course_id).await.unwrap();
+        assert_eq!((active, waitlisted), (1, 0));
+    }
+
+    #[actix_rt::test]
+    async fn more_or_no_capacity_promotes_the_waitlist() {
+        let app_state = test_app_state().await;
+        let (course_id, students) = course_with_students(&app_state, Some(1), 4).await;
+        for &student_id in &students {
+            enroll(&app_state, course_id, student_id, None).await.unwrap();
+        }
+
+        set_capacity(&app_state, course_id, Some(2)).await;
+        assert_eq!(status_of(&app_state, course_id, students[1]).await, Some((EnrollmentStatus::Active, None)));
+        assert_eq!(status_of(&app_state, course_id, students[2]).await, Some((EnrollmentStatus::Waitlisted, Some(1))));
+
+        //no capacity at all releases the whole waitlist
+        set_capacity(&app_state, course_id, None).await;
+        for &student_id in &students {
+            assert_eq!(status_of(&app_state, course_id, student_id).await, Some((EnrollmentStatus::Active, None)));
+        }
+    }
+
+    #[actix_rt::test]
+    async fn concurrent_enrollments_never_exceed_capacity() {
+        let app_state = test_app_state().await;
+        let (course_id, students) = course_with_students(&app_state, Some(3), 8).await;
+
+        let results = join_all(students.iter().map(|&student_id| enroll(&app_state, course_id, student_id, Some(false)))).await;
+        let seated = results.iter().filter(|result| result.is_ok()).count();
+        assert_eq!(seated, 3);
+        assert!(results
+            .iter()
+            .filter_map(|result| result.as_ref().err())
+            .all(|err| err.status_code() == StatusCode::CONFLICT));
+
+        let (active, waitlisted) = count_enrollments_db(&mut app_state.db.acquire().await.unwrap(), course_id).await.unwrap();
+        assert_eq!((active, waitlisted), (3, 0));
+    }
+}
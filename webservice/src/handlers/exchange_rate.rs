@@ -0,0 +1,29 @@
+use crate::state::AppState;
+use actix_web::{web, HttpRequest, HttpResponse};
+use crate::db_access::exchange_rate::*;
+use crate::errors::MyError;
+use crate::models::admin::Admin;
+use crate::models::exchange_rate::NewExchangeRate;
+use crate::negotiation::decode_rows;
+
+
+pub async fn get_exchange_rates(app_state: web::Data<AppState>) -> Result<HttpResponse, MyError> {
+    get_exchange_rates_db(&app_state.db)
+        .await
+        .map(|rates| HttpResponse::Ok().json(rates))
+}
+
+//PUT /exchange-rates with X-Admin-Token
+//body is CSV (text/csv) or JSON lines (application/x-ndjson) of base,quote,rate[,as_of]
+//and replaces every stored rate
+pub async fn replace_exchange_rates(
+    req: HttpRequest,
+    body: web::Bytes,
+    app_state: web::Data<AppState>,
+    _admin: Admin,
+) -> Result<HttpResponse, MyError> {
+    let rates = NewExchangeRate::validate_rows(decode_rows(&req, &body)?)?;
+    replace_exchange_rates_db(&app_state.db, rates)
+        .await
+        .map(|rates| HttpResponse::Ok().json(rates))
+}
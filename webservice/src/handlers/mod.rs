@@ -1,2 +1,13 @@
+pub mod certificate;
 pub mod course;
-pub mod general;
\ No newline at end of file
+pub mod enrollment;
+pub mod exchange_rate;
+pub mod general;
+pub mod outline;
+pub mod prerequisite;
+pub mod progress;
+pub mod quiz;
+pub mod review;
+pub mod student;
+pub mod taxonomy;
+pub mod translation;
\ No newline at end of file
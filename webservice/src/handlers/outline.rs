@@ -0,0 +1,203 @@
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use crate::db_access::course::{get_course_details_db, lock_course_db};
+use crate::db_access::outline::*;
+use crate::handlers::progress::complete_finished_enrollments;
+use crate::errors::MyError;
+use crate::models::course::Viewer;
+use crate::models::outline::{CreateLesson, CreateModule, Reorder, UpdateLesson, UpdateModule};
+use sqlx::postgres::PgConnection;
+
+
+//outline edits are the course teacher's, and lock the course row so two edits of the same course never interleave positions
+async fn lock_outline_db(conn: &mut PgConnection, viewer: &Viewer, teacher_id: i32, course_id: i32) -> Result<(), MyError> {
+    viewer.require_owner(teacher_id, "change its outline")?;
+    lock_course_db(conn, teacher_id, course_id)
+        .await?
+        .map(|_course| ())
+        .ok_or_else(|| MyError::NotFound("Course id not found".into()))
+}
+
+//GET /courses/{teacher_id}/{course_id}/modules, every module with its lessons
+pub async fn get_course_modules(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, viewer.owns(teacher_id)).await?;
+    let mut conn = app_state.db.acquire().await?;
+    let outline = get_outline_db(&mut conn, course_id).await?;
+    Ok(HttpResponse::Ok().json(outline))
+}
+
+pub async fn post_new_module(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    new_module: web::Json<CreateModule>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    let new_module = new_module.into_inner();
+    new_module.validate()?;
+    let mut tx = app_state.db.begin().await?;
+    lock_outline_db(&mut tx, &viewer, teacher_id, course_id).await?;
+    let module = insert_module_db(&mut tx, course_id, new_module).await?;
+    touch_course_db(&mut tx, course_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(module))
+}
+
+//PUT /courses/{teacher_id}/{course_id}/modules/reorder
+//all modules move in one transaction, the outline is returned in its new order
+pub async fn reorder_modules(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    reorder: web::Json<Reorder>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    let mut tx = app_state.db.begin().await?;
+    lock_outline_db(&mut tx, &viewer, teacher_id, course_id).await?;
+    reorder.validate(&get_module_ids_db(&mut tx, course_id).await?)?;
+    reorder_modules_db(&mut tx, course_id, &reorder.ids).await?;
+    touch_course_db(&mut tx, course_id).await?;
+    let outline = get_outline_db(&mut tx, course_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(outline))
+}
+
+//GET /courses/{teacher_id}/{course_id}/modules/{module_id}, the module with its lessons
+pub async fn get_module_detail(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, module_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, viewer.owns(teacher_id)).await?;
+    let mut conn = app_state.db.acquire().await?;
+    get_module_outline_db(&mut conn, course_id, module_id)
+        .await
+        .map(|module| HttpResponse::Ok().json(module))
+}
+
+pub async fn update_module(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    update: web::Json<UpdateModule>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, module_id) = params.into_inner();
+    let update = update.into_inner();
+    update.validate()?;
+    let mut tx = app_state.db.begin().await?;
+    lock_outline_db(&mut tx, &viewer, teacher_id, course_id).await?;
+    let module = update_module_db(&mut tx, course_id, module_id, update).await?;
+    touch_course_db(&mut tx, course_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(module))
+}
+
+//deletes the module and its lessons, returns the remaining outline
+pub async fn delete_module(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, module_id) = params.into_inner();
+    let mut tx = app_state.db.begin().await?;
+    lock_outline_db(&mut tx, &viewer, teacher_id, course_id).await?;
+    delete_module_db(&mut tx, course_id, module_id).await?;
+    complete_finished_enrollments(&mut tx, &app_state.certificates, teacher_id, course_id).await?;
+    touch_course_db(&mut tx, course_id).await?;
+    let outline = get_outline_db(&mut tx, course_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(outline))
+}
+
+pub async fn post_new_lesson(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    new_lesson: web::Json<CreateLesson>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, module_id) = params.into_inner();
+    let new_lesson = new_lesson.into_inner();
+    new_lesson.validate()?;
+    let mut tx = app_state.db.begin().await?;
+    lock_outline_db(&mut tx, &viewer, teacher_id, course_id).await?;
+    get_module_db(&mut tx, course_id, module_id).await?;
+    let lesson = insert_lesson_db(&mut tx, module_id, new_lesson).await?;
+    touch_course_db(&mut tx, course_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(lesson))
+}
+
+//PUT .../modules/{module_id}/lessons/reorder, returns the module with its lessons in the new order
+pub async fn reorder_lessons(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    reorder: web::Json<Reorder>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, module_id) = params.into_inner();
+    let mut tx = app_state.db.begin().await?;
+    lock_outline_db(&mut tx, &viewer, teacher_id, course_id).await?;
+    get_module_db(&mut tx, course_id, module_id).await?;
+    reorder.validate(&get_lesson_ids_db(&mut tx, module_id).await?)?;
+    reorder_lessons_db(&mut tx, module_id, &reorder.ids).await?;
+    touch_course_db(&mut tx, course_id).await?;
+    let module = get_module_outline_db(&mut tx, course_id, module_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(module))
+}
+
+pub async fn get_lesson_detail(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, module_id, lesson_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, viewer.owns(teacher_id)).await?;
+    get_module_db(&app_state.db, course_id, module_id).await?;
+    get_lesson_db(&app_state.db, module_id, lesson_id)
+        .await
+        .map(|lesson| HttpResponse::Ok().json(lesson))
+}
+
+pub async fn update_lesson(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32, i32)>,
+    update: web::Json<UpdateLesson>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, module_id, lesson_id) = params.into_inner();
+    let update = update.into_inner();
+    update.validate()?;
+    let mut tx = app_state.db.begin().await?;
+    lock_outline_db(&mut tx, &viewer, teacher_id, course_id).await?;
+    get_module_db(&mut tx, course_id, module_id).await?;
+    let lesson = update_lesson_db(&mut tx, module_id, lesson_id, update).await?;
+    complete_finished_enrollments(&mut tx, &app_state.certificates, teacher_id, course_id).await?;
+    touch_course_db(&mut tx, course_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(lesson))
+}
+
+//deletes the lesson, returns its module with the remaining lessons
+pub async fn delete_lesson(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, module_id, lesson_id) = params.into_inner();
+    let mut tx = app_state.db.begin().await?;
+    lock_outline_db(&mut tx, &viewer, teacher_id, course_id).await?;
+    get_module_db(&mut tx, course_id, module_id).await?;
+    delete_lesson_db(&mut tx, module_id, lesson_id).await?;
+    complete_finished_enrollments(&mut tx, &app_state.certificates, teacher_id, course_id).await?;
+    touch_course_db(&mut tx, course_id).await?;
+    let module = get_module_outline_db(&mut tx, course_id, module_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(module))
+}
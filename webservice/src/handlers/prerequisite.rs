@@ -0,0 +1,128 @@
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use crate::db_access::course::{get_course_details_db, lock_course_db};
+use crate::db_access::outline::touch_course_db;
+use crate::db_access::prerequisite::*;
+use crate::db_access::student::get_student_db;
+use crate::errors::MyError;
+use crate::models::course::{CourseStatus, Viewer};
+use crate::models::prerequisite::*;
+
+
+pub async fn get_course_prerequisites(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, viewer.owns(teacher_id)).await?;
+    let mut prerequisites = get_prerequisites_db(&app_state.db, course_id).await?;
+    prerequisites.retain(|course| course.visible_to(&viewer));
+    Ok(HttpResponse::Ok().json(prerequisites))
+}
+
+/**
+PUT /courses/{teacher_id}/{course_id}/prerequisites, by the course's teacher
+a prerequisite can be any live course of the same teacher, or a published course of another one
+a change that would make a course require itself, directly or through others, is refused with 409
+**/
+pub async fn set_course_prerequisites(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    prerequisites: web::Json<SetPrerequisites>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "change its prerequisites")?;
+    let prerequisite_ids = prerequisites.into_inner().validate(course_id)?;
+    let mut tx = app_state.db.begin().await?;
+    lock_course_db(&mut tx, teacher_id, course_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Course Id not found".into()))?;
+    lock_prerequisites_db(&mut tx).await?;
+
+    let courses = get_prerequisite_courses_db(&mut tx, &prerequisite_ids).await?;
+    let usable = courses
+        .iter()
+        .filter(|course| course.teacher_id == teacher_id || course.status == CourseStatus::Published)
+        .count();
+    if usable != prerequisite_ids.len() {
+        return Err(MyError::NotFound("Prerequisite course not found".into()));
+    }
+
+    let graph = PrerequisiteGraph::new(get_prerequisite_edges_db(&mut tx, &prerequisite_ids).await?)
+        .with_prerequisites(course_id, &prerequisite_ids);
+    if let Some(cycle) = graph.find_cycle(course_id) {
+        let cycle: Vec<String> = cycle.iter().map(|id| id.to_string()).collect();
+        return Err(MyError::Conflict(format!(
+            "Prerequisites cannot form a cycle, course {} would require itself",
+            cycle.join(" -> ")
+        )));
+    }
+
+    let prerequisites = set_prerequisites_db(&mut tx, course_id, &prerequisite_ids).await?;
+    touch_course_db(&mut tx, course_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(prerequisites))
+}
+
+//GET /courses/{teacher_id}/{course_id}/prerequisites/chain, everything to take first, in an order it can be taken
+pub async fn get_prerequisite_chain(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, viewer.owns(teacher_id)).await?;
+    let mut conn = app_state.db.acquire().await?;
+    let order = PrerequisiteGraph::new(get_prerequisite_edges_db(&mut conn, &[course_id]).await?).chain(course_id);
+    let mut courses = get_prerequisite_courses_db(&mut conn, &order).await?;
+    courses.retain(|course| course.visible_to(&viewer));
+    courses.sort_by_key(|course| order.iter().position(|id| *id == course.id));
+    Ok(HttpResponse::Ok().json(courses))
+}
+
+pub async fn get_prerequisite_overrides(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "manage prerequisite overrides")?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    get_overrides_db(&app_state.db, course_id)
+        .await
+        .map(|overrides| HttpResponse::Ok().json(overrides))
+}
+
+//POST /courses/{teacher_id}/{course_id}/prerequisites/overrides, lets a student enroll without the prerequisites
+pub async fn grant_prerequisite_override(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    grant: web::Json<GrantOverride>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "manage prerequisite overrides")?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    let mut tx = app_state.db.begin().await?;
+    get_student_db(&mut tx, grant.student_id).await?;
+    let overrides = grant_override_db(&mut tx, course_id, grant.student_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(overrides))
+}
+
+//DELETE /courses/{teacher_id}/{course_id}/prerequisites/overrides/{student_id}
+pub async fn revoke_prerequisite_override(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, student_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "manage prerequisite overrides")?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    let mut tx = app_state.db.begin().await?;
+    let overrides = revoke_override_db(&mut tx, course_id, student_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(overrides))
+}
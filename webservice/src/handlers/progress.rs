@@ -0,0 +1,230 @@
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use crate::db_access::course::get_course_details_db;
+use crate::db_access::enrollment::{get_enrollment_db, lock_enrollment_db};
+use crate::db_access::progress::*;
+use crate::handlers::certificate::issue_certificate;
+use crate::errors::MyError;
+use crate::models::certificate::CertificateSettings;
+use crate::models::course::Viewer;
+use crate::models::enrollment::{Enrollment, EnrollmentStatus};
+use crate::models::progress::{CourseProgress, ProgressCounts};
+use sqlx::postgres::PgConnection;
+
+
+//what a progress update needs: the locked enrollment of a seated student and a lesson of the course
+async fn progress_target_db(
+    conn: &mut PgConnection,
+    course_id: i32,
+    student_id: i32,
+    lesson_id: i32,
+) -> Result<Enrollment, MyError> {
+    get_course_lesson_db(&mut *conn, course_id, lesson_id).await?;
+    let enrollment = lock_enrollment_db(conn, course_id, student_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Enrollment not found".into()))?;
+    match enrollment.status {
+        EnrollmentStatus::Active | EnrollmentStatus::Completed => Ok(enrollment),
+        _ => Err(MyError::Conflict("Only enrolled students can record progress".into())),
+    }
+}
+
+//after an outline edit, completes the enrollments it left with nothing required to do and certifies them
+pub async fn complete_finished_enrollments(
+    conn: &mut PgConnection,
+    settings: &CertificateSettings,
+    teacher_id: i32,
+    course_id: i32,
+) -> Result<(), MyError> {
+    for enrollment_id in complete_finished_enrollments_db(&mut *conn, course_id).await? {
+        issue_certificate(&mut *conn, settings, teacher_id, enrollment_id).await?;
+    }
+    Ok(())
+}
+
+//POST /courses/{teacher_id}/{course_id}/enrollments/{student_id}/lessons/{lesson_id}/start
+pub async fn start_lesson(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32, i32)>,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, student_id, lesson_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    let mut tx = app_state.db.begin().await?;
+    let enrollment = progress_target_db(&mut tx, course_id, student_id, lesson_id).await?;
+    start_lesson_db(&mut tx, enrollment.id, lesson_id).await?;
+    let lessons = get_lesson_progress_db(&mut tx, course_id, enrollment.id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(CourseProgress::new(&enrollment, lessons)))
+}
+
+//POST .../lessons/{lesson_id}/complete
+//completing the last required lesson flips the enrollment to completed and issues its certificate, in the same transaction
+//the certificate's PDF is only rendered on its first download
+pub async fn complete_lesson(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32, i32)>,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, student_id, lesson_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    let mut tx = app_state.db.begin().await?;
+    let mut enrollment = progress_target_db(&mut tx, course_id, student_id, lesson_id).await?;
+    complete_lesson_db(&mut tx, enrollment.id, lesson_id).await?;
+    let lessons = get_lesson_progress_db(&mut tx, course_id, enrollment.id).await?;
+    if ProgressCounts::from_lessons(&lessons).is_complete() && complete_enrollment_db(&mut tx, enrollment.id).await? {
+        enrollment = get_enrollment_db(&mut tx, course_id, student_id)
+            .await?
+            .ok_or_else(|| MyError::NotFound("Enrollment not found".into()))?;
+        issue_certificate(&mut tx, &app_state.certificates, teacher_id, enrollment.id).await?;
+    }
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(CourseProgress::new(&enrollment, lessons)))
+}
+
+//GET /courses/{teacher_id}/{course_id}/enrollments/{student_id}/progress
+pub async fn get_student_progress(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, student_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "see its students' progress")?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    let enrollment = get_enrollment_db(&app_state.db, course_id, student_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Enrollment not found".into()))?;
+    let lessons = get_lesson_progress_db(&app_state.db, course_id, enrollment.id).await?;
+    Ok(HttpResponse::Ok().json(CourseProgress::new(&enrollment, lessons)))
+}
+
+//GET /courses/{teacher_id}/{course_id}/progress, the seated students with the most progress first
+pub async fn get_course_progress(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "see its students' progress")?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    get_roster_progress_db(&app_state.db, course_id)
+        .await
+        .map(|roster| HttpResponse::Ok().json(roster))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_access::course::{post_new_course_db, set_course_status_db};
+    use crate::db_access::outline::{insert_lesson_db, insert_module_db};
+    use crate::db_access::student::post_new_student_db;
+    use crate::handlers::enrollment::enroll_in_course;
+    use crate::handlers::outline::{delete_lesson, update_lesson};
+    use crate::models::course::{CourseStatus, CreateCourse};
+    use crate::models::enrollment::EnrollmentRequest;
+    use crate::models::outline::{CreateLesson, CreateModule, UpdateLesson};
+    use crate::models::student::CreateStudent;
+    use actix_web::{http::StatusCode, error::ResponseError};
+    use dotenv::dotenv;
+    use sqlx::postgres::PgPoolOptions;
+    use std::env;
+    use std::sync::Mutex;
+
+    async fn test_app_state() -> web::Data<AppState> {
+        dotenv().ok();
+        let db_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set.");
+        let db_pool = PgPoolOptions::new().connect(&db_url).await.unwrap();
+        web::Data::new(AppState {
+            health_check_response: "".to_string(),
+            visit_count: Mutex::new(0),
+            db: db_pool,
+            certificates: Default::default(),
+        })
+    }
+
+    //a published course of teacher 1 with one module of three required lessons, and an enrolled student
+    async fn course_with_lessons(app_state: &web::Data<AppState>) -> (i32, i32, Vec<i32>, i32) {
+        let course = post_new_course_db(&app_state.db, CreateCourse {
+            teacher_id: 1,
+            name: "progress test".into(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: None,
+            language: None,
+            level: None,
+            capacity: None,
+        })
+        .await
+        .unwrap();
+        let mut conn = app_state.db.acquire().await.unwrap();
+        set_course_status_db(&mut conn, 1, course.id, CourseStatus::Published).await.unwrap();
+        let module = insert_module_db(&mut conn, course.id, CreateModule { title: "module".into(), description: None, position: None })
+            .await
+            .unwrap();
+        let mut lesson_ids = vec![];
+        for _ in 0..3 {
+            let lesson = insert_lesson_db(&mut conn, module.id, CreateLesson {
+                title: "lesson".into(),
+                content: None,
+                duration_minutes: None,
+                required: None,
+                position: None,
+            })
+            .await
+            .unwrap();
+            lesson_ids.push(lesson.id);
+        }
+        let student = post_new_student_db(&app_state.db, CreateStudent {
+            name: "progress test".into(),
+            email: format!("{}@example.org", uuid::Uuid::new_v4()),
+        })
+        .await
+        .unwrap();
+        enroll_in_course(app_state.clone(), web::Path::from((1, course.id)), web::Json(EnrollmentRequest { student_id: student.id, waitlist: None }))
+            .await
+            .unwrap();
+        (course.id, module.id, lesson_ids, student.id)
+    }
+
+    fn owner() -> Viewer {
+        Viewer { teacher_id: Some(1) }
+    }
+
+    async fn status_of(app_state: &AppState, course_id: i32, student_id: i32) -> EnrollmentStatus {
+        get_enrollment_db(&app_state.db, course_id, student_id).await.unwrap().unwrap().status
+    }
+
+    #[actix_rt::test]
+    async fn outline_edits_complete_enrollments_with_nothing_left_to_do() {
+        let app_state = test_app_state().await;
+        let (course_id, module_id, lesson_ids, student_id) = course_with_lessons(&app_state).await;
+        complete_lesson(app_state.clone(), web::Path::from((1, course_id, student_id, lesson_ids[0]))).await.unwrap();
+        assert_eq!(status_of(&app_state, course_id, student_id).await, EnrollmentStatus::Active);
+
+        let optional = UpdateLesson { title: None, content: None, duration_minutes: None, required: Some(false) };
+        update_lesson(app_state.clone(), web::Path::from((1, course_id, module_id, lesson_ids[1])), web::Json(optional), owner())
+            .await
+            .unwrap();
+        assert_eq!(status_of(&app_state, course_id, student_id).await, EnrollmentStatus::Active);
+
+        let lesson = || web::Path::from((1, course_id, module_id, lesson_ids[2]));
+        let err = delete_lesson(app_state.clone(), lesson(), Viewer::default()).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::FORBIDDEN);
+        assert_eq!(status_of(&app_state, course_id, student_id).await, EnrollmentStatus::Active);
+
+        delete_lesson(app_state.clone(), lesson(), owner()).await.unwrap();
+        assert_eq!(status_of(&app_state, course_id, student_id).await, EnrollmentStatus::Completed);
+    }
+
+    #[actix_rt::test]
+    async fn only_the_teacher_sees_a_students_progress() {
+        let app_state = test_app_state().await;
+        let (course_id, _, _, student_id) = course_with_lessons(&app_state).await;
+        let params = || web::Path::from((1, course_id, student_id));
+
+        let err = get_student_progress(app_state.clone(), params(), Viewer::default()).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::FORBIDDEN);
+        let resp = get_student_progress(app_state.clone(), params(), owner()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use crate::db_access::course::get_course_details_db;
+use crate::db_access::enrollment::lock_enrollment_db;
+use crate::db_access::progress::get_course_lesson_db;
+use crate::db_access::quiz::*;
+use crate::errors::MyError;
+use crate::models::course::Viewer;
+use crate::models::enrollment::EnrollmentStatus;
+use crate::models::quiz::*;
+
+
+//POST /courses/{teacher_id}/{course_id}/quizzes, the quiz with its questions in one transaction
+pub async fn post_new_quiz(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    new_quiz: web::Json<CreateQuiz>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "add quizzes")?;
+    let new_quiz = new_quiz.into_inner();
+    new_quiz.validate()?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    if let Some(lesson_id) = new_quiz.lesson_id {
+        get_course_lesson_db(&app_state.db, course_id, lesson_id).await?;
+    }
+    let mut tx = app_state.db.begin().await?;
+    let quiz = post_new_quiz_db(&mut tx, course_id, new_quiz).await?;
+    let questions = get_questions_db(&mut tx, quiz.id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(QuizDetail { quiz, questions: QuestionView::list(&questions, true) }))
+}
+
+pub async fn get_course_quizzes(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, viewer.owns(teacher_id)).await?;
+    get_quizzes_db(&app_state.db, course_id)
+        .await
+        .map(|quizzes| HttpResponse::Ok().json(quizzes))
+}
+
+//GET /courses/{teacher_id}/{course_id}/quizzes/{quiz_id}, the answer key only for the course's teacher
+pub async fn get_quiz_detail(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, quiz_id) = params.into_inner();
+    let owner = viewer.owns(teacher_id);
+    get_course_details_db(&app_state.db, teacher_id, course_id, owner).await?;
+    let quiz = get_quiz_db(&app_state.db, course_id, quiz_id).await?;
+    let questions = get_questions_db(&app_state.db, quiz_id).await?;
+    Ok(HttpResponse::Ok().json(QuizDetail { quiz, questions: QuestionView::list(&questions, owner) }))
+}
+
+pub async fn update_quiz(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    update: web::Json<UpdateQuiz>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, quiz_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "change its quizzes")?;
+    let update = update.into_inner();
+    update.validate()?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    update_quiz_db(&app_state.db, course_id, quiz_id, update)
+        .await
+        .map(|quiz| HttpResponse::Ok().json(quiz))
+}
+
+//the quizzes left in the course are returned
+pub async fn delete_quiz(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, quiz_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "delete its quizzes")?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    delete_quiz_db(&app_state.db, course_id, quiz_id).await?;
+    get_quizzes_db(&app_state.db, course_id)
+        .await
+        .map(|quizzes| HttpResponse::Ok().json(quizzes))
+}
+
+/**
+POST /courses/{teacher_id}/{course_id}/quizzes/{quiz_id}/attempts
+the enrollment is locked, so two starts of the same student are counted one after the other
+an attempt whose time ran out is expired first and counts towards max_attempts
+**/
+pub async fn start_quiz_attempt(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    start: web::Json<StartAttempt>,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, quiz_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    let mut tx = app_state.db.begin().await?;
+    let quiz = get_quiz_db(&mut tx, course_id, quiz_id).await?;
+    let enrollment = lock_enrollment_db(&mut tx, course_id, start.student_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Enrollment not found".into()))?;
+    if !matches!(enrollment.status, EnrollmentStatus::Active | EnrollmentStatus::Completed) {
+        return Err(MyError::Conflict("Only enrolled students can take quizzes".into()));
+    }
+    expire_overdue_attempts_db(&mut tx, quiz.id, Some(enrollment.id)).await?;
+    let attempts = get_attempts_db(&mut tx, quiz.id, Some(enrollment.id)).await?;
+    if attempts.iter().any(|attempt| attempt.status == AttemptStatus::InProgress) {
+        return Err(MyError::Conflict("An attempt of this quiz is already in progress".into()));
+    }
+    if quiz.max_attempts.is_some_and(|max| attempts.len() as i32 >= max) {
+        return Err(MyError::Conflict("No attempts left for this quiz".into()));
+    }
+    let questions = get_questions_db(&mut tx, quiz.id).await?;
+    let max_score = questions.iter().map(|question| question.points).sum();
+    let attempt = start_attempt_db(&mut tx, &quiz, enrollment.id, attempts.len() as i32 + 1, max_score).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(AttemptView { attempt, questions: QuestionView::list(&questions, false) }))
+}
+
+//GET .../quizzes/{quiz_id}/attempts, every attempt of every student
+pub async fn get_quiz_attempts(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, quiz_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "see the attempts of a quiz")?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    get_quiz_db(&app_state.db, course_id, quiz_id).await?;
+    expire_overdue_attempts_db(&app_state.db, quiz_id, None).await?;
+    get_attempts_db(&app_state.db, quiz_id, None)
+        .await
+        .map(|attempts| HttpResponse::Ok().json(attempts))
+}
+
+//GET .../quizzes/{quiz_id}/attempts/{attempt_id}, the attempt with its graded answers
+pub async fn get_attempt_detail(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, quiz_id, attempt_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "see a student's answers")?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    get_quiz_db(&app_state.db, course_id, quiz_id).await?;
+    let attempt = get_attempt_db(&app_state.db, quiz_id, attempt_id).await?;
+    let answers = get_answers_db(&app_state.db, attempt_id).await?;
+    Ok(HttpResponse::Ok().json(AttemptResult { attempt, answers }))
+}
+
+/**
+POST .../quizzes/{quiz_id}/attempts/{attempt_id}/submit
+objective questions are graded right away, short text questions without accepted answers wait for the teacher
+a submission after the deadline expires the attempt and is refused
+**/
+pub async fn submit_quiz_attempt(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32, i32)>,
+    submission: web::Json<SubmitAttempt>,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, quiz_id, attempt_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    let mut tx = app_state.db.begin().await?;
+    get_quiz_db(&mut tx, course_id, quiz_id).await?;
+    let attempt = lock_attempt_db(&mut tx, quiz_id, attempt_id).await?;
+    if attempt.status != AttemptStatus::InProgress {
+        return Err(MyError::Conflict("This attempt is no longer in progress".into()));
+    }
+    if expire_attempt_db(&mut tx, attempt_id).await? {
+        tx.commit().await?;
+        return Err(MyError::Conflict("The time limit of this attempt has expired".into()));
+    }
+    let questions = get_questions_db(&mut tx, quiz_id).await?;
+    let answers = QuizAnswer::grade_all(&questions, submission.into_inner().answers)?;
+    submit_attempt_db(&mut tx, attempt_id, &answers).await?;
+    let attempt = get_attempt_db(&mut tx, quiz_id, attempt_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(AttemptResult { attempt, answers }))
+}
+
+//PUT .../attempts/{attempt_id}/answers/{question_id}, full points mark the answer correct
+pub async fn grade_quiz_answer(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32, i32, i32)>,
+    grade: web::Json<GradeAnswer>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, quiz_id, attempt_id, question_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "grade answers")?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    let mut tx = app_state.db.begin().await?;
+    get_quiz_db(&mut tx, course_id, quiz_id).await?;
+    let attempt = lock_attempt_db(&mut tx, quiz_id, attempt_id).await?;
+    if attempt.status != AttemptStatus::Submitted {
+        return Err(MyError::Conflict("Only submitted attempts can be graded".into()));
+    }
+    let question = get_questions_db(&mut tx, quiz_id)
+        .await?
+        .into_iter()
+        .find(|question| question.id == question_id)
+        .ok_or_else(|| MyError::NotFound("Question id not found".into()))?;
+    if !(0..=question.points).contains(&grade.points_awarded) {
+        return Err(MyError::InvalidInput(format!("points_awarded must be 0 to {}", question.points)));
+    }
+    grade_answer_db(&mut tx, attempt_id, question_id, grade.points_awarded, grade.points_awarded == question.points).await?;
+    let attempt = get_attempt_db(&mut tx, quiz_id, attempt_id).await?;
+    let answers = get_answers_db(&mut tx, attempt_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(AttemptResult { attempt, answers }))
+}
+
+//GET .../quizzes/{quiz_id}/stats, per question over the submitted attempts
+pub async fn get_quiz_stats(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, quiz_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "see quiz statistics")?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    get_quiz_db(&app_state.db, course_id, quiz_id).await?;
+    let questions = get_questions_db(&app_state.db, quiz_id).await?;
+    let answers = get_submitted_answers_db(&app_state.db, quiz_id).await?;
+    Ok(HttpResponse::Ok().json(QuestionStats::compute(&questions, &answers)))
+}
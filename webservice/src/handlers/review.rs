@@ -0,0 +1,127 @@
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use crate::db_access::course::get_course_details_db;
+use crate::db_access::enrollment::get_enrollment_db;
+use crate::db_access::review::*;
+use crate::db_access::student::get_student_db;
+use crate::errors::MyError;
+use crate::models::course::Viewer;
+use crate::models::enrollment::EnrollmentStatus;
+use crate::models::review::*;
+
+
+//GET /courses/{teacher_id}/{course_id}/reviews?sort=helpful&page=1&per_page=20
+pub async fn get_course_reviews(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    query: web::Query<ReviewQuery>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    let (page, per_page) = query.pagination()?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, viewer.owns(teacher_id)).await?;
+    let (reviews, total) = get_reviews_db(&app_state.db, course_id, query.sort.unwrap_or_default(), page, per_page).await?;
+    Ok(HttpResponse::Ok().json(ReviewPage { page, per_page, total, reviews }))
+}
+
+pub async fn get_review_detail(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, review_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, viewer.owns(teacher_id)).await?;
+    get_review_db(&app_state.db, course_id, review_id)
+        .await
+        .map(|review| HttpResponse::Ok().json(review))
+}
+
+//POST /courses/{teacher_id}/{course_id}/reviews, by a student who holds a seat or completed the course
+pub async fn post_new_review(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    new_review: web::Json<CreateReview>,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    let new_review = new_review.into_inner().validate()?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    let enrollment = get_enrollment_db(&app_state.db, course_id, new_review.student_id).await?;
+    if !enrollment.is_some_and(|enrollment| matches!(enrollment.status, EnrollmentStatus::Active | EnrollmentStatus::Completed)) {
+        return Err(MyError::Forbidden("Only students enrolled in the course can review it".into()));
+    }
+    let mut tx = app_state.db.begin().await?;
+    let review = insert_review_db(&mut tx, course_id, new_review).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(review))
+}
+
+//PUT /courses/{teacher_id}/{course_id}/reviews/{review_id}, by the student who wrote it
+pub async fn update_review(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    update: web::Json<UpdateReview>,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, review_id) = params.into_inner();
+    let update = update.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    let mut tx = app_state.db.begin().await?;
+    let current = lock_review_db(&mut tx, course_id, review_id).await?;
+    if current.student_id != update.student_id {
+        return Err(MyError::Forbidden("Only the student who wrote a review can edit it".into()));
+    }
+    let (rating, body) = update.apply(&current)?;
+    let review = update_review_db(&mut tx, &current, rating, body).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(review))
+}
+
+//PUT .../reviews/{review_id}/reply, the course's teacher answers a review
+pub async fn reply_to_review(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    reply: web::Json<ReviewReply>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, review_id) = params.into_inner();
+    viewer.require_owner(teacher_id, "reply to its reviews")?;
+    let reply = reply.into_inner().validate()?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, true).await?;
+    reply_to_review_db(&app_state.db, course_id, review_id, reply)
+        .await
+        .map(|review| HttpResponse::Ok().json(review))
+}
+
+//POST .../reviews/{review_id}/helpful, students cannot vote for their own review
+pub async fn mark_review_helpful(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32)>,
+    vote: web::Json<HelpfulVote>,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, review_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, false).await?;
+    get_student_db(&app_state.db, vote.student_id).await?;
+    let mut tx = app_state.db.begin().await?;
+    let review = get_review_db(&mut tx, course_id, review_id).await?;
+    if review.student_id == vote.student_id {
+        return Err(MyError::Conflict("Students cannot vote for their own review".into()));
+    }
+    add_helpful_vote_db(&mut tx, review_id, vote.student_id).await?;
+    let review = get_review_db(&mut tx, course_id, review_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(review))
+}
+
+//DELETE .../reviews/{review_id}/helpful/{student_id}, takes a vote back
+pub async fn unmark_review_helpful(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, i32, i32)>,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, review_id, student_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, false).await?;
+    let mut tx = app_state.db.begin().await?;
+    get_review_db(&mut tx, course_id, review_id).await?;
+    remove_helpful_vote_db(&mut tx, review_id, student_id).await?;
+    let review = get_review_db(&mut tx, course_id, review_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(review))
+}
@@ -0,0 +1,68 @@
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use crate::db_access::enrollment::get_student_enrollments_db;
+use crate::db_access::notification::get_notifications_db;
+use crate::db_access::student::*;
+use crate::errors::MyError;
+use crate::models::enrollment::EnrollmentQuery;
+use crate::models::notification::NotificationQuery;
+use crate::models::student::{CreateStudent, UpdateStudent};
+
+
+pub async fn post_new_student(
+    new_student: web::Json<CreateStudent>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, MyError> {
+    let new_student = new_student.into_inner().validate()?;
+    post_new_student_db(&app_state.db, new_student)
+        .await
+        .map(|student| HttpResponse::Ok().json(student))
+}
+
+pub async fn get_student_detail(
+    app_state: web::Data<AppState>,
+    params: web::Path<i32>,
+) -> Result<HttpResponse, MyError> {
+    let student_id = params.into_inner();
+    get_student_db(&app_state.db, student_id)
+        .await
+        .map(|student| HttpResponse::Ok().json(student))
+}
+
+pub async fn update_student(
+    app_state: web::Data<AppState>,
+    params: web::Path<i32>,
+    update: web::Json<UpdateStudent>,
+) -> Result<HttpResponse, MyError> {
+    let student_id = params.into_inner();
+    let update = update.into_inner().validate()?;
+    update_student_db(&app_state.db, student_id, update)
+        .await
+        .map(|student| HttpResponse::Ok().json(student))
+}
+
+//GET /students/{student_id}/enrollments?status=active
+pub async fn get_student_enrollments(
+    app_state: web::Data<AppState>,
+    params: web::Path<i32>,
+    query: web::Query<EnrollmentQuery>,
+) -> Result<HttpResponse, MyError> {
+    let student_id = params.into_inner();
+    get_student_db(&app_state.db, student_id).await?;
+    get_student_enrollments_db(&app_state.db, student_id, query.status)
+        .await
+        .map(|enrollments| HttpResponse::Ok().json(enrollments))
+}
+
+//GET /students/{student_id}/notifications?after=41, events such as a promotion from a waitlist
+pub async fn get_student_notifications(
+    app_state: web::Data<AppState>,
+    params: web::Path<i32>,
+    query: web::Query<NotificationQuery>,
+) -> Result<HttpResponse, MyError> {
+    let student_id = params.into_inner();
+    get_student_db(&app_state.db, student_id).await?;
+    get_notifications_db(&app_state.db, student_id, query.after)
+        .await
+        .map(|events| HttpResponse::Ok().json(events))
+}
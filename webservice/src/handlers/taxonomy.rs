@@ -0,0 +1,145 @@
+use crate::state::AppState;
+use actix_web::{web, HttpResponse};
+use crate::db_access::course::{get_course_details_db, lock_course_db};
+use crate::db_access::outline::touch_course_db;
+use crate::db_access::taxonomy::*;
+use crate::errors::MyError;
+use crate::models::admin::Admin;
+use crate::models::course::Viewer;
+use crate::models::taxonomy::*;
+
+
+//GET /categories, the whole tree, public
+pub async fn get_category_tree(app_state: web::Data<AppState>) -> Result<HttpResponse, MyError> {
+    get_categories_db(&app_state.db)
+        .await
+        .map(|categories| HttpResponse::Ok().json(CategoryNode::tree(categories)))
+}
+
+pub async fn get_category_detail(
+    app_state: web::Data<AppState>,
+    category_id: web::Path<i32>,
+) -> Result<HttpResponse, MyError> {
+    get_category_db(&app_state.db, category_id.into_inner())
+        .await
+        .map(|category| HttpResponse::Ok().json(category))
+}
+
+//POST /categories with X-Admin-Token
+pub async fn post_new_category(
+    app_state: web::Data<AppState>,
+    new_category: web::Json<CreateCategory>,
+    _admin: Admin,
+) -> Result<HttpResponse, MyError> {
+    let new_category = new_category.into_inner().validate()?;
+    let mut tx = app_state.db.begin().await?;
+    lock_categories_db(&mut tx).await?;
+    let category = insert_category_db(&mut tx, new_category).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(category))
+}
+
+//PUT /categories/{category_id} with X-Admin-Token, moving a category takes its subcategories along
+pub async fn update_category(
+    app_state: web::Data<AppState>,
+    category_id: web::Path<i32>,
+    update: web::Json<UpdateCategory>,
+    _admin: Admin,
+) -> Result<HttpResponse, MyError> {
+    let update = update.into_inner().validate()?;
+    let mut tx = app_state.db.begin().await?;
+    lock_categories_db(&mut tx).await?;
+    let category = update_category_db(&mut tx, category_id.into_inner(), update).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(category))
+}
+
+//DELETE /categories/{category_id} with X-Admin-Token, returns the tree that is left
+pub async fn delete_category(
+    app_state: web::Data<AppState>,
+    category_id: web::Path<i32>,
+    _admin: Admin,
+) -> Result<HttpResponse, MyError> {
+    let mut tx = app_state.db.begin().await?;
+    lock_categories_db(&mut tx).await?;
+    delete_category_db(&mut tx, category_id.into_inner()).await?;
+    let categories = get_categories_db(&mut tx).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(CategoryNode::tree(categories)))
+}
+
+//GET /tags?prefix=ru&limit=10, public
+pub async fn get_tag_suggestions(
+    app_state: web::Data<AppState>,
+    query: web::Query<TagQuery>,
+) -> Result<HttpResponse, MyError> {
+    let (pattern, limit) = query.pattern()?;
+    get_tag_suggestions_db(&app_state.db, &pattern, limit)
+        .await
+        .map(|tags| HttpResponse::Ok().json(tags))
+}
+
+pub async fn get_course_categories(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, viewer.owns(teacher_id)).await?;
+    get_course_categories_db(&app_state.db, course_id)
+        .await
+        .map(|categories| HttpResponse::Ok().json(categories))
+}
+
+pub async fn get_course_tags(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, viewer.owns(teacher_id)).await?;
+    get_course_tags_db(&app_state.db, course_id)
+        .await
+        .map(|tags| HttpResponse::Ok().json(tags))
+}
+
+//PUT /courses/{teacher_id}/{course_id}/categories, moves the course's updated_at
+//the course is locked until commit, so two replacements of its categories or tags do not interleave
+pub async fn set_course_categories(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    categories: web::Json<CourseCategories>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    let category_ids = categories.into_inner().validate()?;
+    viewer.require_owner(teacher_id, "change how it is categorized")?;
+    let mut tx = app_state.db.begin().await?;
+    lock_course_db(&mut tx, teacher_id, course_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Course Id not found".into()))?;
+    let categories = set_course_categories_db(&mut tx, course_id, &category_ids).await?;
+    touch_course_db(&mut tx, course_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(categories))
+}
+
+//PUT /courses/{teacher_id}/{course_id}/tags, moves the course's updated_at
+pub async fn set_course_tags(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    tags: web::Json<CourseTags>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    let tags = tags.into_inner().validate()?;
+    viewer.require_owner(teacher_id, "change how it is categorized")?;
+    let mut tx = app_state.db.begin().await?;
+    lock_course_db(&mut tx, teacher_id, course_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Course Id not found".into()))?;
+    let tags = set_course_tags_db(&mut tx, course_id, &tags).await?;
+    touch_course_db(&mut tx, course_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(tags))
+}
@@ -0,0 +1,97 @@
+use crate::state::AppState;
+use actix_web::{http::header, web, HttpResponse};
+use crate::db_access::course::{get_course_details_db, lock_course_db};
+use crate::db_access::outline::touch_course_db;
+use crate::db_access::translation::*;
+use crate::errors::MyError;
+use crate::models::course::Viewer;
+use crate::models::facet::LanguageTag;
+use crate::models::translation::SaveTranslation;
+
+
+/**
+Content-Language lists the languages the body is in, a list of courses can mix several
+Vary tells caches the body also depends on Accept-Language, next to the Accept the body format depends on
+**/
+pub fn with_content_language(mut response: HttpResponse, locales: &[String]) -> HttpResponse {
+    let headers = response.headers_mut();
+    headers.insert(header::VARY, header::HeaderValue::from_static("Accept, Accept-Language"));
+    if locales.is_empty() {
+        return response;
+    }
+    if let Ok(value) = header::HeaderValue::from_str(&locales.join(", ")) {
+        headers.insert(header::CONTENT_LANGUAGE, value);
+    }
+    response
+}
+
+fn parse_locale(locale: &str) -> Result<LanguageTag, MyError> {
+    LanguageTag::parse(locale).map_err(MyError::InvalidInput)
+}
+
+pub async fn get_course_translations(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id) = params.into_inner();
+    get_course_details_db(&app_state.db, teacher_id, course_id, viewer.owns(teacher_id)).await?;
+    get_translations_db(&app_state.db, course_id)
+        .await
+        .map(|translations| HttpResponse::Ok().json(translations))
+}
+
+pub async fn get_course_translation(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, String)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, locale) = params.into_inner();
+    let locale = parse_locale(&locale)?;
+    get_course_details_db(&app_state.db, teacher_id, course_id, viewer.owns(teacher_id)).await?;
+    get_translation_db(&app_state.db, course_id, &locale)
+        .await
+        .map(|translation| HttpResponse::Ok().json(translation))
+}
+
+//PUT /courses/{teacher_id}/{course_id}/translations/{locale}, adds or replaces a translation
+//translations change what course reads show, so they move course.updated_at like any other edit
+pub async fn save_course_translation(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, String)>,
+    translation: web::Json<SaveTranslation>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, locale) = params.into_inner();
+    let locale = parse_locale(&locale)?;
+    let translation = translation.into_inner().validate()?;
+    viewer.require_owner(teacher_id, "change its translations")?;
+    let mut tx = app_state.db.begin().await?;
+    lock_course_db(&mut tx, teacher_id, course_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Course Id not found".into()))?;
+    let translation = upsert_translation_db(&mut tx, course_id, &locale, translation).await?;
+    touch_course_db(&mut tx, course_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(translation))
+}
+
+//DELETE /courses/{teacher_id}/{course_id}/translations/{locale}, returns the translations left
+pub async fn delete_course_translation(
+    app_state: web::Data<AppState>,
+    params: web::Path<(i32, i32, String)>,
+    viewer: Viewer,
+) -> Result<HttpResponse, MyError> {
+    let (teacher_id, course_id, locale) = params.into_inner();
+    let locale = parse_locale(&locale)?;
+    viewer.require_owner(teacher_id, "change its translations")?;
+    let mut tx = app_state.db.begin().await?;
+    lock_course_db(&mut tx, teacher_id, course_id)
+        .await?
+        .ok_or_else(|| MyError::NotFound("Course Id not found".into()))?;
+    delete_translation_db(&mut tx, course_id, &locale).await?;
+    touch_course_db(&mut tx, course_id).await?;
+    let translations = get_translations_db(&mut tx, course_id).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(translations))
+}
@@ -0,0 +1,144 @@
+use crate::db_access::audit::insert_course_audit_db;
+use crate::db_access::course::*;
+use crate::db_access::exchange_rate::replace_exchange_rates_db;
+use crate::errors::MyError;
+use crate::models::audit::{AuditAction, AuditContext};
+use crate::models::course::{Course, CourseStatus};
+use crate::models::duration::CourseDuration;
+use crate::models::exchange_rate::NewExchangeRate;
+use crate::negotiation::decode_csv_rows;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::{PgConnection, PgPool};
+use sqlx::Connection;
+use std::time::Duration;
+
+//how often the trash purge runs
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/**
+Spawns the background task that hard deletes courses which have been
+in the trash for more than retention_days
+runs once at startup, then every PURGE_INTERVAL
+**/
+pub fn spawn_trash_purge(pool: PgPool, retention_days: i32) {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            match purge_trash_db(&pool, retention_days).await {
+                Ok(0) => {}
+                Ok(purged) => log::info!("Purged {} course(s) from the trash", purged),
+                Err(err) => log::error!("Trash purge failed: {:?}", err),
+            }
+        }
+    });
+}
+
+/**
+Spawns the scheduler that applies publish_at and unpublish_at
+runs once at startup, which catches up on anything missed while down, then every interval
+several instances can run it side by side, an advisory lock lets only one of them work per tick
+**/
+pub fn spawn_publish_scheduler(pool: PgPool, every: Duration) {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(every);
+        loop {
+            interval.tick().await;
+            match run_scheduled_transitions(&pool).await {
+                Ok(0) => {}
+                Ok(applied) => log::info!("Processed the schedule of {} course(s)", applied),
+                Err(err) => log::error!("Publish scheduler failed: {:?}", err),
+            }
+        }
+    });
+}
+
+//every due course is applied in a savepoint of its own, so a course that fails
+//is logged and skipped without undoing, or holding up, the others
+async fn run_scheduled_transitions(pool: &PgPool) -> Result<usize, MyError> {
+    let mut tx = pool.begin().await?;
+    if !try_lock_scheduler_db(&mut tx).await? {
+        return Ok(0);
+    }
+
+    let ctx = AuditContext {
+        actor: "scheduler".into(),
+        request_id: uuid::Uuid::new_v4().to_string(),
+    };
+    let now = Utc::now();
+    let due = due_scheduled_courses_db(&mut tx, now).await?;
+    let mut applied = 0;
+
+    for before in due {
+        let mut savepoint = tx.begin().await?;
+        match apply_due_schedule(&mut savepoint, &ctx, &before, now).await {
+            Ok(()) => {
+                savepoint.commit().await?;
+                applied += 1;
+            }
+            Err(err) => {
+                savepoint.rollback().await?;
+                log::error!("Schedule of course {} not applied: {:?}", before.id, err);
+            }
+        }
+    }
+
+    tx.commit().await?;
+    Ok(applied)
+}
+
+async fn apply_due_schedule(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    before: &Course,
+    now: DateTime<Utc>,
+) -> Result<(), MyError> {
+    let mut status = before.status;
+    //publish first, so a course whose whole window passed during downtime ends up unpublished
+    if before.publish_at.is_some_and(|at| at <= now) && status.can_become(CourseStatus::Published) {
+        status = set_course_status_db(&mut *conn, before.teacher_id, before.id, CourseStatus::Published)
+            .await?
+            .status;
+    }
+    if before.unpublish_at.is_some_and(|at| at <= now) && status == CourseStatus::Published {
+        set_course_status_db(&mut *conn, before.teacher_id, before.id, CourseStatus::Draft).await?;
+    }
+    let after = clear_due_schedule_db(&mut *conn, before.id, now).await?;
+    insert_course_audit_db(&mut *conn, ctx, AuditAction::Status, Some(before), Some(&after)).await?;
+    Ok(())
+}
+
+/**
+Replaces the exchange rate table with the rates in a CSV file (base,quote,rate[,as_of])
+run once at startup when EXCHANGE_RATES_FILE is set, the admin endpoint can change them later
+**/
+pub async fn load_exchange_rates_file(pool: &PgPool, path: &str) -> Result<usize, MyError> {
+    let body = std::fs::read(path)
+        .map_err(|err| MyError::ActixError(format!("Cannot read {}: {}", path, err)))?;
+    let rates = NewExchangeRate::validate_rows(decode_csv_rows(&body))?;
+    let rates = replace_exchange_rates_db(pool, rates).await?;
+    Ok(rates.len())
+}
+
+/**
+Parses the free text durations stored before durations were structured,
+run once at startup, rows already parsed are not read again
+text that is not a duration is moved to course_cleanup and the course is left without one
+**/
+pub async fn migrate_legacy_durations(pool: &PgPool) -> Result<(usize, usize), MyError> {
+    let (mut parsed, mut cleared) = (0, 0);
+    for (id, text) in get_legacy_durations_db(pool).await? {
+        match CourseDuration::parse_legacy(&text) {
+            Ok(duration) => {
+                resolve_legacy_duration_db(pool, id, Some(&duration)).await?;
+                parsed += 1;
+            }
+            Err(err) => {
+                log::warn!("Course {}: {}, moved to course_cleanup", id, err);
+                resolve_legacy_duration_db(pool, id, None).await?;
+                cleared += 1;
+            }
+        }
+    }
+    Ok((parsed, cleared))
+}
@@ -0,0 +1,29 @@
+use actix_web::{dev::Payload, FromRequest, HttpRequest};
+use std::env;
+use std::future::{ready, Ready};
+use crate::errors::MyError;
+
+/**
+A request allowed to change service wide settings, such as exchange rates
+it has to carry an X-Admin-Token header equal to the ADMIN_TOKEN variable,
+without ADMIN_TOKEN every admin request is refused
+**/
+#[derive(Debug, Clone, Copy)]
+pub struct Admin;
+
+impl FromRequest for Admin {
+    type Error = MyError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let expected = env::var("ADMIN_TOKEN").ok().filter(|token| !token.is_empty());
+        let given = req
+            .headers()
+            .get("X-Admin-Token")
+            .and_then(|value| value.to_str().ok());
+        ready(match (expected, given) {
+            (Some(expected), Some(given)) if expected == given => Ok(Admin),
+            _ => Err(MyError::Forbidden("A valid X-Admin-Token header is required".into())),
+        })
+    }
+}
@@ -0,0 +1,79 @@
+use actix_web::{dev::Payload, FromRequest, HttpRequest};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::future::{ready, Ready};
+use crate::errors::MyError;
+
+//a row of course_audit, read only
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct CourseAuditEntry {
+    pub id: i64,
+    pub course_id: i32,
+    pub teacher_id: i32,
+    pub action: String,
+    pub actor: String,
+    pub request_id: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Restore,
+    Status,
+    Schedule,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+            AuditAction::Status => "status",
+            AuditAction::Schedule => "schedule",
+        }
+    }
+}
+
+/**
+Who made a request, taken from the X-Actor header ("anonymous" when missing)
+and which request it was, taken from X-Request-Id or generated
+**/
+#[derive(Debug, Clone)]
+pub struct AuditContext {
+    pub actor: String,
+    pub request_id: String,
+}
+
+impl AuditContext {
+    fn from_request(req: &HttpRequest) -> AuditContext {
+        let header = |name: &str, max: usize| {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(|value| value.chars().take(max).collect::<String>())
+        };
+        AuditContext {
+            actor: header("X-Actor", 100).unwrap_or_else(|| "anonymous".into()),
+            request_id: header("X-Request-Id", 64)
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        }
+    }
+}
+
+impl FromRequest for AuditContext {
+    type Error = MyError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Ok(AuditContext::from_request(req)))
+    }
+}
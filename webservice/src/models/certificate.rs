@@ -0,0 +1,257 @@
+use chrono::{DateTime, Utc};
+use printpdf::{BuiltinFont, Line, Mm, PdfDocument, Point};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use crate::errors::MyError;
+
+//Crockford's base32, without the letters that read like digits
+const CODE_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const CODE_LENGTH: usize = 12;
+//average width of a Helvetica glyph in em, close enough to center a line
+const GLYPH_WIDTH_EM: f32 = 0.5;
+const MM_PER_POINT: f32 = 0.3528;
+
+//XXXX-XXXX-XXXX from 60 random bits
+pub fn new_verification_code() -> String {
+    let bits = uuid::Uuid::new_v4().as_u128();
+    let code: String = (0..CODE_LENGTH)
+        .map(|i| CODE_ALPHABET[((bits >> (i * 5)) & 31) as usize] as char)
+        .collect();
+    group_code(&code)
+}
+
+fn group_code(code: &str) -> String {
+    [&code[0..4], &code[4..8], &code[8..12]].join("-")
+}
+
+//reads a code the way people type it: any case, with or without dashes, O for 0 and I or L for 1
+pub fn normalize_verification_code(input: &str) -> Option<String> {
+    let code: String = input
+        .chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .map(|c| match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        })
+        .collect();
+    if code.len() != CODE_LENGTH || !code.bytes().all(|c| CODE_ALPHABET.contains(&c)) {
+        return None;
+    }
+    Some(group_code(&code))
+}
+
+//a row of certificate
+#[derive(Serialize, Debug, Clone)]
+pub struct Certificate {
+    pub id: i32,
+    //None once the course was purged, the certificate stays verifiable
+    pub enrollment_id: Option<i32>,
+    pub code: String,
+    pub student_id: i32,
+    pub course_id: i32,
+    pub teacher_id: i32,
+    pub student_name: String,
+    pub course_name: String,
+    pub teacher_name: String,
+    pub completed_at: DateTime<Utc>,
+    pub issued_at: DateTime<Utc>,
+    pub file_name: String,
+}
+
+//GET /certificates/verify/{code}, public, so nothing beyond what is printed on the certificate
+#[derive(Serialize, Debug, Clone)]
+pub struct CertificateVerification {
+    pub code: String,
+    pub valid: bool,
+    pub student_name: String,
+    pub course_name: String,
+    pub teacher_name: String,
+    pub completed_at: DateTime<Utc>,
+    pub issued_at: DateTime<Utc>,
+}
+
+impl From<Certificate> for CertificateVerification {
+    fn from(certificate: Certificate) -> Self {
+        CertificateVerification {
+            code: certificate.code,
+            valid: true,
+            student_name: certificate.student_name,
+            course_name: certificate.course_name,
+            teacher_name: certificate.teacher_name,
+            completed_at: certificate.completed_at,
+            issued_at: certificate.issued_at,
+        }
+    }
+}
+
+//one line of text, centered across the page
+#[derive(Deserialize, Debug, Clone)]
+pub struct TemplateLine {
+    pub text: String,
+    pub size: f32,
+    //from the bottom of the page
+    pub y_mm: f32,
+    #[serde(default)]
+    pub bold: bool,
+}
+
+impl TemplateLine {
+    fn new(text: &str, size: f32, y_mm: f32, bold: bool) -> Self {
+        TemplateLine { text: text.into(), size, y_mm, bold }
+    }
+}
+
+/**
+Layout of the certificate PDF, read from the JSON file in CERTIFICATE_TEMPLATE_FILE
+missing fields keep the default, an A4 landscape page
+line texts can use {student}, {course}, {teacher}, {date} and {code}
+the service only knows teachers by id, teachers maps ids to the names to print
+text is set in Helvetica, which covers Western European characters
+**/
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct CertificateTemplate {
+    pub title: String,
+    pub page_width_mm: f32,
+    pub page_height_mm: f32,
+    pub border: bool,
+    //chrono format of {date}
+    pub date_format: String,
+    pub lines: Vec<TemplateLine>,
+    pub teachers: HashMap<i32, String>,
+}
+
+impl Default for CertificateTemplate {
+    fn default() -> Self {
+        CertificateTemplate {
+            title: "Certificate of Completion".into(),
+            page_width_mm: 297.0,
+            page_height_mm: 210.0,
+            border: true,
+            date_format: "%B %-d, %Y".into(),
+            lines: vec![
+                TemplateLine::new("Certificate of Completion", 32.0, 160.0, true),
+                TemplateLine::new("This certifies that", 14.0, 135.0, false),
+                TemplateLine::new("{student}", 26.0, 120.0, true),
+                TemplateLine::new("has completed the course", 14.0, 105.0, false),
+                TemplateLine::new("{course}", 22.0, 92.0, true),
+                TemplateLine::new("taught by {teacher}, on {date}", 14.0, 75.0, false),
+                TemplateLine::new("Verification code {code}", 10.0, 25.0, false),
+            ],
+            teachers: HashMap::new(),
+        }
+    }
+}
+
+impl CertificateTemplate {
+    pub fn teacher_name(&self, teacher_id: i32) -> String {
+        self.teachers
+            .get(&teacher_id)
+            .cloned()
+            .unwrap_or_else(|| format!("Teacher #{}", teacher_id))
+    }
+
+    fn fill(&self, text: &str, certificate: &Certificate) -> String {
+        text.replace("{student}", &certificate.student_name)
+            .replace("{course}", &certificate.course_name)
+            .replace("{teacher}", &certificate.teacher_name)
+            .replace("{date}", &certificate.completed_at.format(&self.date_format).to_string())
+            .replace("{code}", &certificate.code)
+    }
+
+    pub fn render(&self, certificate: &Certificate) -> Result<Vec<u8>, MyError> {
+        let pdf_error = |err: printpdf::Error| MyError::ActixError(format!("Cannot render certificate: {}", err));
+        let (width, height) = (self.page_width_mm, self.page_height_mm);
+        let (doc, page, layer) = PdfDocument::new(self.fill(&self.title, certificate), Mm(width), Mm(height), "Certificate");
+        let layer = doc.get_page(page).get_layer(layer);
+        let regular = doc.add_builtin_font(BuiltinFont::Helvetica).map_err(pdf_error)?;
+        let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold).map_err(pdf_error)?;
+
+        if self.border {
+            let margin = 10.0;
+            let corners = [(margin, margin), (width - margin, margin), (width - margin, height - margin), (margin, height - margin)];
+            layer.add_line(Line {
+                points: corners.iter().map(|&(x, y)| (Point::new(Mm(x), Mm(y)), false)).collect(),
+                is_closed: true,
+            });
+        }
+        for line in &self.lines {
+            let text = self.fill(&line.text, certificate);
+            let text_width = text.chars().count() as f32 * line.size * GLYPH_WIDTH_EM * MM_PER_POINT;
+            let x = ((width - text_width) / 2.0).max(0.0);
+            layer.use_text(text, line.size, Mm(x), Mm(line.y_mm), if line.bold { &bold } else { &regular });
+        }
+        doc.save_to_bytes().map_err(pdf_error)
+    }
+}
+
+//where certificates are written and how they look, set up once at startup
+#[derive(Debug, Clone)]
+pub struct CertificateSettings {
+    pub dir: PathBuf,
+    pub template: CertificateTemplate,
+}
+
+impl Default for CertificateSettings {
+    fn default() -> Self {
+        CertificateSettings {
+            dir: PathBuf::from("certificates"),
+            template: CertificateTemplate::default(),
+        }
+    }
+}
+
+impl CertificateSettings {
+    pub fn load(dir: &str, template_file: Option<&str>) -> Result<Self, MyError> {
+        let template = match template_file {
+            Some(path) => {
+                let body = std::fs::read(path)
+                    .map_err(|err| MyError::ActixError(format!("Cannot read {}: {}", path, err)))?;
+                serde_json::from_slice(&body)
+                    .map_err(|err| MyError::InvalidInput(format!("Invalid certificate template {}: {}", path, err)))?
+            }
+            None => CertificateTemplate::default(),
+        };
+        Ok(CertificateSettings { dir: PathBuf::from(dir), template })
+    }
+
+    pub fn path(&self, certificate: &Certificate) -> PathBuf {
+        self.dir.join(&certificate.file_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn certificates_render_with_a_readable_code() {
+        let code = new_verification_code();
+        assert_eq!(code.len(), 14);
+        assert_eq!(normalize_verification_code(&code.to_lowercase().replace('-', "")), Some(code.clone()));
+        assert_eq!(normalize_verification_code("o1ab-cdef-ghjk"), Some("01AB-CDEF-GHJK".into()));
+        assert_eq!(normalize_verification_code("01AB-CDEF-GHJU"), None);
+        assert_eq!(normalize_verification_code("01AB"), None);
+
+        let template = CertificateTemplate::default();
+        let certificate = Certificate {
+            id: 1,
+            enrollment_id: Some(1),
+            code,
+            student_id: 1,
+            course_id: 1,
+            teacher_id: 7,
+            student_name: "Zoë Martín".into(),
+            course_name: "Rust".into(),
+            teacher_name: template.teacher_name(7),
+            completed_at: Utc.with_ymd_and_hms(2024, 3, 5, 10, 0, 0).unwrap(),
+            issued_at: Utc::now(),
+            file_name: "certificate.pdf".into(),
+        };
+        assert_eq!(template.fill("taught by {teacher}, on {date}", &certificate), "taught by Teacher #7, on March 5, 2024");
+        assert!(template.render(&certificate).unwrap().starts_with(b"%PDF"));
+    }
+}
@@ -1,8 +1,18 @@
-use actix_web::web;
-use chrono::NaiveDateTime;
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest};
+use chrono::{DateTime, LocalResult, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use crate::errors::MyError;
+use crate::models::duration::{CourseDuration, DurationBound};
+use crate::models::facet::{CourseFormat, CourseLevel, LanguageTag};
+use crate::models::exchange_rate::{ConvertedPrice, ExchangeRates};
+use crate::models::money::{Currency, Price};
+use crate::models::nullable::clearable;
+use crate::models::outline::ModuleOutline;
+use crate::models::taxonomy::normalize_tags;
+use crate::negotiation::StreamFormat;
 use std::convert::TryFrom;
+use std::future::{ready, Ready};
 //use crate::models::course:Course;
 
 
@@ -14,28 +24,254 @@ pub struct Course {
     pub teacher_id: i32, //db does not support usize, change it to i32
     pub id: i32,
     pub name: String,
-    pub time: Option<NaiveDateTime>,
+    pub created_at: DateTime<Utc>,
+    //moved by the database on every change to the row
+    pub updated_at: DateTime<Utc>,
 
     pub description: Option<String>,
-    pub format: Option<String>,
+    pub format: Option<CourseFormat>,
     pub structure: Option<String>,
-    pub duration: Option<String>,
-    pub price: Option<i32>,
-    pub language:Option<String>,
-    pub level: Option<String>,
+    pub duration: Option<CourseDuration>,
+    pub price: Option<Price>,
+    pub language: Option<LanguageTag>,
+    pub level: Option<CourseLevel>,
+    //seats, None when the course takes any number of students
+    pub capacity: Option<i32>,
+    pub status: CourseStatus,
+    pub published_at: Option<DateTime<Utc>>,
+    //pending scheduled transitions, cleared once the scheduler applied them
+    pub publish_at: Option<DateTime<Utc>>,
+    pub unpublish_at: Option<DateTime<Utc>>,
+    //ratings of the course's reviews, moved with every review, None until the first one
+    pub review_count: i32,
+    pub average_rating: Option<f64>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+//lifecycle of a course, stored as text in course.status
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum CourseStatus {
+    Draft,
+    InReview,
+    Published,
+    Archived,
+}
+
+impl CourseStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CourseStatus::Draft => "draft",
+            CourseStatus::InReview => "in_review",
+            CourseStatus::Published => "published",
+            CourseStatus::Archived => "archived",
+        }
+    }
+
+    //draft <-> in_review, both can be published,
+    //published goes back to draft (unpublish) or is archived, archived can only be redrafted
+    pub fn can_become(&self, next: CourseStatus) -> bool {
+        use CourseStatus::*;
+        matches!(
+            (self, next),
+            (Draft, InReview)
+                | (Draft, Published)
+                | (InReview, Draft)
+                | (InReview, Published)
+                | (Published, Draft)
+                | (Published, Archived)
+                | (Archived, Draft)
+        )
+    }
+
+    pub fn transition(&self, next: CourseStatus) -> Result<CourseStatus, MyError> {
+        if self.can_become(next) {
+            Ok(next)
+        } else {
+            Err(MyError::Conflict(format!(
+                "A {} course cannot become {}",
+                self.as_str(),
+                next.as_str()
+            )))
+        }
+    }
+}
+
+//POST /courses/{teacher_id}/{course_id}/status
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct StatusChange {
+    pub status: CourseStatus,
+}
+
+/**
+PUT /courses/{teacher_id}/{course_id}/schedule
+times are RFC 3339 with an offset ("2026-11-02T09:00:00+01:00"), or local times
+("2026-11-02T09:00:00") read in the IANA timezone given ("Europe/Berlin")
+a missing or null time clears that part of the schedule
+**/
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ScheduleCourse {
+    pub publish_at: Option<String>,
+    pub unpublish_at: Option<String>,
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CourseSchedule {
+    pub publish_at: Option<DateTime<Utc>>,
+    pub unpublish_at: Option<DateTime<Utc>>,
+}
+
+impl ScheduleCourse {
+    pub fn resolve(&self) -> Result<CourseSchedule, MyError> {
+        let timezone = self
+            .timezone
+            .as_deref()
+            .map(|name| {
+                name.parse::<Tz>()
+                    .map_err(|_err| MyError::InvalidInput(format!("Unknown timezone {}", name)))
+            })
+            .transpose()?;
+        let parse = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(|value| parse_schedule_time(value, timezone))
+                .transpose()
+        };
+        let schedule = CourseSchedule {
+            publish_at: parse(&self.publish_at)?,
+            unpublish_at: parse(&self.unpublish_at)?,
+        };
+        if let (Some(publish_at), Some(unpublish_at)) = (schedule.publish_at, schedule.unpublish_at) {
+            if unpublish_at <= publish_at {
+                return Err(MyError::InvalidInput(
+                    "unpublish_at must be after publish_at".into(),
+                ));
+            }
+        }
+        Ok(schedule)
+    }
+}
+
+fn parse_schedule_time(value: &str, timezone: Option<Tz>) -> Result<DateTime<Utc>, MyError> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let local = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_err| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+        .map_err(|_err| MyError::InvalidInput(format!("{} is not a valid date and time", value)))?;
+    let timezone = timezone.ok_or_else(|| {
+        MyError::InvalidInput(format!("{} needs an offset or a timezone", value))
+    })?;
+    match timezone.from_local_datetime(&local) {
+        LocalResult::Single(time) => Ok(time.with_timezone(&Utc)),
+        //a wall clock time that happens twice when the clocks go back, take the first
+        LocalResult::Ambiguous(earliest, _latest) => Ok(earliest.with_timezone(&Utc)),
+        LocalResult::None => Err(MyError::InvalidInput(format!(
+            "{} does not exist in {}, the clocks skip it",
+            value, timezone
+        ))),
+    }
+}
+
+/**
+The teacher a read is made on behalf of, from the X-Teacher-Id header
+a teacher sees their own drafts, everybody else only sees published courses
+**/
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Viewer {
+    pub teacher_id: Option<i32>,
+}
+
+impl Viewer {
+    pub fn owns(&self, teacher_id: i32) -> bool {
+        self.teacher_id == Some(teacher_id)
+    }
+
+    //403 for anyone but the course's teacher, what is read as "Only the course's teacher can ..."
+    pub fn require_owner(&self, teacher_id: i32, what: &str) -> Result<(), MyError> {
+        if self.owns(teacher_id) {
+            Ok(())
+        } else {
+            Err(MyError::Forbidden(format!("Only the course's teacher can {}", what)))
+        }
+    }
+}
+
+impl FromRequest for Viewer {
+    type Error = MyError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let teacher_id = req
+            .headers()
+            .get("X-Teacher-Id")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok());
+        ready(Ok(Viewer { teacher_id }))
+    }
+}
+
+/**
+A course read with extras asked for in the query, each field is left out when not asked for
+converted_price (?currency=) is null when no rate between the two currencies is stored,
+price keeps the canonical price
+outline (?embed=outline) lists the modules with their lessons in order
+**/
+#[derive(Serialize, Debug, Clone)]
+pub struct CourseView {
+    #[serde(flatten)]
+    pub course: Course,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converted_price: Option<Option<ConvertedPrice>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outline: Option<Vec<ModuleOutline>>,
+}
+
+impl CourseView {
+    pub fn new(course: Course) -> Self {
+        CourseView { course, converted_price: None, outline: None }
+    }
+
+    pub fn localized(mut self, rates: &ExchangeRates, currency: Currency) -> Self {
+        let converted_price = self.course.price.as_ref().and_then(|price| rates.convert(price, currency));
+        self.converted_price = Some(converted_price);
+        self
+    }
+
+    pub fn with_outline(mut self, outline: Vec<ModuleOutline>) -> Self {
+        self.outline = Some(outline);
+        self
+    }
+}
+
+//a soft deleted course, as listed in the teacher's trash
+#[derive(Serialize, Debug, Clone)]
+pub struct TrashedCourse {
+    #[serde(flatten)]
+    pub course: Course,
+    pub deleted_at: DateTime<Utc>,
+}
+
+fn validate_capacity(capacity: Option<i32>) -> Result<(), MyError> {
+    if capacity.is_some_and(|capacity| capacity < 1) {
+        return Err(MyError::InvalidInput("capacity must be positive".into()));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct CreateCourse {
     pub teacher_id: i32,
     pub name: String,
     pub description: Option<String>,
-    pub format: Option<String>,
+    pub format: Option<CourseFormat>,
     pub structure: Option<String>,
-    pub duration: Option<String>,
-    pub price: Option<i32>,
-    pub language: Option<String>,
-    pub level: Option<String>,
+    pub duration: Option<CourseDuration>,
+    pub price: Option<Price>,
+    pub language: Option<LanguageTag>,
+    pub level: Option<CourseLevel>,
+    pub capacity: Option<i32>,
 }
 
 
@@ -47,16 +283,44 @@ impl TryFrom<web::Json<CreateCourse>> for CreateCourse {
             teacher_id: course.teacher_id,
             name: course.name.clone(),
             description: course.description.clone(),
-            format: course.format.clone(),
+            format: course.format,
             structure: course.structure.clone(),
-            duration: course.duration.clone(),
+            duration: course.duration,
             price: course.price,
             language: course.language.clone(),
-            level: course.level.clone(),
+            level: course.level,
+            capacity: course.capacity,
         })
     }
 }
 
+impl CreateCourse {
+    //checks the same limits the course table enforces,
+    //so a bad row is reported before it reaches the database
+    pub fn validate(&self) -> Result<(), MyError> {
+        if self.teacher_id <= 0 {
+            return Err(MyError::InvalidInput("teacher_id must be positive".into()));
+        }
+        if self.name.trim().is_empty() {
+            return Err(MyError::InvalidInput("name must not be empty".into()));
+        }
+        let limits = [
+            ("name", Some(&self.name), 140),
+            ("description", self.description.as_ref(), 2000),
+            ("structure", self.structure.as_ref(), 200),
+        ];
+        validate_capacity(self.capacity)?;
+        for (field, value, max) in limits {
+            if value.is_some_and(|v| v.chars().count() > max) {
+                return Err(MyError::InvalidInput(format!(
+                    "{} must be at most {} characters",
+                    field, max
+                )));
+            }
+        }
+        Ok(())
+    }
+}
 
 // //add new course need from trait
 // impl From<web::Json<CreateCourse>> for CreateCourse {
@@ -67,7 +331,7 @@ impl TryFrom<web::Json<CreateCourse>> for CreateCourse {
 //             description: course.description.clone(),
 //             format: course.format.clone(),
 //             structure: course.structure.clone(),
-//             duration: course.duration.clone(),
+//             duration: course.duration,
 //             price: course.price,
 //             language: course.language.clone(),
 //             level: course.level.clone(),
@@ -79,12 +343,15 @@ impl TryFrom<web::Json<CreateCourse>> for CreateCourse {
 pub struct UpdateCourse {
     pub name: Option<String>,
     pub description: Option<String>,
-    pub format: Option<String>,
+    pub format: Option<CourseFormat>,
     pub structure: Option<String>,
-    pub duration: Option<String>,
-    pub price: Option<i32>,
-    pub language: Option<String>,
-    pub level: Option<String>,
+    pub duration: Option<CourseDuration>,
+    pub price: Option<Price>,
+    pub language: Option<LanguageTag>,
+    pub level: Option<CourseLevel>,
+    //"capacity": null makes the course unlimited again
+    #[serde(default, deserialize_with = "clearable")]
+    pub capacity: Option<Option<i32>>,
 }
 
 
@@ -92,15 +359,204 @@ impl TryFrom<web::Json<UpdateCourse>> for UpdateCourse {
     type Error = MyError;
 
     fn try_from(course:web::Json<UpdateCourse>) -> Result<Self, Self::Error> {
+        validate_capacity(course.capacity.flatten())?;
         Ok(UpdateCourse {
             name: course.name.clone(),
             description: course.description.clone(),
-            format: course.format.clone(),
+            format: course.format,
             structure: course.structure.clone(),
-            duration: course.duration.clone(),
+            duration: course.duration,
             price: course.price,
             language: course.language.clone(),
-            level: course.level.clone(),
+            level: course.level,
+            capacity: course.capacity,
+        })
+    }
+}
+
+//?dry_run=true&mode=per_row on POST /courses/import
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    //all rows are inserted in one transaction, any error rolls everything back
+    #[default]
+    Atomic,
+    //every row is inserted on its own, bad rows are skipped
+    PerRow,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub mode: ImportMode,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportedRow {
+    pub row: usize,
+    pub name: String,
+    //only set when the import was committed
+    pub course_id: Option<i32>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportRowError {
+    pub row: usize,
+    pub error: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub mode: ImportMode,
+    pub committed: bool,
+    pub total_rows: usize,
+    pub accepted: Vec<ImportedRow>,
+    pub errors: Vec<ImportRowError>,
+}
+
+impl ImportReport {
+    pub fn new(query: &ImportQuery, total_rows: usize) -> Self {
+        ImportReport {
+            dry_run: query.dry_run,
+            mode: query.mode,
+            committed: false,
+            total_rows,
+            accepted: vec![],
+            errors: vec![],
+        }
+    }
+}
+
+//POST /courses/{teacher_id}/{course_id}/clone, the copy goes to teacher_id when given, else to the same teacher
+#[derive(Deserialize, Debug, Clone)]
+pub struct CloneCourse {
+    pub name: String,
+    pub teacher_id: Option<i32>,
+}
+
+impl CloneCourse {
+    //(teacher_id, name) of the copy
+    pub fn validate(self, source_teacher_id: i32) -> Result<(i32, String), MyError> {
+        let teacher_id = self.teacher_id.unwrap_or(source_teacher_id);
+        if teacher_id <= 0 {
+            return Err(MyError::InvalidInput("teacher_id must be positive".into()));
+        }
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > 140 {
+            return Err(MyError::InvalidInput("name must be 1 to 140 characters".into()));
+        }
+        Ok((teacher_id, name.to_string()))
+    }
+}
+
+//GET /courses/{teacher_id}?updated_since=2024-11-26T00:00:00Z&currency=EUR&min_duration=PT2H&max_duration=P1W&category=3&tags=rust,web
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ListQuery {
+    pub updated_since: Option<DateTime<Utc>>,
+    //bounds on the total duration, inclusive, courses without a duration are left out
+    pub min_duration: Option<DurationBound>,
+    pub max_duration: Option<DurationBound>,
+    //adds the price converted to this currency
+    pub currency: Option<Currency>,
+    //courses in this category or any category under it
+    pub category: Option<i32>,
+    //comma separated, courses carrying every one of these tags
+    pub tags: Option<String>,
+}
+
+//what a course list is narrowed to
+#[derive(Debug, Clone, Default)]
+pub struct CourseFilter {
+    pub updated_since: Option<DateTime<Utc>>,
+    pub min_minutes: Option<i32>,
+    pub max_minutes: Option<i32>,
+    pub category_id: Option<i32>,
+    pub tags: Vec<String>,
+}
+
+impl ListQuery {
+    pub fn filter(&self) -> Result<CourseFilter, MyError> {
+        let tags = match &self.tags {
+            Some(tags) => normalize_tags(tags.split(',').filter(|tag| !tag.trim().is_empty()))?,
+            None => vec![],
+        };
+        Ok(CourseFilter {
+            updated_since: self.updated_since,
+            min_minutes: self.min_duration.map(|bound| bound.0),
+            max_minutes: self.max_duration.map(|bound| bound.0),
+            category_id: self.category,
+            tags,
         })
     }
-}
\ No newline at end of file
+}
+
+//what ?embed= can add to a course detail
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CourseEmbed {
+    Outline,
+}
+
+//GET /courses/{teacher_id}/{course_id}?currency=EUR&embed=outline
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct DetailQuery {
+    pub currency: Option<Currency>,
+    pub embed: Option<CourseEmbed>,
+}
+
+//GET /courses/export?teacher_id=1&updated_since=2024-11-26T00:00:00Z&format=csv
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExportQuery {
+    pub teacher_id: Option<i32>,
+    //RFC 3339, only courses changed at or after this time
+    pub updated_since: Option<DateTime<Utc>>,
+    //falls back to the Accept header when missing
+    pub format: Option<StreamFormat>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_transitions_follow_the_lifecycle() {
+        use CourseStatus::*;
+        assert!(Draft.can_become(Published));
+        assert!(InReview.can_become(Published));
+        assert!(Published.can_become(Draft));
+        assert!(Published.can_become(Archived));
+        assert!(!Draft.can_become(Archived));
+        assert!(!Archived.can_become(Published));
+        assert!(!Published.can_become(Published));
+        assert!(matches!(Archived.transition(InReview), Err(MyError::Conflict(_))));
+    }
+
+    #[test]
+    fn schedule_reads_local_times_in_the_given_timezone() {
+        let schedule = ScheduleCourse {
+            publish_at: Some("2026-11-02T09:00:00".into()),
+            unpublish_at: Some("2026-12-01T09:00:00+00:00".into()),
+            timezone: Some("Europe/Berlin".into()),
+        }
+        .resolve()
+        .unwrap();
+        assert_eq!(schedule.publish_at.unwrap().to_rfc3339(), "2026-11-02T08:00:00+00:00");
+        assert_eq!(schedule.unpublish_at.unwrap().to_rfc3339(), "2026-12-01T09:00:00+00:00");
+
+        let without_zone = ScheduleCourse {
+            publish_at: Some("2026-11-02T09:00:00".into()),
+            ..Default::default()
+        };
+        assert!(without_zone.resolve().is_err());
+
+        let backwards = ScheduleCourse {
+            publish_at: Some("2026-11-02T09:00:00Z".into()),
+            unpublish_at: Some("2026-11-01T09:00:00Z".into()),
+            timezone: None,
+        };
+        assert!(backwards.resolve().is_err());
+    }
+}
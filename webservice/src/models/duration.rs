@@ -0,0 +1,370 @@
+use serde::de::{self, Deserializer, MapAccess, Visitor};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgTypeInfo, PgValueRef, Postgres};
+use sqlx::types::Json;
+use std::fmt;
+
+const MINUTES_PER_HOUR: i64 = 60;
+const MINUTES_PER_DAY: i64 = 24 * MINUTES_PER_HOUR;
+const MINUTES_PER_WEEK: i64 = 7 * MINUTES_PER_DAY;
+
+//upper bounds that keep a typo such as P600W out of the catalogue
+const MAX_MINUTES: i64 = 520 * MINUTES_PER_WEEK;
+const MAX_WEEKS: i32 = 520;
+const MAX_SESSIONS: i32 = 1000;
+
+/**
+Length of a course: minutes is the total length and what listings filter on,
+weeks and sessions optionally say how it is spread out
+responses carry the breakdown and the total as an ISO 8601 duration:
+{"minutes": 600, "weeks": 4, "sessions": 8, "iso": "PT10H"}
+requests may send that object (iso is ignored) or an ISO 8601 duration string,
+"PT10H", "PT1H30M", "P6W" or "P2DT3H"; years and months have no fixed length and are rejected
+**/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CourseDuration {
+    minutes: i32,
+    weeks: Option<i32>,
+    sessions: Option<i32>,
+}
+
+impl CourseDuration {
+    pub fn new(minutes: i64, weeks: Option<i32>, sessions: Option<i32>) -> Result<CourseDuration, String> {
+        if minutes <= 0 || minutes > MAX_MINUTES {
+            return Err(format!("A duration must be between 1 minute and {} weeks", MAX_WEEKS));
+        }
+        if weeks.is_some_and(|weeks| !(1..=MAX_WEEKS).contains(&weeks)) {
+            return Err(format!("weeks must be between 1 and {}", MAX_WEEKS));
+        }
+        if sessions.is_some_and(|sessions| !(1..=MAX_SESSIONS).contains(&sessions)) {
+            return Err(format!("sessions must be between 1 and {}", MAX_SESSIONS));
+        }
+        Ok(CourseDuration {
+            minutes: minutes as i32,
+            weeks,
+            sessions,
+        })
+    }
+
+    //the course.duration_minutes, duration_weeks and duration_sessions columns
+    pub fn columns(duration: Option<&CourseDuration>) -> (Option<i32>, Option<i32>, Option<i32>) {
+        match duration {
+            None => (None, None, None),
+            Some(duration) => (Some(duration.minutes), duration.weeks, duration.sessions),
+        }
+    }
+
+    //a whole number of weeks only when given in weeks alone, "P6W" also records weeks: 6
+    pub fn parse_iso(value: &str) -> Result<CourseDuration, String> {
+        let total = parse_iso_minutes(value)?;
+        let weeks = value
+            .trim()
+            .strip_prefix('P')
+            .and_then(|weeks| weeks.strip_suffix('W'))
+            .and_then(|weeks| weeks.parse().ok());
+        CourseDuration::new(total, weeks, None)
+    }
+
+    //the total in the largest units that divide it exactly, weeks are not mixed with other units
+    pub fn iso(&self) -> String {
+        let minutes = self.minutes as i64;
+        if minutes % MINUTES_PER_WEEK == 0 {
+            return format!("P{}W", minutes / MINUTES_PER_WEEK);
+        }
+        let days = minutes / MINUTES_PER_DAY;
+        let hours = minutes % MINUTES_PER_DAY / MINUTES_PER_HOUR;
+        let rest = minutes % MINUTES_PER_HOUR;
+        let mut iso = String::from("P");
+        if days > 0 {
+            iso.push_str(&format!("{}D", days));
+        }
+        if hours > 0 || rest > 0 {
+            iso.push('T');
+        }
+        if hours > 0 {
+            iso.push_str(&format!("{}H", hours));
+        }
+        if rest > 0 {
+            iso.push_str(&format!("{}M", rest));
+        }
+        iso
+    }
+
+    /**
+    Reads the free text durations stored before durations were structured,
+    "6 weeks", "10 hours", "1.5 hrs", "90 min", "3h 30m", "2 days", "8 weeks, 16 sessions"
+    with hours or minutes in the text they are the total and weeks only the breakdown,
+    "10 hours over 4 weeks" is 600 minutes in 4 weeks, "6 weeks" alone is six whole weeks
+    a session count that is not whole, "1.5 sessions", makes the text unreadable
+    **/
+    pub fn parse_legacy(value: &str) -> Result<CourseDuration, String> {
+        let unreadable = || format!("{} is not a duration", value);
+        let text = value.to_ascii_lowercase().replace(',', " ");
+        let mut tokens = vec![];
+        //split "3h30m" into 3, h, 30, m
+        let mut current = String::new();
+        for c in text.chars() {
+            let boundary = match current.chars().last() {
+                Some(last) => {
+                    c.is_whitespace()
+                        || (last.is_ascii_digit() || last == '.') != (c.is_ascii_digit() || c == '.')
+                }
+                None => false,
+            };
+            if boundary {
+                tokens.push(std::mem::take(&mut current));
+            }
+            if !c.is_whitespace() {
+                current.push(c);
+            }
+        }
+        if !current.is_empty() {
+            tokens.push(current);
+        }
+
+        let (mut time_minutes, mut span_minutes) = (0.0_f64, 0.0_f64);
+        let (mut weeks, mut sessions) = (None, None);
+        let mut i = 0;
+        while i < tokens.len() {
+            let amount: f64 = match tokens[i].parse() {
+                Ok(amount) => amount,
+                //filler words such as "over", "approx" or "total"
+                Err(_err) => {
+                    i += 1;
+                    continue;
+                }
+            };
+            let unit = tokens.get(i + 1).ok_or_else(unreadable)?;
+            match unit.trim_end_matches('.') {
+                "m" | "min" | "mins" | "minute" | "minutes" => time_minutes += amount,
+                "h" | "hr" | "hrs" | "hour" | "hours" => time_minutes += amount * MINUTES_PER_HOUR as f64,
+                "d" | "day" | "days" => span_minutes += amount * MINUTES_PER_DAY as f64,
+                "w" | "wk" | "wks" | "week" | "weeks" => {
+                    span_minutes += amount * MINUTES_PER_WEEK as f64;
+                    if amount.fract() == 0.0 {
+                        weeks = Some(amount as i32);
+                    }
+                }
+                //half a session is not something a course can be split into, so the text is unreadable
+                "session" | "sessions" | "lesson" | "lessons" | "class" | "classes" => {
+                    if amount.fract() != 0.0 {
+                        return Err(format!("{} has a session count that is not a whole number", value));
+                    }
+                    sessions = Some(amount as i32)
+                }
+                _ => return Err(unreadable()),
+            }
+            i += 2;
+        }
+
+        let minutes = if time_minutes > 0.0 { time_minutes } else { span_minutes };
+        if minutes <= 0.0 {
+            return Err(unreadable());
+        }
+        CourseDuration::new(minutes.round() as i64, weeks, sessions)
+    }
+}
+
+//PnWnDTnHnMnS in minutes, seconds must add up to whole minutes
+fn parse_iso_minutes(value: &str) -> Result<i64, String> {
+    let invalid = || format!("{} is not an ISO 8601 duration such as PT10H or P6W", value);
+    let rest = value.trim().strip_prefix('P').ok_or_else(invalid)?;
+    let (date, time) = match rest.split_once('T') {
+        Some((date, time)) if !time.is_empty() => (date, Some(time)),
+        Some(_) => return Err(invalid()),
+        None => (rest, None),
+    };
+    if date.is_empty() && time.is_none() {
+        return Err(invalid());
+    }
+
+    let mut seconds: i64 = 0;
+    for (part, units) in [(date, "WD"), (time.unwrap_or_default(), "HMS")] {
+        let mut number = String::new();
+        let mut last_unit = None;
+        for c in part.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            //units in order, each at most once, every one after a number
+            let position = units.find(c).ok_or_else(|| {
+                if "YM".contains(c) && part == date {
+                    format!("{} uses years or months, which have no fixed length", value)
+                } else {
+                    invalid()
+                }
+            })?;
+            if number.is_empty() || last_unit.is_some_and(|last| last >= position) {
+                return Err(invalid());
+            }
+            let amount: i64 = number.parse().map_err(|_err| invalid())?;
+            let unit_seconds = match c {
+                'W' => MINUTES_PER_WEEK * 60,
+                'D' => MINUTES_PER_DAY * 60,
+                'H' => MINUTES_PER_HOUR * 60,
+                'M' => 60,
+                _ => 1,
+            };
+            seconds = amount
+                .checked_mul(unit_seconds)
+                .and_then(|part| seconds.checked_add(part))
+                .ok_or_else(invalid)?;
+            number.clear();
+            last_unit = Some(position);
+        }
+        if !number.is_empty() {
+            return Err(invalid());
+        }
+    }
+    if seconds % 60 != 0 {
+        return Err(format!("{} is not a whole number of minutes", value));
+    }
+    Ok(seconds / 60)
+}
+
+impl fmt::Display for CourseDuration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.iso())
+    }
+}
+
+impl Serialize for CourseDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CourseDuration", 4)?;
+        state.serialize_field("minutes", &self.minutes)?;
+        state.serialize_field("weeks", &self.weeks)?;
+        state.serialize_field("sessions", &self.sessions)?;
+        state.serialize_field("iso", &self.iso())?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for CourseDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DurationVisitor;
+
+        impl<'de> Visitor<'de> for DurationVisitor {
+            type Value = CourseDuration;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an ISO 8601 duration such as \"PT10H\", or {\"minutes\", \"weeks\", \"sessions\"}")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<CourseDuration, E> {
+                CourseDuration::parse_iso(value).map_err(E::custom)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<CourseDuration, A::Error> {
+                let mut minutes: Option<i64> = None;
+                let mut weeks: Option<i32> = None;
+                let mut sessions: Option<i32> = None;
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "minutes" => minutes = Some(map.next_value()?),
+                        "weeks" => weeks = map.next_value()?,
+                        "sessions" => sessions = map.next_value()?,
+                        _ => {
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+                let minutes = minutes.ok_or_else(|| de::Error::missing_field("minutes"))?;
+                CourseDuration::new(minutes, weeks, sessions).map_err(de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_any(DurationVisitor)
+    }
+}
+
+//read from the generated course.duration jsonb column
+impl sqlx::Type<Postgres> for CourseDuration {
+    fn type_info() -> PgTypeInfo {
+        <Json<CourseDuration> as sqlx::Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <Json<CourseDuration> as sqlx::Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Postgres> for CourseDuration {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(<Json<CourseDuration> as sqlx::Decode<Postgres>>::decode(value)?.0)
+    }
+}
+
+/**
+?min_duration= and ?max_duration= on course listings, in minutes
+taken as an ISO 8601 duration ("PT2H") or a plain number of minutes ("120")
+**/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurationBound(pub i32);
+
+impl<'de> Deserialize<'de> for DurationBound {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        let minutes = match value.trim().parse::<i64>() {
+            Ok(minutes) if minutes >= 0 => minutes,
+            Ok(_negative) => return Err(de::Error::custom("a duration bound must not be negative")),
+            Err(_err) => parse_iso_minutes(&value).map_err(de::Error::custom)?,
+        };
+        i32::try_from(minutes)
+            .map(DurationBound)
+            .map_err(|_err| de::Error::custom(format!("{} is too long", value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iso_durations_round_trip() {
+        let parse = |iso: &str| CourseDuration::parse_iso(iso).unwrap();
+        assert_eq!(parse("PT10H").minutes, 600);
+        assert_eq!(parse("PT1H30M").iso(), "PT1H30M");
+        assert_eq!(parse("P6W").weeks, Some(6));
+        assert_eq!(parse("P6W").minutes as i64, 6 * MINUTES_PER_WEEK);
+        assert_eq!(parse("P2DT3H").iso(), "P2DT3H");
+        assert_eq!(parse("PT5400S").iso(), "PT1H30M");
+
+        for iso in ["P1M", "P1Y", "PT", "P", "10H", "PT10", "PT30M1H", "PT90S", "P0D"] {
+            assert!(CourseDuration::parse_iso(iso).is_err(), "{} should be rejected", iso);
+        }
+    }
+
+    #[test]
+    fn legacy_strings_are_parsed() {
+        let parse = |text: &str| CourseDuration::parse_legacy(text).unwrap();
+        assert_eq!(parse("6 weeks"), CourseDuration::parse_iso("P6W").unwrap());
+        assert_eq!(parse("10 hours").minutes, 600);
+        assert_eq!(parse("1.5 hrs").minutes, 90);
+        assert_eq!(parse("3h30m").minutes, 210);
+        assert_eq!(parse("2 Days").iso(), "P2D");
+
+        let spread = parse("10 hours over 4 weeks, 8 sessions");
+        assert_eq!((spread.minutes, spread.weeks, spread.sessions), (600, Some(4), Some(8)));
+
+        for text in ["", "self paced", "12 sessions", "6 fortnights", "10", "10 hours, 1.5 sessions"] {
+            assert!(CourseDuration::parse_legacy(text).is_err(), "{} should be rejected", text);
+        }
+    }
+
+    #[test]
+    fn duration_reads_strings_objects_and_bounds() {
+        let duration: CourseDuration =
+            serde_json::from_str(r#"{"minutes": 90, "sessions": 3}"#).unwrap();
+        assert_eq!(duration.sessions, Some(3));
+        let json = serde_json::to_string(&duration).unwrap();
+        assert_eq!(serde_json::from_str::<CourseDuration>(&json).unwrap(), duration);
+        assert!(serde_json::from_str::<CourseDuration>(r#"{"minutes": 0}"#).is_err());
+
+        let bound: DurationBound = serde_json::from_str("\"PT2H\"").unwrap();
+        assert_eq!(bound, DurationBound(120));
+        let bound: DurationBound = serde_json::from_str("\"45\"").unwrap();
+        assert_eq!(bound, DurationBound(45));
+    }
+}
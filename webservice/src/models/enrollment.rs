@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+//where a student stands in a course, stored as text in enrollment.status
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum EnrollmentStatus {
+    Active,
+    //asked for a seat while the course was full, promoted in order as seats free up
+    Waitlisted,
+    //finished every required lesson, keeps the seat
+    Completed,
+    Withdrawn,
+}
+
+impl EnrollmentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EnrollmentStatus::Active => "active",
+            EnrollmentStatus::Waitlisted => "waitlisted",
+            EnrollmentStatus::Completed => "completed",
+            EnrollmentStatus::Withdrawn => "withdrawn",
+        }
+    }
+}
+
+//a row of enrollment
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct Enrollment {
+    pub id: i32,
+    pub course_id: i32,
+    pub student_id: i32,
+    pub status: EnrollmentStatus,
+    //1 for the next student to get a seat, None when not waitlisted
+    pub waitlist_position: Option<i64>,
+    //when the student got a seat, set again when a withdrawn student enrolls anew
+    pub enrolled_at: Option<DateTime<Utc>>,
+    //when the student joined the waitlist, which orders it
+    pub waitlisted_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub withdrawn_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+//GET /courses/{teacher_id}/{course_id}/enrollments, one student of the roster
+#[derive(Serialize, Debug, Clone)]
+pub struct RosterEntry {
+    pub student_id: i32,
+    pub name: String,
+    pub email: String,
+    pub status: EnrollmentStatus,
+    pub waitlist_position: Option<i64>,
+    pub enrolled_at: Option<DateTime<Utc>>,
+    pub waitlisted_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub withdrawn_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+//GET /students/{student_id}/enrollments, one course the student enrolled in
+#[derive(Serialize, Debug, Clone)]
+pub struct StudentEnrollment {
+    pub course_id: i32,
+    pub teacher_id: i32,
+    pub course_name: String,
+    pub status: EnrollmentStatus,
+    pub waitlist_position: Option<i64>,
+    pub enrolled_at: Option<DateTime<Utc>>,
+    pub waitlisted_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub withdrawn_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+//POST /courses/{teacher_id}/{course_id}/enrollments
+#[derive(Deserialize, Debug, Clone)]
+pub struct EnrollmentRequest {
+    pub student_id: i32,
+    //joins the waitlist when the course is full, false refuses instead, true when missing
+    pub waitlist: Option<bool>,
+}
+
+//?status=active on the roster and on a student's enrollments
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct EnrollmentQuery {
+    pub status: Option<EnrollmentStatus>,
+}
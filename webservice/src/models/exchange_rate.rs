@@ -0,0 +1,137 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::errors::MyError;
+use crate::models::money::{Currency, Price, Rate};
+use crate::negotiation::DecodedRow;
+
+//a row of exchange_rate, one unit of base buys rate units of quote
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct ExchangeRate {
+    pub base: String,
+    pub quote: String,
+    pub rate: Rate,
+    pub as_of: DateTime<Utc>,
+}
+
+//a rate as an admin uploads it, one CSV row or JSON line: base,quote,rate[,as_of]
+#[derive(Deserialize, Debug, Clone)]
+pub struct NewExchangeRate {
+    pub base: Currency,
+    pub quote: Currency,
+    pub rate: Rate,
+    //when the rate was taken, the upload time when missing
+    #[serde(default)]
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+impl NewExchangeRate {
+    //a rate is positive once it is read
+    fn validate(&self) -> Result<(), MyError> {
+        if self.base == self.quote {
+            return Err(MyError::InvalidInput("base and quote must differ".into()));
+        }
+        Ok(())
+    }
+
+    //a rate table is replaced as a whole, so the first bad row rejects all of them
+    pub fn validate_rows(rows: Vec<DecodedRow<NewExchangeRate>>) -> Result<Vec<NewExchangeRate>, MyError> {
+        if rows.is_empty() {
+            return Err(MyError::InvalidInput("No exchange rates given".into()));
+        }
+        rows.into_iter()
+            .map(|(row, rate)| {
+                rate.and_then(|rate| rate.validate().map(|_| rate))
+                    .map_err(|err| MyError::InvalidInput(format!("row {}: {}", row, err)))
+            })
+            .collect()
+    }
+}
+
+/**
+The price of a course in the currency a reader asked for with ?currency=
+rate and rate_as_of tell which stored rate was used,
+a price already in that currency has rate 1 and no rate_as_of, a free course has neither
+**/
+#[derive(Serialize, Debug, Clone)]
+pub struct ConvertedPrice {
+    pub price: Price,
+    pub rate: Option<Rate>,
+    pub rate_as_of: Option<DateTime<Utc>>,
+}
+
+//the rate table loaded for one request, looked up in both directions
+pub struct ExchangeRates {
+    rates: HashMap<(String, String), (Rate, DateTime<Utc>)>,
+}
+
+impl ExchangeRates {
+    pub fn new(rates: Vec<ExchangeRate>) -> Self {
+        ExchangeRates {
+            rates: rates
+                .into_iter()
+                .map(|rate| ((rate.base, rate.quote), (rate.rate, rate.as_of)))
+                .collect(),
+        }
+    }
+
+    //a direct rate wins over the inverse of the opposite one
+    fn find(&self, from: Currency, to: Currency) -> Option<(Rate, DateTime<Utc>)> {
+        let key = |base: Currency, quote: Currency| (base.code().to_string(), quote.code().to_string());
+        self.rates
+            .get(&key(from, to))
+            .copied()
+            .or_else(|| self.rates.get(&key(to, from)).map(|&(rate, as_of)| (rate.inverse(), as_of)))
+    }
+
+    //None when there is no rate between the two currencies
+    pub fn convert(&self, price: &Price, to: Currency) -> Option<ConvertedPrice> {
+        match price {
+            Price::Free => Some(ConvertedPrice {
+                price: Price::Free,
+                rate: None,
+                rate_as_of: None,
+            }),
+            Price::Paid(money) if money.currency() == to => Some(ConvertedPrice {
+                price: *price,
+                rate: Some(Rate::ONE),
+                rate_as_of: None,
+            }),
+            Price::Paid(money) => self.find(money.currency(), to).map(|(rate, as_of)| ConvertedPrice {
+                price: Price::Paid(money.convert(to, rate)),
+                rate: Some(rate),
+                rate_as_of: Some(as_of),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_are_used_in_both_directions() {
+        let as_of = Utc::now();
+        let rates = ExchangeRates::new(vec![ExchangeRate {
+            base: "USD".into(),
+            quote: "EUR".into(),
+            rate: Rate::parse("0.8").unwrap(),
+            as_of,
+        }]);
+        let usd = Currency::from_code("USD").unwrap();
+        let eur = Currency::from_code("EUR").unwrap();
+        let gbp = Currency::from_code("GBP").unwrap();
+
+        let converted = rates.convert(&Price::parse("10 USD").unwrap(), eur).unwrap();
+        assert_eq!(converted.price.formatted(), "8.00 EUR");
+        assert_eq!(converted.rate_as_of, Some(as_of));
+
+        let converted = rates.convert(&Price::parse("8 EUR").unwrap(), usd).unwrap();
+        assert_eq!(converted.price.formatted(), "10.00 USD");
+        assert_eq!(converted.rate.map(Rate::to_f64), Some(1.25));
+
+        assert!(rates.convert(&Price::parse("10 USD").unwrap(), gbp).is_none());
+        assert_eq!(rates.convert(&Price::Free, gbp).unwrap().price, Price::Free);
+    }
+}
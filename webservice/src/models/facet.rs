@@ -0,0 +1,275 @@
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+//how far a student should be to take a course, stored as text in course.level
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum CourseLevel {
+    Beginner,
+    Intermediate,
+    Advanced,
+    AllLevels,
+}
+
+impl CourseLevel {
+    pub const ALL: [CourseLevel; 4] = [
+        CourseLevel::Beginner,
+        CourseLevel::Intermediate,
+        CourseLevel::Advanced,
+        CourseLevel::AllLevels,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CourseLevel::Beginner => "beginner",
+            CourseLevel::Intermediate => "intermediate",
+            CourseLevel::Advanced => "advanced",
+            CourseLevel::AllLevels => "all_levels",
+        }
+    }
+}
+
+//how a course is delivered, stored as text in course.format
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum CourseFormat {
+    SelfPaced,
+    LiveOnline,
+    InPerson,
+    Hybrid,
+}
+
+impl CourseFormat {
+    pub const ALL: [CourseFormat; 4] = [
+        CourseFormat::SelfPaced,
+        CourseFormat::LiveOnline,
+        CourseFormat::InPerson,
+        CourseFormat::Hybrid,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CourseFormat::SelfPaced => "self_paced",
+            CourseFormat::LiveOnline => "live_online",
+            CourseFormat::InPerson => "in_person",
+            CourseFormat::Hybrid => "hybrid",
+        }
+    }
+}
+
+//longest tag course.language takes
+const MAX_LANGUAGE_TAG_LEN: usize = 35;
+
+/**
+A BCP 47 (RFC 5646) language tag such as "en", "pt-BR", "zh-Hant-TW" or "es-419"
+tags are checked for the shape of every subtag, not against the IANA registry,
+the primary language must be a two or three letter ISO 639 code
+subtags are stored in their conventional case: language lower, Script title, REGION upper
+**/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, sqlx::Type)]
+#[serde(try_from = "String", into = "String")]
+#[sqlx(transparent)]
+pub struct LanguageTag(String);
+
+impl LanguageTag {
+    pub fn parse(value: &str) -> Result<LanguageTag, String> {
+        let invalid = || format!("{} is not a BCP 47 language tag", value);
+        let lower = value.trim().to_ascii_lowercase();
+        if lower.is_empty() || lower.len() > MAX_LANGUAGE_TAG_LEN {
+            return Err(invalid());
+        }
+
+        let subtags: Vec<&str> = lower.split('-').collect();
+        let alpha = |s: &str, min: usize, max: usize| {
+            (min..=max).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphabetic())
+        };
+        let alnum = |s: &str, min: usize, max: usize| {
+            (min..=max).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric())
+        };
+        let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+
+        let mut tag: Vec<String> = vec![];
+        let mut i = 0;
+        if subtags[0] != "x" {
+            if !alpha(subtags[0], 2, 3) {
+                return Err(invalid());
+            }
+            tag.push(subtags[0].into());
+            i += 1;
+            //extlang
+            while i < subtags.len() && i <= 3 && alpha(subtags[i], 3, 3) {
+                tag.push(subtags[i].into());
+                i += 1;
+            }
+            //script
+            if i < subtags.len() && alpha(subtags[i], 4, 4) {
+                let (first, rest) = subtags[i].split_at(1);
+                tag.push(first.to_ascii_uppercase() + rest);
+                i += 1;
+            }
+            //region
+            if i < subtags.len()
+                && (alpha(subtags[i], 2, 2) || (subtags[i].len() == 3 && digits(subtags[i])))
+            {
+                tag.push(subtags[i].to_ascii_uppercase());
+                i += 1;
+            }
+            //variants, each at most once
+            let mut variants = vec![];
+            while i < subtags.len()
+                && (alnum(subtags[i], 5, 8)
+                    || (alnum(subtags[i], 4, 4) && subtags[i].starts_with(|c: char| c.is_ascii_digit())))
+            {
+                if variants.contains(&subtags[i]) {
+                    return Err(invalid());
+                }
+                variants.push(subtags[i]);
+                tag.push(subtags[i].into());
+                i += 1;
+            }
+            //extensions, a singleton other than x followed by its subtags, each singleton at most once
+            let mut singletons = vec![];
+            while i < subtags.len() && alnum(subtags[i], 1, 1) && subtags[i] != "x" {
+                if singletons.contains(&subtags[i]) {
+                    return Err(invalid());
+                }
+                singletons.push(subtags[i]);
+                tag.push(subtags[i].into());
+                i += 1;
+                let start = i;
+                while i < subtags.len() && alnum(subtags[i], 2, 8) {
+                    tag.push(subtags[i].into());
+                    i += 1;
+                }
+                if i == start {
+                    return Err(invalid());
+                }
+            }
+        }
+        //private use
+        if i < subtags.len() && subtags[i] == "x" {
+            tag.push("x".into());
+            i += 1;
+            let start = i;
+            while i < subtags.len() && alnum(subtags[i], 1, 8) {
+                tag.push(subtags[i].into());
+                i += 1;
+            }
+            if i == start {
+                return Err(invalid());
+            }
+        }
+        if i != subtags.len() {
+            return Err(invalid());
+        }
+        Ok(LanguageTag(tag.join("-")))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for LanguageTag {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        LanguageTag::parse(&value)
+    }
+}
+
+impl From<LanguageTag> for String {
+    fn from(tag: LanguageTag) -> Self {
+        tag.0
+    }
+}
+
+impl fmt::Display for LanguageTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+/**
+GET /courses/facets, the values course filters can take with how many published courses have each
+level and format list every allowed value, also those no course uses yet,
+language lists the tags in use, most used first
+**/
+#[derive(Serialize, Debug, Clone)]
+pub struct CourseFacets {
+    pub level: Vec<FacetCount>,
+    pub format: Vec<FacetCount>,
+    pub language: Vec<FacetCount>,
+}
+
+impl CourseFacets {
+    //rows are (facet, value, count) as counted by the database
+    pub fn from_counts(rows: Vec<(String, String, i64)>) -> Self {
+        let count = |facet: &str, value: &str| {
+            rows.iter()
+                .find(|(row_facet, row_value, _)| row_facet == facet && row_value == value)
+                .map_or(0, |(_, _, count)| *count)
+        };
+        let facet = |facet: &str, value: &str| FacetCount {
+            value: value.into(),
+            count: count(facet, value),
+        };
+        let mut language: Vec<FacetCount> = rows
+            .iter()
+            .filter(|(facet, _, _)| facet == "language")
+            .map(|(_, value, count)| FacetCount { value: value.clone(), count: *count })
+            .collect();
+        language.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+
+        CourseFacets {
+            level: CourseLevel::ALL.iter().map(|level| facet("level", level.as_str())).collect(),
+            format: CourseFormat::ALL.iter().map(|format| facet("format", format.as_str())).collect(),
+            language,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_tags_are_checked_and_normalized() {
+        let parse = |tag: &str| LanguageTag::parse(tag).map(String::from);
+        assert_eq!(parse("en").unwrap(), "en");
+        assert_eq!(parse("EN-us").unwrap(), "en-US");
+        assert_eq!(parse("zh-hant-tw").unwrap(), "zh-Hant-TW");
+        assert_eq!(parse("es-419").unwrap(), "es-419");
+        assert_eq!(parse("sl-rozaj-biske").unwrap(), "sl-rozaj-biske");
+        assert_eq!(parse("de-DE-u-co-phonebk").unwrap(), "de-DE-u-co-phonebk");
+        assert_eq!(parse("en-x-pirate").unwrap(), "en-x-pirate");
+
+        for tag in ["English", "e", "en-", "en--us", "en-US-US", "de-1996-1996", "en-u", "x", "en_US"] {
+            assert!(parse(tag).is_err(), "{} should be rejected", tag);
+        }
+    }
+
+    #[test]
+    fn facets_list_every_level_and_format() {
+        let facets = CourseFacets::from_counts(vec![
+            ("level".into(), "beginner".into(), 3),
+            ("format".into(), "hybrid".into(), 1),
+            ("language".into(), "en".into(), 2),
+            ("language".into(), "fr".into(), 5),
+        ]);
+        assert_eq!(facets.level.len(), CourseLevel::ALL.len());
+        assert_eq!(facets.level[0], FacetCount { value: "beginner".into(), count: 3 });
+        assert_eq!(facets.level[1].count, 0);
+        assert_eq!(facets.format[3].count, 1);
+        assert_eq!(facets.language[0].value, "fr");
+    }
+}
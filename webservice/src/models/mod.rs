@@ -1 +1,20 @@
+pub mod admin;
+pub mod audit;
+pub mod certificate;
 pub mod course;
+pub mod duration;
+pub mod enrollment;
+pub mod exchange_rate;
+pub mod facet;
+pub mod money;
+pub mod notification;
+pub mod nullable;
+pub mod outline;
+pub mod prerequisite;
+pub mod progress;
+pub mod quiz;
+pub mod review;
+pub mod revision;
+pub mod student;
+pub mod taxonomy;
+pub mod translation;
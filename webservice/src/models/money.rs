@@ -0,0 +1,480 @@
+use serde::de::{self, Deserializer, MapAccess, Visitor};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgTypeInfo, PgValueRef, Postgres};
+use sqlx::types::Json;
+use std::fmt;
+
+//ISO 4217 codes the service accepts, with the number of minor unit digits
+const SUPPORTED_CURRENCIES: &[(&str, u32)] = &[
+    ("AUD", 2),
+    ("BHD", 3),
+    ("BRL", 2),
+    ("CAD", 2),
+    ("CHF", 2),
+    ("CNY", 2),
+    ("DKK", 2),
+    ("EUR", 2),
+    ("GBP", 2),
+    ("HKD", 2),
+    ("INR", 2),
+    ("JPY", 0),
+    ("KRW", 0),
+    ("KWD", 3),
+    ("MXN", 2),
+    ("NOK", 2),
+    ("NZD", 2),
+    ("PLN", 2),
+    ("SEK", 2),
+    ("SGD", 2),
+    ("USD", 2),
+    ("ZAR", 2),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Currency {
+    code: &'static str,
+    exponent: u32,
+}
+
+impl Currency {
+    pub fn from_code(code: &str) -> Result<Currency, String> {
+        let code = code.trim().to_ascii_uppercase();
+        SUPPORTED_CURRENCIES
+            .iter()
+            .find(|(supported, _)| *supported == code)
+            .map(|&(code, exponent)| Currency { code, exponent })
+            .ok_or_else(|| format!("Currency {} is not supported", code))
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code)
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Currency::from_code(&code).map_err(de::Error::custom)
+    }
+}
+
+//an amount in the smallest unit of its currency, 1999 EUR is 19.99 EUR
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Money {
+    amount_minor: i64,
+    currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount_minor: i64, currency: Currency) -> Result<Money, String> {
+        if amount_minor <= 0 {
+            return Err("A price must be positive, use \"free\" for free courses".into());
+        }
+        Ok(Money { amount_minor, currency })
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    //the amount in another currency, one unit of this currency buys rate units of it
+    //worked out in integers and rounded half away from zero to the other currency's minor unit,
+    //a price too small to be worth a minor unit there comes out as 0
+    pub fn convert(&self, to: Currency, rate: Rate) -> Money {
+        let (rate_numerator, rate_denominator) = rate.fraction();
+        let mut numerator = self.amount_minor as i128 * rate_numerator;
+        let mut denominator = rate_denominator;
+        let shift = to.exponent as i32 - self.currency.exponent as i32;
+        if shift >= 0 {
+            numerator *= 10_i128.pow(shift as u32);
+        } else {
+            denominator *= 10_i128.pow(shift.unsigned_abs());
+        }
+        let rounded = (numerator * 2 + denominator) / (denominator * 2);
+        Money {
+            amount_minor: i64::try_from(rounded).unwrap_or(i64::MAX),
+            currency: to,
+        }
+    }
+
+    //"19.99", with as many decimals as the currency has minor digits
+    pub fn amount(&self) -> String {
+        let exponent = self.currency.exponent;
+        if exponent == 0 {
+            return self.amount_minor.to_string();
+        }
+        let scale = 10_i64.pow(exponent);
+        format!(
+            "{}.{:0width$}",
+            self.amount_minor / scale,
+            self.amount_minor % scale,
+            width = exponent as usize
+        )
+    }
+
+    //reads "19.99 EUR" or "EUR 19.99"
+    pub fn parse(value: &str) -> Result<Money, String> {
+        let parts: Vec<&str> = value.split_whitespace().collect();
+        let (amount, code) = match parts.as_slice() {
+            [amount, code] if amount.starts_with(|c: char| c.is_ascii_digit()) => (*amount, *code),
+            [code, amount] => (*amount, *code),
+            _ => return Err(format!("{} is not a price like \"19.99 EUR\"", value)),
+        };
+        let currency = Currency::from_code(code)?;
+
+        let (units, decimals) = amount.split_once('.').unwrap_or((amount, ""));
+        let all_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if units.is_empty() || !all_digits(units) || !all_digits(decimals) {
+            return Err(format!("{} is not a valid amount", amount));
+        }
+        if decimals.len() > currency.exponent as usize {
+            return Err(format!(
+                "{} takes at most {} decimals",
+                currency.code, currency.exponent
+            ));
+        }
+        let padded = format!("{}{:0<width$}", units, decimals, width = currency.exponent as usize);
+        let amount_minor = padded
+            .parse::<i64>()
+            .map_err(|_err| format!("{} is too large", amount))?;
+        Money::new(amount_minor, currency)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.amount(), self.currency.code)
+    }
+}
+
+//decimals an exchange rate is kept with, as exact as the exchange_rate.rate column
+const RATE_DECIMALS: u32 = 9;
+
+/**
+How many units of one currency a unit of another buys, as an exact decimal
+kept as an integer in billionths, so every conversion rounds the same way
+the inverse of a stored rate keeps the stored value and divides by it instead
+**/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    scaled: i64,
+    inverted: bool,
+}
+
+impl Rate {
+    pub const ONE: Rate = Rate { scaled: 10_i64.pow(RATE_DECIMALS), inverted: false };
+
+    //reads a positive decimal like "0.92" or "161.5", with at most RATE_DECIMALS decimals
+    pub fn parse(value: &str) -> Result<Rate, String> {
+        let value = value.trim();
+        let (units, decimals) = value.split_once('.').unwrap_or((value, ""));
+        let all_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if units.is_empty() || !all_digits(units) || !all_digits(decimals) {
+            return Err(format!("{} is not a valid rate", value));
+        }
+        if decimals.len() > RATE_DECIMALS as usize {
+            return Err(format!("a rate takes at most {} decimals", RATE_DECIMALS));
+        }
+        let scaled = format!("{}{:0<width$}", units, decimals, width = RATE_DECIMALS as usize)
+            .parse::<i64>()
+            .map_err(|_err| format!("{} is too large for a rate", value))?;
+        if scaled == 0 {
+            return Err("a rate must be positive".into());
+        }
+        Ok(Rate { scaled, inverted: false })
+    }
+
+    //the rate the other way round
+    pub fn inverse(self) -> Rate {
+        Rate { inverted: !self.inverted, ..self }
+    }
+
+    //the rate as numerator / denominator
+    fn fraction(&self) -> (i128, i128) {
+        let scale = 10_i128.pow(RATE_DECIMALS);
+        if self.inverted {
+            (scale, self.scaled as i128)
+        } else {
+            (self.scaled as i128, scale)
+        }
+    }
+
+    //the closest f64, to show the rate that was used
+    pub fn to_f64(self) -> f64 {
+        let (numerator, denominator) = self.fraction();
+        numerator as f64 / denominator as f64
+    }
+}
+
+//"0.92", the exact decimal of a stored rate
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.inverted {
+            return write!(f, "{}", self.to_f64());
+        }
+        let scale = 10_i64.pow(RATE_DECIMALS);
+        let decimals = format!("{:0width$}", self.scaled % scale, width = RATE_DECIMALS as usize);
+        match decimals.trim_end_matches('0') {
+            "" => write!(f, "{}", self.scaled / scale),
+            decimals => write!(f, "{}.{}", self.scaled / scale, decimals),
+        }
+    }
+}
+
+//a JSON number like the other amounts in responses
+impl Serialize for Rate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_f64())
+    }
+}
+
+//a string or a number, a number is read as the shortest decimal that gives it back
+impl<'de> Deserialize<'de> for Rate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RateVisitor;
+
+        impl<'de> Visitor<'de> for RateVisitor {
+            type Value = Rate;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a positive decimal like 0.92")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Rate, E> {
+                Rate::parse(value).map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Rate, E> {
+                Rate::parse(&value.to_string()).map_err(E::custom)
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Rate, E> {
+                Rate::parse(&value.to_string()).map_err(E::custom)
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Rate, E> {
+                Rate::parse(&value.to_string()).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(RateVisitor)
+    }
+}
+
+//read from the exchange_rate.rate numeric column cast to text
+impl sqlx::Type<Postgres> for Rate {
+    fn type_info() -> PgTypeInfo {
+        <&str as sqlx::Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <&str as sqlx::Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Postgres> for Rate {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(Rate::parse(<&str as sqlx::Decode<Postgres>>::decode(value)?)?)
+    }
+}
+
+/**
+Price of a course, a course without a price has none at all
+responses carry the minor units and currency plus the amount formatted for display:
+{"kind": "paid", "amount_minor": 1999, "currency": "EUR", "amount": "19.99", "formatted": "19.99 EUR"}
+{"kind": "free", "formatted": "Free"}
+requests may send that object (only kind, amount_minor and currency are read),
+or a string: "free", "19.99 EUR" or "EUR 19.99", which also works in CSV and XML
+**/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Price {
+    Free,
+    Paid(Money),
+}
+
+impl Price {
+    //the course.price_minor and course.currency columns, free is 0 without a currency
+    pub fn columns(price: Option<&Price>) -> (Option<i64>, Option<&'static str>) {
+        match price {
+            None => (None, None),
+            Some(Price::Free) => (Some(0), None),
+            Some(Price::Paid(money)) => (Some(money.amount_minor), Some(money.currency.code)),
+        }
+    }
+
+    pub fn formatted(&self) -> String {
+        match self {
+            Price::Free => "Free".into(),
+            Price::Paid(money) => money.to_string(),
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Price, String> {
+        if value.trim().eq_ignore_ascii_case("free") {
+            Ok(Price::Free)
+        } else {
+            Money::parse(value).map(Price::Paid)
+        }
+    }
+}
+
+impl Serialize for Price {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Price::Free => {
+                let mut state = serializer.serialize_struct("Price", 2)?;
+                state.serialize_field("kind", "free")?;
+                state.serialize_field("formatted", &self.formatted())?;
+                state.end()
+            }
+            Price::Paid(money) => {
+                let mut state = serializer.serialize_struct("Price", 5)?;
+                state.serialize_field("kind", "paid")?;
+                state.serialize_field("amount_minor", &money.amount_minor)?;
+                state.serialize_field("currency", &money.currency)?;
+                state.serialize_field("amount", &money.amount())?;
+                state.serialize_field("formatted", &self.formatted())?;
+                state.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Price {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PriceVisitor;
+
+        impl<'de> Visitor<'de> for PriceVisitor {
+            type Value = Price;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("\"free\", a price like \"19.99 EUR\", or {\"amount_minor\", \"currency\"}")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Price, E> {
+                Price::parse(value).map_err(E::custom)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Price, A::Error> {
+                let mut kind: Option<String> = None;
+                let mut amount_minor: Option<i64> = None;
+                let mut currency: Option<Currency> = None;
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "kind" => kind = Some(map.next_value()?),
+                        "amount_minor" => amount_minor = Some(map.next_value()?),
+                        "currency" => currency = map.next_value()?,
+                        _ => {
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+                match kind.as_deref() {
+                    Some("free") => Ok(Price::Free),
+                    Some("paid") | None => {
+                        let amount_minor =
+                            amount_minor.ok_or_else(|| de::Error::missing_field("amount_minor"))?;
+                        let currency = currency.ok_or_else(|| de::Error::missing_field("currency"))?;
+                        Money::new(amount_minor, currency)
+                            .map(Price::Paid)
+                            .map_err(de::Error::custom)
+                    }
+                    Some(other) => Err(de::Error::unknown_variant(other, &["free", "paid"])),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(PriceVisitor)
+    }
+}
+
+//read from the generated course.price jsonb column
+impl sqlx::Type<Postgres> for Price {
+    fn type_info() -> PgTypeInfo {
+        <Json<Price> as sqlx::Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <Json<Price> as sqlx::Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Postgres> for Price {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(<Json<Price> as sqlx::Decode<Postgres>>::decode(value)?.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amounts_follow_the_currency_exponent() {
+        assert_eq!(Money::parse("19.99 EUR").unwrap().amount_minor, 1999);
+        assert_eq!(Money::parse("EUR 19.9").unwrap().amount_minor, 1990);
+        assert_eq!(Money::parse("1500 JPY").unwrap().amount(), "1500");
+        assert_eq!(Money::parse("1.5 kwd").unwrap().to_string(), "1.500 KWD");
+        assert_eq!(Money::parse("12 usd").unwrap().to_string(), "12.00 USD");
+
+        assert!(Money::parse("1.999 USD").is_err());
+        assert!(Money::parse("10 XYZ").is_err());
+        assert!(Money::parse("0 USD").is_err());
+        assert!(Money::parse("-5 USD").is_err());
+        assert!(Money::parse("100").is_err());
+    }
+
+    #[test]
+    fn conversion_rounds_to_the_target_minor_unit() {
+        let eur = Money::parse("19.99 EUR").unwrap();
+        let jpy = Currency::from_code("JPY").unwrap();
+        let kwd = Currency::from_code("KWD").unwrap();
+        let rate = |value: &str| Rate::parse(value).unwrap();
+        assert_eq!(eur.convert(jpy, rate("161.5")).to_string(), "3228 JPY");
+        assert_eq!(eur.convert(kwd, rate("0.3335")).to_string(), "6.667 KWD");
+        assert_eq!(Money::parse("100 JPY").unwrap().convert(eur.currency(), rate("0.0062")).to_string(), "0.62 EUR");
+        //exactly half a minor unit rounds up, below half it is worth nothing
+        assert_eq!(Money::parse("1 JPY").unwrap().convert(eur.currency(), rate("0.005")).to_string(), "0.01 EUR");
+        assert_eq!(Money::parse("1 JPY").unwrap().convert(eur.currency(), rate("0.0049")).to_string(), "0.00 EUR");
+        //the inverse divides by the stored rate, 10 EUR at 1 USD = 0.8 EUR
+        assert_eq!(Money::parse("10 EUR").unwrap().convert(Currency::from_code("USD").unwrap(), rate("0.8").inverse()).to_string(), "12.50 USD");
+    }
+
+    #[test]
+    fn rates_are_exact_decimals() {
+        assert_eq!(Rate::parse("0.92").unwrap().to_string(), "0.92");
+        assert_eq!(Rate::parse("161.500").unwrap().to_string(), "161.5");
+        assert_eq!(Rate::parse("2").unwrap().to_string(), "2");
+        assert_eq!(serde_json::from_str::<Rate>("0.1").unwrap(), Rate::parse("0.1").unwrap());
+        assert_eq!(serde_json::from_str::<Rate>("\"0.1\"").unwrap(), Rate::parse("0.1").unwrap());
+        assert_eq!(Rate::parse("0.8").unwrap().inverse().to_f64(), 1.25);
+
+        assert!(Rate::parse("0").is_err());
+        assert!(Rate::parse("-1").is_err());
+        assert!(Rate::parse("0.0000000001").is_err());
+        assert!(Rate::parse("1e3").is_err());
+    }
+
+    #[test]
+    fn price_reads_strings_and_objects() {
+        let free: Price = serde_json::from_str("\"free\"").unwrap();
+        assert_eq!(free, Price::Free);
+        let paid: Price =
+            serde_json::from_str(r#"{"amount_minor": 2500, "currency": "GBP"}"#).unwrap();
+        assert_eq!(paid.formatted(), "25.00 GBP");
+        assert_eq!(Price::columns(Some(&paid)), (Some(2500), Some("GBP")));
+        assert_eq!(Price::columns(Some(&Price::Free)), (Some(0), None));
+
+        //the response shape reads back as the same price
+        let json = serde_json::to_string(&paid).unwrap();
+        assert_eq!(serde_json::from_str::<Price>(&json).unwrap(), paid);
+        assert!(serde_json::from_str::<Price>("100").is_err());
+    }
+}
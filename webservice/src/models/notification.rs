@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+//what happened, stored as text in notification_event.kind
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum NotificationKind {
+    //a seat freed up and the student moved from the waitlist into the course
+    WaitlistPromoted,
+}
+
+impl NotificationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::WaitlistPromoted => "waitlist_promoted",
+        }
+    }
+}
+
+//a row of notification_event, payload carries the details a message needs
+#[derive(Serialize, Debug, Clone)]
+pub struct NotificationEvent {
+    pub id: i64,
+    pub student_id: i32,
+    pub course_id: Option<i32>,
+    pub kind: NotificationKind,
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+//GET /students/{student_id}/notifications?after=41, only events newer than the last one seen
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct NotificationQuery {
+    pub after: Option<i64>,
+}
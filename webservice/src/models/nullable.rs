@@ -0,0 +1,14 @@
+use serde::{Deserialize, Deserializer};
+
+/**
+Reads an update field that can be left out, sent as null, or sent with a value
+left out is None and keeps the current value, null is Some(None) and clears it
+use it with #[serde(default, deserialize_with = "clearable")] on an Option<Option<T>>
+**/
+pub fn clearable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
@@ -0,0 +1,257 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::errors::MyError;
+use crate::models::nullable::clearable;
+
+//longest lesson content, a page of text rather than a file upload
+const MAX_CONTENT_LEN: usize = 100_000;
+
+//a row of module, one chapter of a course's syllabus
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct Module {
+    pub id: i32,
+    pub course_id: i32,
+    //1 for the first module, without gaps
+    pub position: i32,
+    pub title: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+//a row of lesson
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct Lesson {
+    pub id: i32,
+    pub module_id: i32,
+    //1 for the first lesson of its module, without gaps
+    pub position: i32,
+    pub title: String,
+    pub content: Option<String>,
+    //estimated time to work through the lesson
+    pub duration_minutes: Option<i32>,
+    //counts towards course completion, optional lessons do not
+    pub required: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+//a module with its lessons in order, as listed in a course outline
+#[derive(Serialize, Debug, Clone)]
+pub struct ModuleOutline {
+    #[serde(flatten)]
+    pub module: Module,
+    //sum of the lesson estimates, None when no lesson has one
+    pub duration_minutes: Option<i64>,
+    pub lessons: Vec<Lesson>,
+}
+
+impl ModuleOutline {
+    //groups lessons under their modules, both already sorted by position
+    pub fn build(modules: Vec<Module>, lessons: Vec<Lesson>) -> Vec<ModuleOutline> {
+        let mut outline: Vec<ModuleOutline> = modules
+            .into_iter()
+            .map(|module| ModuleOutline {
+                module,
+                duration_minutes: None,
+                lessons: vec![],
+            })
+            .collect();
+        for lesson in lessons {
+            if let Some(entry) = outline.iter_mut().find(|entry| entry.module.id == lesson.module_id) {
+                if let Some(minutes) = lesson.duration_minutes {
+                    *entry.duration_minutes.get_or_insert(0) += minutes as i64;
+                }
+                entry.lessons.push(lesson);
+            }
+        }
+        outline
+    }
+}
+
+fn validate_text(field: &str, value: Option<&String>, max: usize) -> Result<(), MyError> {
+    match value {
+        Some(value) if value.chars().count() > max => Err(MyError::InvalidInput(format!(
+            "{} must be at most {} characters",
+            field, max
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn validate_title(title: Option<&String>) -> Result<(), MyError> {
+    if title.is_some_and(|title| title.trim().is_empty()) {
+        return Err(MyError::InvalidInput("title must not be empty".into()));
+    }
+    validate_text("title", title, 140)
+}
+
+fn validate_position(position: Option<i32>) -> Result<(), MyError> {
+    if position.is_some_and(|position| position < 1) {
+        return Err(MyError::InvalidInput("position starts at 1".into()));
+    }
+    Ok(())
+}
+
+fn validate_duration(duration_minutes: Option<i32>) -> Result<(), MyError> {
+    if duration_minutes.is_some_and(|minutes| minutes < 1) {
+        return Err(MyError::InvalidInput("duration_minutes must be positive".into()));
+    }
+    Ok(())
+}
+
+//POST /courses/{teacher_id}/{course_id}/modules
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateModule {
+    pub title: String,
+    pub description: Option<String>,
+    //where to insert the module, the end of the course when missing
+    pub position: Option<i32>,
+}
+
+impl CreateModule {
+    pub fn validate(&self) -> Result<(), MyError> {
+        validate_title(Some(&self.title))?;
+        validate_text("description", self.description.as_ref(), 2000)?;
+        validate_position(self.position)
+    }
+}
+
+//PUT /courses/{teacher_id}/{course_id}/modules/{module_id}, missing fields keep their value
+//"description": null clears it
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateModule {
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "clearable")]
+    pub description: Option<Option<String>>,
+}
+
+impl UpdateModule {
+    pub fn validate(&self) -> Result<(), MyError> {
+        validate_title(self.title.as_ref())?;
+        validate_text("description", self.description.as_ref().and_then(Option::as_ref), 2000)
+    }
+}
+
+//POST /courses/{teacher_id}/{course_id}/modules/{module_id}/lessons
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateLesson {
+    pub title: String,
+    pub content: Option<String>,
+    pub duration_minutes: Option<i32>,
+    //true when missing
+    pub required: Option<bool>,
+    //where to insert the lesson, the end of the module when missing
+    pub position: Option<i32>,
+}
+
+impl CreateLesson {
+    pub fn validate(&self) -> Result<(), MyError> {
+        validate_title(Some(&self.title))?;
+        validate_text("content", self.content.as_ref(), MAX_CONTENT_LEN)?;
+        validate_duration(self.duration_minutes)?;
+        validate_position(self.position)
+    }
+}
+
+//PUT .../lessons/{lesson_id}, missing fields keep their value
+//"content": null and "duration_minutes": null clear them
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateLesson {
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "clearable")]
+    pub content: Option<Option<String>>,
+    #[serde(default, deserialize_with = "clearable")]
+    pub duration_minutes: Option<Option<i32>>,
+    pub required: Option<bool>,
+}
+
+impl UpdateLesson {
+    pub fn validate(&self) -> Result<(), MyError> {
+        validate_title(self.title.as_ref())?;
+        validate_text("content", self.content.as_ref().and_then(Option::as_ref), MAX_CONTENT_LEN)?;
+        validate_duration(self.duration_minutes.flatten())
+    }
+}
+
+/**
+PUT .../modules/reorder with {"ids": [3, 1, 2]}, and the same for .../lessons/reorder
+ids lists every module of the course (or lesson of the module) once, in the new order
+**/
+#[derive(Deserialize, Debug, Clone)]
+pub struct Reorder {
+    pub ids: Vec<i32>,
+}
+
+impl Reorder {
+    //the new order must be a permutation of the current one
+    pub fn validate(&self, current: &[i32]) -> Result<(), MyError> {
+        let mut given = self.ids.clone();
+        let mut current = current.to_vec();
+        given.sort_unstable();
+        current.sort_unstable();
+        if given != current {
+            return Err(MyError::InvalidInput(
+                "ids must list every current id exactly once".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lesson(id: i32, module_id: i32, duration_minutes: Option<i32>) -> Lesson {
+        Lesson {
+            id,
+            module_id,
+            position: id,
+            title: format!("lesson {}", id),
+            content: None,
+            duration_minutes,
+            required: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn outline_groups_lessons_and_sums_estimates() {
+        let module = |id: i32, position: i32| Module {
+            id,
+            course_id: 1,
+            position,
+            title: format!("module {}", id),
+            description: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let outline = ModuleOutline::build(
+            vec![module(7, 1), module(3, 2)],
+            vec![lesson(1, 3, Some(30)), lesson(2, 7, None), lesson(4, 3, Some(45))],
+        );
+        assert_eq!(outline[0].module.id, 7);
+        assert_eq!(outline[0].duration_minutes, None);
+        assert_eq!(outline[1].lessons.len(), 2);
+        assert_eq!(outline[1].duration_minutes, Some(75));
+    }
+
+    #[test]
+    fn reorder_must_be_a_permutation() {
+        let reorder = Reorder { ids: vec![3, 1, 2] };
+        assert!(reorder.validate(&[1, 2, 3]).is_ok());
+        assert!(reorder.validate(&[1, 2, 3, 4]).is_err());
+        assert!(Reorder { ids: vec![1, 1, 2] }.validate(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn updates_tell_a_missing_field_from_null() {
+        let update: UpdateLesson = serde_json::from_str(r#"{"title": "Loops", "content": null}"#).unwrap();
+        assert_eq!(update.content, Some(None));
+        assert_eq!(update.duration_minutes, None);
+        let update: UpdateModule = serde_json::from_str(r#"{"description": "Basics"}"#).unwrap();
+        assert_eq!(update.description, Some(Some("Basics".into())));
+    }
+}
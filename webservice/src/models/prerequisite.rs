@@ -0,0 +1,172 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::errors::MyError;
+use crate::models::course::{CourseStatus, Viewer};
+
+const MAX_PREREQUISITES: usize = 20;
+
+//a course another course requires
+#[derive(Serialize, Debug, Clone)]
+pub struct PrerequisiteCourse {
+    pub id: i32,
+    pub teacher_id: i32,
+    pub name: String,
+    pub status: CourseStatus,
+}
+
+impl PrerequisiteCourse {
+    //unpublished courses are only listed to their own teacher
+    pub fn visible_to(&self, viewer: &Viewer) -> bool {
+        self.status == CourseStatus::Published || viewer.owns(self.teacher_id)
+    }
+}
+
+//PUT /courses/{teacher_id}/{course_id}/prerequisites, replaces the course's direct prerequisites
+#[derive(Deserialize, Debug, Clone)]
+pub struct SetPrerequisites {
+    pub prerequisite_ids: Vec<i32>,
+}
+
+impl SetPrerequisites {
+    pub fn validate(self, course_id: i32) -> Result<Vec<i32>, MyError> {
+        let mut ids = self.prerequisite_ids;
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.contains(&course_id) {
+            return Err(MyError::InvalidInput("A course cannot be its own prerequisite".into()));
+        }
+        if ids.len() > MAX_PREREQUISITES {
+            return Err(MyError::InvalidInput(format!("a course can have at most {} prerequisites", MAX_PREREQUISITES)));
+        }
+        Ok(ids)
+    }
+}
+
+/**
+Course ids and the courses each one directly requires
+the stored graph has no cycle, the service checks every change against the graph
+as it would be after the change
+**/
+#[derive(Debug, Clone, Default)]
+pub struct PrerequisiteGraph {
+    prerequisites: HashMap<i32, Vec<i32>>,
+}
+
+impl PrerequisiteGraph {
+    //edges are (course_id, prerequisite_id)
+    pub fn new(edges: Vec<(i32, i32)>) -> Self {
+        let mut prerequisites: HashMap<i32, Vec<i32>> = HashMap::new();
+        for (course_id, prerequisite_id) in edges {
+            prerequisites.entry(course_id).or_default().push(prerequisite_id);
+        }
+        for ids in prerequisites.values_mut() {
+            ids.sort_unstable();
+        }
+        PrerequisiteGraph { prerequisites }
+    }
+
+    //the graph once course_id requires exactly these courses
+    pub fn with_prerequisites(mut self, course_id: i32, prerequisite_ids: &[i32]) -> Self {
+        self.prerequisites.insert(course_id, prerequisite_ids.to_vec());
+        self
+    }
+
+    fn direct(&self, course_id: i32) -> &[i32] {
+        self.prerequisites.get(&course_id).map_or(&[], Vec::as_slice)
+    }
+
+    //a path from course_id back to itself, e.g. [3, 5, 3] when 3 requires 5 and 5 requires 3
+    pub fn find_cycle(&self, course_id: i32) -> Option<Vec<i32>> {
+        let mut path = vec![course_id];
+        let mut cleared = HashSet::new();
+        if self.cycle_from(course_id, course_id, &mut path, &mut cleared) {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    fn cycle_from(&self, start: i32, current: i32, path: &mut Vec<i32>, cleared: &mut HashSet<i32>) -> bool {
+        for &next in self.direct(current) {
+            if next == start {
+                path.push(next);
+                return true;
+            }
+            //a course already searched leads nowhere near start, and no cycle skips start
+            if !cleared.insert(next) {
+                continue;
+            }
+            path.push(next);
+            if self.cycle_from(start, next, path, cleared) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    //every course course_id requires directly or through others, each after the courses it requires
+    pub fn chain(&self, course_id: i32) -> Vec<i32> {
+        let mut order = Vec::new();
+        let mut seen = HashSet::from([course_id]);
+        self.visit(course_id, &mut seen, &mut order);
+        order
+    }
+
+    fn visit(&self, course_id: i32, seen: &mut HashSet<i32>, order: &mut Vec<i32>) {
+        for &prerequisite_id in self.direct(course_id) {
+            if seen.insert(prerequisite_id) {
+                self.visit(prerequisite_id, seen, order);
+                order.push(prerequisite_id);
+            }
+        }
+    }
+}
+
+//POST /courses/{teacher_id}/{course_id}/prerequisites/overrides
+#[derive(Deserialize, Debug, Clone)]
+pub struct GrantOverride {
+    pub student_id: i32,
+}
+
+//a student the teacher lets enroll without the prerequisites
+#[derive(Serialize, Debug, Clone)]
+pub struct PrerequisiteOverride {
+    pub course_id: i32,
+    pub student_id: i32,
+    pub student_name: String,
+    pub granted_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prerequisites_never_loop_back() {
+        //4 requires 2 and 3, both require 1
+        let graph = PrerequisiteGraph::new(vec![(4, 2), (4, 3), (2, 1), (3, 1)]);
+        assert_eq!(graph.chain(4), [1, 2, 3]);
+        assert_eq!(graph.chain(1), Vec::<i32>::new());
+        assert_eq!(graph.find_cycle(4), None);
+
+        let graph = graph.with_prerequisites(1, &[4]);
+        assert_eq!(graph.find_cycle(1), Some(vec![1, 4, 2, 1]));
+        let graph = graph.with_prerequisites(1, &[]);
+        assert_eq!(graph.find_cycle(1), None);
+
+        assert_eq!(SetPrerequisites { prerequisite_ids: vec![3, 2, 3] }.validate(4).unwrap(), [2, 3]);
+        assert!(SetPrerequisites { prerequisite_ids: vec![4] }.validate(4).is_err());
+    }
+
+    #[test]
+    fn drafts_are_only_listed_to_their_teacher() {
+        let draft = PrerequisiteCourse { id: 2, teacher_id: 1, name: "Draft".into(), status: CourseStatus::Draft };
+        assert!(draft.visible_to(&Viewer { teacher_id: Some(1) }));
+        assert!(!draft.visible_to(&Viewer { teacher_id: Some(2) }));
+        assert!(!draft.visible_to(&Viewer::default()));
+        let published = PrerequisiteCourse { status: CourseStatus::Published, ..draft };
+        assert!(published.visible_to(&Viewer::default()));
+    }
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use crate::models::enrollment::{Enrollment, EnrollmentStatus};
+
+//where a student is in one lesson, both times are None for a lesson not started yet
+#[derive(Serialize, Debug, Clone)]
+pub struct LessonProgress {
+    pub lesson_id: i32,
+    pub module_id: i32,
+    pub title: String,
+    pub required: bool,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    //last start or completion of the lesson
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/**
+Lesson counts of one student in one course
+when no lesson is marked required every lesson counts, so a course of optional lessons can still be completed
+a course without lessons is never complete
+**/
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProgressCounts {
+    pub total_lessons: i64,
+    pub completed_lessons: i64,
+    pub required_lessons: i64,
+    pub completed_required_lessons: i64,
+}
+
+impl ProgressCounts {
+    pub fn from_lessons(lessons: &[LessonProgress]) -> Self {
+        let count = |keep: &dyn Fn(&LessonProgress) -> bool| lessons.iter().filter(|lesson| keep(lesson)).count() as i64;
+        ProgressCounts {
+            total_lessons: lessons.len() as i64,
+            completed_lessons: count(&|lesson| lesson.completed_at.is_some()),
+            required_lessons: count(&|lesson| lesson.required),
+            completed_required_lessons: count(&|lesson| lesson.required && lesson.completed_at.is_some()),
+        }
+    }
+
+    //(completed, out of) of the lessons that decide completion
+    fn counted(&self) -> (i64, i64) {
+        if self.required_lessons > 0 {
+            (self.completed_required_lessons, self.required_lessons)
+        } else {
+            (self.completed_lessons, self.total_lessons)
+        }
+    }
+
+    //rounded down, so 100 means complete
+    pub fn percent(&self) -> i64 {
+        match self.counted() {
+            (_, 0) => 0,
+            (completed, out_of) => completed * 100 / out_of,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        let (completed, out_of) = self.counted();
+        out_of > 0 && completed >= out_of
+    }
+}
+
+//GET /courses/{teacher_id}/{course_id}/enrollments/{student_id}/progress
+#[derive(Serialize, Debug, Clone)]
+pub struct CourseProgress {
+    pub course_id: i32,
+    pub student_id: i32,
+    pub status: EnrollmentStatus,
+    #[serde(flatten)]
+    pub counts: ProgressCounts,
+    pub completion_percent: i64,
+    //latest start or completion of a lesson
+    pub last_activity_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub lessons: Vec<LessonProgress>,
+}
+
+impl CourseProgress {
+    pub fn new(enrollment: &Enrollment, lessons: Vec<LessonProgress>) -> Self {
+        let counts = ProgressCounts::from_lessons(&lessons);
+        CourseProgress {
+            course_id: enrollment.course_id,
+            student_id: enrollment.student_id,
+            status: enrollment.status,
+            counts,
+            completion_percent: counts.percent(),
+            last_activity_at: lessons.iter().filter_map(|lesson| lesson.updated_at).max(),
+            completed_at: enrollment.completed_at,
+            lessons,
+        }
+    }
+}
+
+//GET /courses/{teacher_id}/{course_id}/progress, one student of the roster
+#[derive(Serialize, Debug, Clone)]
+pub struct RosterProgress {
+    pub student_id: i32,
+    pub name: String,
+    pub email: String,
+    pub status: EnrollmentStatus,
+    #[serde(flatten)]
+    pub counts: ProgressCounts,
+    pub completion_percent: i64,
+    pub last_activity_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl RosterProgress {
+    //most progress first, ties by most recent activity, students without any activity last
+    pub fn sort(roster: &mut [RosterProgress]) {
+        roster.sort_by(|a, b| {
+            b.completion_percent
+                .cmp(&a.completion_percent)
+                .then_with(|| b.last_activity_at.cmp(&a.last_activity_at))
+                .then_with(|| a.student_id.cmp(&b.student_id))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completion_counts_required_lessons() {
+        let counts = ProgressCounts {
+            total_lessons: 5,
+            completed_lessons: 3,
+            required_lessons: 3,
+            completed_required_lessons: 2,
+        };
+        assert_eq!(counts.percent(), 66);
+        assert!(!counts.is_complete());
+
+        let counts = ProgressCounts { completed_required_lessons: 3, ..counts };
+        assert_eq!(counts.percent(), 100);
+        assert!(counts.is_complete());
+
+        //only optional lessons, every lesson counts
+        let counts = ProgressCounts { total_lessons: 4, completed_lessons: 1, ..Default::default() };
+        assert_eq!(counts.percent(), 25);
+        assert!(!ProgressCounts::default().is_complete());
+    }
+}
@@ -0,0 +1,577 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+use crate::errors::MyError;
+use crate::models::nullable::clearable;
+
+const MAX_QUESTIONS: usize = 100;
+const MAX_OPTIONS: usize = 20;
+const MAX_POINTS: i32 = 100;
+//numeric answers are compared with this much slack on top of the tolerance, for floating point noise
+const NUMERIC_EPSILON: f64 = 1e-9;
+
+/**
+What a question asks and how it is graded, stored as JSON in quiz_question.spec
+{"kind": "single_choice", "options": ["2", "3", "4"], "correct": 2}
+{"kind": "multiple_choice", "options": [...], "correct": [0, 2]}, every correct option and no other
+{"kind": "numeric", "answer": 2.5, "tolerance": 0.01}
+{"kind": "short_text", "answers": ["Paris"], "case_sensitive": false}, compared with whitespace collapsed
+a short_text question without accepted answers is graded by the teacher
+**/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QuestionSpec {
+    SingleChoice {
+        options: Vec<String>,
+        correct: usize,
+    },
+    MultipleChoice {
+        options: Vec<String>,
+        correct: Vec<usize>,
+    },
+    Numeric {
+        answer: f64,
+        #[serde(default)]
+        tolerance: f64,
+    },
+    ShortText {
+        #[serde(default)]
+        answers: Vec<String>,
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+}
+
+fn normalize_text(text: &str, case_sensitive: bool) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if case_sensitive {
+        text
+    } else {
+        text.to_lowercase()
+    }
+}
+
+//option indices of a choice response, sorted and without repeats
+fn choices(response: &Value, options: usize) -> Result<Vec<usize>, String> {
+    let indices = match response {
+        Value::Array(values) => values.iter().map(Value::as_u64).collect::<Option<Vec<u64>>>(),
+        value => value.as_u64().map(|index| vec![index]),
+    }
+    .ok_or("the answer must be option indices")?;
+    let mut indices: Vec<usize> = indices.into_iter().map(|index| index as usize).collect();
+    if indices.iter().any(|&index| index >= options) {
+        return Err(format!("options are numbered 0 to {}", options - 1));
+    }
+    indices.sort_unstable();
+    indices.dedup();
+    Ok(indices)
+}
+
+impl QuestionSpec {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            QuestionSpec::SingleChoice { .. } => "single_choice",
+            QuestionSpec::MultipleChoice { .. } => "multiple_choice",
+            QuestionSpec::Numeric { .. } => "numeric",
+            QuestionSpec::ShortText { .. } => "short_text",
+        }
+    }
+
+    pub fn options(&self) -> Option<&Vec<String>> {
+        match self {
+            QuestionSpec::SingleChoice { options, .. } | QuestionSpec::MultipleChoice { options, .. } => Some(options),
+            _ => None,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(options) = self.options() {
+            if !(2..=MAX_OPTIONS).contains(&options.len()) {
+                return Err(format!("a choice question has 2 to {} options", MAX_OPTIONS));
+            }
+            if options.iter().any(|option| option.trim().is_empty()) {
+                return Err("options must not be empty".into());
+            }
+        }
+        match self {
+            QuestionSpec::SingleChoice { options, correct } if *correct >= options.len() => {
+                Err("correct must be the index of an option".into())
+            }
+            QuestionSpec::MultipleChoice { options, correct } => {
+                let mut indices = correct.clone();
+                indices.sort_unstable();
+                indices.dedup();
+                if indices.is_empty() || indices.len() != correct.len() || indices.iter().any(|&i| i >= options.len()) {
+                    Err("correct must list option indices, each once".into())
+                } else {
+                    Ok(())
+                }
+            }
+            QuestionSpec::Numeric { answer, tolerance } if !answer.is_finite() || !tolerance.is_finite() || *tolerance < 0.0 => {
+                Err("answer must be a number and tolerance a number of at least 0".into())
+            }
+            QuestionSpec::ShortText { answers, .. } if answers.iter().any(|answer| answer.trim().is_empty()) => {
+                Err("accepted answers must not be empty".into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    //the spec without kind and options, what only the teacher sees
+    pub fn answer_key(&self) -> Value {
+        let mut key = serde_json::to_value(self).unwrap_or_default();
+        if let Some(key) = key.as_object_mut() {
+            key.remove("kind");
+            key.remove("options");
+        }
+        key
+    }
+
+    //Some(correct) for an auto-graded answer, None when the teacher grades it,
+    //an error when the response does not fit the kind of question
+    pub fn grade(&self, response: &Value) -> Result<Option<bool>, String> {
+        match self {
+            QuestionSpec::SingleChoice { options, correct } => {
+                let chosen = choices(response, options.len())?;
+                if chosen.len() != 1 || response.is_array() {
+                    return Err("the answer must be one option index".into());
+                }
+                Ok(Some(chosen[0] == *correct))
+            }
+            QuestionSpec::MultipleChoice { options, correct } => {
+                let mut correct = correct.clone();
+                correct.sort_unstable();
+                Ok(Some(choices(response, options.len())? == correct))
+            }
+            QuestionSpec::Numeric { answer, tolerance } => {
+                let value = response
+                    .as_f64()
+                    .filter(|value| value.is_finite())
+                    .ok_or("the answer must be a number")?;
+                Ok(Some((value - answer).abs() <= tolerance + NUMERIC_EPSILON))
+            }
+            QuestionSpec::ShortText { answers, case_sensitive } => {
+                let text = response.as_str().ok_or("the answer must be text")?;
+                if answers.is_empty() {
+                    return Ok(None);
+                }
+                let text = normalize_text(text, *case_sensitive);
+                Ok(Some(answers.iter().any(|answer| normalize_text(answer, *case_sensitive) == text)))
+            }
+        }
+    }
+}
+
+//a row of quiz
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct Quiz {
+    pub id: i32,
+    pub course_id: i32,
+    //set when the quiz belongs to a lesson rather than to the whole course
+    pub lesson_id: Option<i32>,
+    pub title: String,
+    pub description: Option<String>,
+    //None for no limit
+    pub max_attempts: Option<i32>,
+    pub time_limit_minutes: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+//a row of quiz_question
+#[derive(Debug, Clone)]
+pub struct QuizQuestion {
+    pub id: i32,
+    pub position: i32,
+    pub prompt: String,
+    pub points: i32,
+    pub spec: Json<QuestionSpec>,
+}
+
+//a question as shown, answer_key only for the course's teacher
+#[derive(Serialize, Debug, Clone)]
+pub struct QuestionView {
+    pub id: i32,
+    pub position: i32,
+    pub prompt: String,
+    pub points: i32,
+    pub kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answer_key: Option<Value>,
+}
+
+impl QuestionView {
+    pub fn new(question: &QuizQuestion, with_answer_key: bool) -> Self {
+        QuestionView {
+            id: question.id,
+            position: question.position,
+            prompt: question.prompt.clone(),
+            points: question.points,
+            kind: question.spec.kind(),
+            options: question.spec.options().cloned(),
+            answer_key: with_answer_key.then(|| question.spec.answer_key()),
+        }
+    }
+
+    pub fn list(questions: &[QuizQuestion], with_answer_key: bool) -> Vec<QuestionView> {
+        questions.iter().map(|question| QuestionView::new(question, with_answer_key)).collect()
+    }
+}
+
+//GET /courses/{teacher_id}/{course_id}/quizzes/{quiz_id}
+#[derive(Serialize, Debug, Clone)]
+pub struct QuizDetail {
+    #[serde(flatten)]
+    pub quiz: Quiz,
+    pub questions: Vec<QuestionView>,
+}
+
+fn validate_text(field: &str, value: Option<&String>, max: usize) -> Result<(), MyError> {
+    if value.is_some_and(|value| value.trim().is_empty()) {
+        return Err(MyError::InvalidInput(format!("{} must not be empty", field)));
+    }
+    if value.is_some_and(|value| value.chars().count() > max) {
+        return Err(MyError::InvalidInput(format!("{} must be at most {} characters", field, max)));
+    }
+    Ok(())
+}
+
+fn validate_limits(max_attempts: Option<i32>, time_limit_minutes: Option<i32>) -> Result<(), MyError> {
+    if max_attempts.is_some_and(|attempts| attempts < 1) || time_limit_minutes.is_some_and(|minutes| minutes < 1) {
+        return Err(MyError::InvalidInput("max_attempts and time_limit_minutes must be positive".into()));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateQuestion {
+    pub prompt: String,
+    //1 when missing
+    pub points: Option<i32>,
+    #[serde(flatten)]
+    pub spec: QuestionSpec,
+}
+
+//POST /courses/{teacher_id}/{course_id}/quizzes, the quiz with all its questions in order
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateQuiz {
+    pub title: String,
+    pub description: Option<String>,
+    pub lesson_id: Option<i32>,
+    pub max_attempts: Option<i32>,
+    pub time_limit_minutes: Option<i32>,
+    pub questions: Vec<CreateQuestion>,
+}
+
+impl CreateQuiz {
+    pub fn validate(&self) -> Result<(), MyError> {
+        validate_text("title", Some(&self.title), 140)?;
+        validate_text("description", self.description.as_ref(), 2000)?;
+        validate_limits(self.max_attempts, self.time_limit_minutes)?;
+        if !(1..=MAX_QUESTIONS).contains(&self.questions.len()) {
+            return Err(MyError::InvalidInput(format!("a quiz has 1 to {} questions", MAX_QUESTIONS)));
+        }
+        for (index, question) in self.questions.iter().enumerate() {
+            let invalid = |msg: String| MyError::InvalidInput(format!("question {}: {}", index + 1, msg));
+            validate_text("prompt", Some(&question.prompt), 2000).map_err(|err| invalid(err.to_string()))?;
+            if question.points.is_some_and(|points| !(1..=MAX_POINTS).contains(&points)) {
+                return Err(invalid(format!("points must be 1 to {}", MAX_POINTS)));
+            }
+            question.spec.validate().map_err(invalid)?;
+        }
+        Ok(())
+    }
+}
+
+//PUT /courses/{teacher_id}/{course_id}/quizzes/{quiz_id}, missing fields keep their value
+//questions cannot be changed once created, attempts were graded against them
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateQuiz {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    //null removes the limit
+    #[serde(default, deserialize_with = "clearable")]
+    pub max_attempts: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "clearable")]
+    pub time_limit_minutes: Option<Option<i32>>,
+}
+
+impl UpdateQuiz {
+    pub fn validate(&self) -> Result<(), MyError> {
+        validate_text("title", self.title.as_ref(), 140)?;
+        validate_text("description", self.description.as_ref(), 2000)?;
+        validate_limits(self.max_attempts.flatten(), self.time_limit_minutes.flatten())
+    }
+}
+
+//state of an attempt, stored as text in quiz_attempt.status
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum AttemptStatus {
+    InProgress,
+    Submitted,
+    //the time limit ran out before the attempt was submitted, it is not graded
+    Expired,
+}
+
+//a row of quiz_attempt, with the student it belongs to
+#[derive(Serialize, Debug, Clone)]
+pub struct QuizAttempt {
+    pub id: i32,
+    pub quiz_id: i32,
+    pub student_id: i32,
+    pub attempt_number: i32,
+    pub status: AttemptStatus,
+    pub started_at: DateTime<Utc>,
+    pub deadline_at: Option<DateTime<Utc>>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    //points of the graded answers, None until submitted
+    pub score: Option<i32>,
+    pub max_score: i32,
+    pub pending_review: bool,
+}
+
+//POST .../quizzes/{quiz_id}/attempts
+#[derive(Deserialize, Debug, Clone)]
+pub struct StartAttempt {
+    pub student_id: i32,
+}
+
+//a started attempt with the questions to answer
+#[derive(Serialize, Debug, Clone)]
+pub struct AttemptView {
+    #[serde(flatten)]
+    pub attempt: QuizAttempt,
+    pub questions: Vec<QuestionView>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SubmittedAnswer {
+    pub question_id: i32,
+    pub response: Value,
+}
+
+//POST .../attempts/{attempt_id}/submit, questions left out count as unanswered
+#[derive(Deserialize, Debug, Clone)]
+pub struct SubmitAttempt {
+    pub answers: Vec<SubmittedAnswer>,
+}
+
+//a row of quiz_answer, correct and points_awarded are None while it waits for review
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct QuizAnswer {
+    pub question_id: i32,
+    pub response: Option<Value>,
+    pub correct: Option<bool>,
+    pub points_awarded: Option<i32>,
+}
+
+impl QuizAnswer {
+    //one answer per question of the quiz, in question order
+    pub fn grade_all(questions: &[QuizQuestion], submitted: Vec<SubmittedAnswer>) -> Result<Vec<QuizAnswer>, MyError> {
+        let mut answers: Vec<QuizAnswer> = questions
+            .iter()
+            .map(|question| QuizAnswer {
+                question_id: question.id,
+                response: None,
+                correct: Some(false),
+                points_awarded: Some(0),
+            })
+            .collect();
+        for submitted in submitted {
+            let index = questions
+                .iter()
+                .position(|question| question.id == submitted.question_id)
+                .ok_or_else(|| MyError::InvalidInput(format!("question {} is not part of this quiz", submitted.question_id)))?;
+            if answers[index].response.is_some() {
+                return Err(MyError::InvalidInput(format!("question {} is answered twice", submitted.question_id)));
+            }
+            let question = &questions[index];
+            let correct = question
+                .spec
+                .grade(&submitted.response)
+                .map_err(|err| MyError::InvalidInput(format!("question {}: {}", question.id, err)))?;
+            answers[index] = QuizAnswer {
+                question_id: question.id,
+                response: Some(submitted.response),
+                correct,
+                points_awarded: correct.map(|correct| if correct { question.points } else { 0 }),
+            };
+        }
+        Ok(answers)
+    }
+
+    //(points of the graded answers, whether some answer waits for review)
+    pub fn score(answers: &[QuizAnswer]) -> (i32, bool) {
+        (
+            answers.iter().filter_map(|answer| answer.points_awarded).sum(),
+            answers.iter().any(|answer| answer.points_awarded.is_none()),
+        )
+    }
+}
+
+//an attempt with its graded answers
+#[derive(Serialize, Debug, Clone)]
+pub struct AttemptResult {
+    #[serde(flatten)]
+    pub attempt: QuizAttempt,
+    pub answers: Vec<QuizAnswer>,
+}
+
+//PUT .../attempts/{attempt_id}/answers/{question_id}, the teacher's grade for one answer
+#[derive(Deserialize, Debug, Clone)]
+pub struct GradeAnswer {
+    pub points_awarded: i32,
+}
+
+//GET .../quizzes/{quiz_id}/stats, how the submitted attempts answered one question
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct QuestionStats {
+    pub question_id: i32,
+    pub position: i32,
+    pub kind: &'static str,
+    pub points: i32,
+    pub answered: i64,
+    pub unanswered: i64,
+    pub correct: i64,
+    pub incorrect: i64,
+    pub pending_review: i64,
+    //share of the graded answers that were correct
+    pub correct_rate: Option<f64>,
+    pub average_points: Option<f64>,
+    //how often each option was chosen, for choice questions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub option_counts: Option<Vec<i64>>,
+}
+
+impl QuestionStats {
+    //answers are those of the submitted attempts
+    pub fn compute(questions: &[QuizQuestion], answers: &[QuizAnswer]) -> Vec<QuestionStats> {
+        questions
+            .iter()
+            .map(|question| {
+                let answers: Vec<&QuizAnswer> = answers.iter().filter(|answer| answer.question_id == question.id).collect();
+                let count = |keep: &dyn Fn(&QuizAnswer) -> bool| answers.iter().filter(|answer| keep(answer)).count() as i64;
+                let correct = count(&|answer| answer.response.is_some() && answer.correct == Some(true));
+                let graded: Vec<i32> = answers.iter().filter_map(|answer| answer.points_awarded).collect();
+                let option_counts = question.spec.options().map(|options| {
+                    let mut counts = vec![0; options.len()];
+                    for response in answers.iter().filter_map(|answer| answer.response.as_ref()) {
+                        for index in choices(response, options.len()).unwrap_or_default() {
+                            counts[index] += 1;
+                        }
+                    }
+                    counts
+                });
+                let graded_answered = count(&|answer| answer.response.is_some() && answer.correct.is_some());
+                QuestionStats {
+                    question_id: question.id,
+                    position: question.position,
+                    kind: question.spec.kind(),
+                    points: question.points,
+                    answered: count(&|answer| answer.response.is_some()),
+                    unanswered: count(&|answer| answer.response.is_none()),
+                    correct,
+                    incorrect: count(&|answer| answer.response.is_some() && answer.correct == Some(false)),
+                    pending_review: count(&|answer| answer.correct.is_none()),
+                    correct_rate: (graded_answered > 0).then(|| correct as f64 / graded_answered as f64),
+                    average_points: (!graded.is_empty())
+                        .then(|| graded.iter().sum::<i32>() as f64 / graded.len() as f64),
+                    option_counts,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn question(id: i32, points: i32, spec: Value) -> QuizQuestion {
+        QuizQuestion {
+            id,
+            position: id,
+            prompt: format!("question {}", id),
+            points,
+            spec: Json(serde_json::from_value(spec).unwrap()),
+        }
+    }
+
+    #[test]
+    fn objective_questions_are_graded() {
+        let single: QuestionSpec = serde_json::from_value(json!({"kind": "single_choice", "options": ["a", "b"], "correct": 1})).unwrap();
+        assert_eq!(single.grade(&json!(1)), Ok(Some(true)));
+        assert_eq!(single.grade(&json!(0)), Ok(Some(false)));
+        assert!(single.grade(&json!(2)).is_err());
+        assert!(single.grade(&json!([1])).is_err());
+
+        let multiple: QuestionSpec = serde_json::from_value(json!({"kind": "multiple_choice", "options": ["a", "b", "c"], "correct": [2, 0]})).unwrap();
+        assert_eq!(multiple.grade(&json!([0, 2])), Ok(Some(true)));
+        assert_eq!(multiple.grade(&json!([0])), Ok(Some(false)));
+
+        let numeric: QuestionSpec = serde_json::from_value(json!({"kind": "numeric", "answer": 2.5, "tolerance": 0.01})).unwrap();
+        assert_eq!(numeric.grade(&json!(2.51)), Ok(Some(true)));
+        assert_eq!(numeric.grade(&json!(2.6)), Ok(Some(false)));
+        assert!(numeric.grade(&json!("2.5")).is_err());
+
+        let text: QuestionSpec = serde_json::from_value(json!({"kind": "short_text", "answers": ["New  York"]})).unwrap();
+        assert_eq!(text.grade(&json!(" new york ")), Ok(Some(true)));
+        let essay: QuestionSpec = serde_json::from_value(json!({"kind": "short_text"})).unwrap();
+        assert_eq!(essay.grade(&json!("it depends")), Ok(None));
+        assert_eq!(essay.answer_key(), json!({"answers": [], "case_sensitive": false}));
+
+        let bad: QuestionSpec = serde_json::from_value(json!({"kind": "multiple_choice", "options": ["a", "b"], "correct": [1, 1]})).unwrap();
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn attempts_are_scored_and_counted() {
+        let questions = vec![
+            question(1, 2, json!({"kind": "single_choice", "options": ["a", "b", "c"], "correct": 0})),
+            question(2, 3, json!({"kind": "short_text"})),
+            question(3, 1, json!({"kind": "numeric", "answer": 4})),
+        ];
+        let submitted = |answers: Value| serde_json::from_value::<Vec<SubmittedAnswer>>(answers).unwrap();
+
+        let first = QuizAnswer::grade_all(&questions, submitted(json!([
+            {"question_id": 1, "response": 0},
+            {"question_id": 2, "response": "because"}
+        ])))
+        .unwrap();
+        assert_eq!(QuizAnswer::score(&first), (2, true));
+        assert_eq!(first[2].response, None);
+
+        let second = QuizAnswer::grade_all(&questions, submitted(json!([
+            {"question_id": 1, "response": 2},
+            {"question_id": 3, "response": 4}
+        ])))
+        .unwrap();
+        assert_eq!(QuizAnswer::score(&second), (1, false));
+        assert!(QuizAnswer::grade_all(&questions, submitted(json!([{"question_id": 9, "response": 1}]))).is_err());
+
+        let answers: Vec<QuizAnswer> = first.into_iter().chain(second).collect();
+        let stats = QuestionStats::compute(&questions, &answers);
+        assert_eq!(stats[0].correct, 1);
+        assert_eq!(stats[0].correct_rate, Some(0.5));
+        assert_eq!(stats[0].option_counts, Some(vec![1, 0, 1]));
+        assert_eq!(stats[1].pending_review, 1);
+        assert_eq!(stats[1].unanswered, 1);
+        assert_eq!(stats[2].average_points, Some(0.5));
+    }
+
+    #[test]
+    fn quiz_limits_can_be_removed() {
+        let update: UpdateQuiz = serde_json::from_str(r#"{"max_attempts": null}"#).unwrap();
+        assert_eq!(update.max_attempts, Some(None));
+        assert_eq!(update.time_limit_minutes, None);
+        assert!(update.validate().is_ok());
+        let update: UpdateQuiz = serde_json::from_str(r#"{"time_limit_minutes": 0}"#).unwrap();
+        assert!(update.validate().is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::errors::MyError;
+
+const MAX_TEXT: usize = 4000;
+const DEFAULT_PER_PAGE: i64 = 20;
+const MAX_PER_PAGE: i64 = 100;
+
+//a row of review, with the name of the student who wrote it
+#[derive(Serialize, Debug, Clone)]
+pub struct Review {
+    pub id: i32,
+    pub course_id: i32,
+    pub student_id: i32,
+    pub student_name: String,
+    pub rating: i32,
+    pub body: Option<String>,
+    pub helpful_count: i32,
+    //the teacher's answer
+    pub reply: Option<String>,
+    pub replied_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    //the student's last edit
+    pub updated_at: DateTime<Utc>,
+}
+
+fn validate_rating(rating: i32) -> Result<(), MyError> {
+    if !(1..=5).contains(&rating) {
+        return Err(MyError::InvalidInput("rating must be 1 to 5 stars".into()));
+    }
+    Ok(())
+}
+
+//trimmed, None when blank
+fn review_text(field: &str, text: Option<String>) -> Result<Option<String>, MyError> {
+    let text = text.map(|text| text.trim().to_string()).filter(|text| !text.is_empty());
+    if text.as_ref().is_some_and(|text| text.chars().count() > MAX_TEXT) {
+        return Err(MyError::InvalidInput(format!("{} must be at most {} characters", field, MAX_TEXT)));
+    }
+    Ok(text)
+}
+
+//POST /courses/{teacher_id}/{course_id}/reviews
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateReview {
+    pub student_id: i32,
+    pub rating: i32,
+    pub body: Option<String>,
+}
+
+impl CreateReview {
+    pub fn validate(self) -> Result<CreateReview, MyError> {
+        validate_rating(self.rating)?;
+        Ok(CreateReview {
+            body: review_text("body", self.body)?,
+            ..self
+        })
+    }
+}
+
+//PUT /courses/{teacher_id}/{course_id}/reviews/{review_id}, by the student who wrote it
+//a missing field keeps its value, an empty body removes the text
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateReview {
+    pub student_id: i32,
+    pub rating: Option<i32>,
+    pub body: Option<String>,
+}
+
+impl UpdateReview {
+    //the rating and body to store, given the review as it is
+    pub fn apply(self, current: &Review) -> Result<(i32, Option<String>), MyError> {
+        let rating = self.rating.unwrap_or(current.rating);
+        validate_rating(rating)?;
+        let body = match self.body {
+            Some(body) => review_text("body", Some(body))?,
+            None => current.body.clone(),
+        };
+        Ok((rating, body))
+    }
+}
+
+//PUT .../reviews/{review_id}/reply, replaces an earlier reply
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReviewReply {
+    pub reply: String,
+}
+
+impl ReviewReply {
+    pub fn validate(self) -> Result<String, MyError> {
+        review_text("reply", Some(self.reply))?.ok_or_else(|| MyError::InvalidInput("reply must not be empty".into()))
+    }
+}
+
+//POST .../reviews/{review_id}/helpful
+#[derive(Deserialize, Debug, Clone)]
+pub struct HelpfulVote {
+    pub student_id: i32,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewSort {
+    //most helpful votes first
+    Helpful,
+    //newest first
+    #[default]
+    Recent,
+}
+
+impl ReviewSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewSort::Helpful => "helpful",
+            ReviewSort::Recent => "recent",
+        }
+    }
+}
+
+//?sort=helpful&page=2&per_page=10 on GET .../reviews
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ReviewQuery {
+    pub sort: Option<ReviewSort>,
+    //from 1
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+impl ReviewQuery {
+    //(page, per_page), checked
+    pub fn pagination(&self) -> Result<(i64, i64), MyError> {
+        let page = self.page.unwrap_or(1);
+        let per_page = self.per_page.unwrap_or(DEFAULT_PER_PAGE);
+        if page < 1 || !(1..=MAX_PER_PAGE).contains(&per_page) {
+            return Err(MyError::InvalidInput(format!("page must be at least 1 and per_page 1 to {}", MAX_PER_PAGE)));
+        }
+        Ok((page, per_page))
+    }
+}
+
+//GET .../reviews, one page of reviews
+#[derive(Serialize, Debug, Clone)]
+pub struct ReviewPage {
+    pub page: i64,
+    pub per_page: i64,
+    //reviews of the course across all pages
+    pub total: i64,
+    pub reviews: Vec<Review>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reviews_are_checked_before_they_are_stored() {
+        let review = CreateReview { student_id: 1, rating: 4, body: Some("  ".into()) }.validate().unwrap();
+        assert_eq!(review.body, None);
+        assert!(CreateReview { student_id: 1, rating: 6, body: None }.validate().is_err());
+
+        let current = Review {
+            id: 1,
+            course_id: 1,
+            student_id: 1,
+            student_name: "s".into(),
+            rating: 4,
+            body: Some("good".into()),
+            helpful_count: 0,
+            reply: None,
+            replied_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let update = |rating, body: Option<&str>| UpdateReview { student_id: 1, rating, body: body.map(String::from) };
+        assert_eq!(update(Some(2), None).apply(&current).unwrap(), (2, Some("good".into())));
+        assert_eq!(update(None, Some("")).apply(&current).unwrap(), (4, None));
+        assert!(update(Some(0), None).apply(&current).is_err());
+
+        assert_eq!(ReviewQuery::default().pagination().unwrap(), (1, 20));
+        assert!(ReviewQuery { page: Some(0), ..Default::default() }.pagination().is_err());
+        assert!(ReviewQuery { per_page: Some(500), ..Default::default() }.pagination().is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+//a row of course_revision, the editable content of a course at one point in time
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct CourseRevision {
+    pub course_id: i32,
+    pub revision: i32,
+    pub teacher_id: i32,
+    pub description: Option<String>,
+    pub structure: Option<String>,
+    //None for the baseline of a course created before revisions were kept
+    pub actor: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+//older rows can hold an empty string where there is no text, which is not a different content
+pub fn same_content(old: &Option<String>, new: &Option<String>) -> bool {
+    old.as_deref().unwrap_or_default() == new.as_deref().unwrap_or_default()
+}
+
+//GET /courses/{teacher_id}/{course_id}/revisions/diff?from=1&to=3
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct DiffQuery {
+    pub from: i32,
+    pub to: i32,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RevisionDiff {
+    pub from: i32,
+    pub to: i32,
+    //only the fields whose value differs between the two revisions
+    pub changes: Vec<FieldChange>,
+}
+
+impl RevisionDiff {
+    pub fn between(from: &CourseRevision, to: &CourseRevision) -> RevisionDiff {
+        let fields = [
+            ("description", &from.description, &to.description),
+            ("structure", &from.structure, &to.structure),
+        ];
+        RevisionDiff {
+            from: from.revision,
+            to: to.revision,
+            changes: fields
+                .into_iter()
+                .filter(|(_, old, new)| !same_content(old, new))
+                .map(|(field, old, new)| FieldChange {
+                    field,
+                    from: old.clone(),
+                    to: new.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revision(revision: i32, description: &str, structure: Option<&str>) -> CourseRevision {
+        CourseRevision {
+            course_id: 1,
+            revision,
+            teacher_id: 1,
+            description: Some(description.into()),
+            structure: structure.map(Into::into),
+            actor: None,
+            created_at: DateTime::default(),
+        }
+    }
+
+    #[test]
+    fn diff_lists_only_changed_fields() {
+        let old = revision(1, "intro", Some("3 modules"));
+        let new = revision(2, "intro", None);
+        let diff = RevisionDiff::between(&old, &new);
+        assert_eq!(diff.from, 1);
+        assert_eq!(diff.to, 2);
+        assert_eq!(
+            diff.changes,
+            vec![FieldChange {
+                field: "structure",
+                from: Some("3 modules".into()),
+                to: None,
+            }]
+        );
+        assert!(RevisionDiff::between(&old, &old).changes.is_empty());
+
+        let empty = revision(3, "intro", Some(""));
+        assert!(RevisionDiff::between(&new, &empty).changes.is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::errors::MyError;
+
+//a row of student, someone who enrolls in courses
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct Student {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn validate_name(name: &str) -> Result<(), MyError> {
+    if name.trim().is_empty() {
+        return Err(MyError::InvalidInput("name must not be empty".into()));
+    }
+    if name.chars().count() > 140 {
+        return Err(MyError::InvalidInput("name must be at most 140 characters".into()));
+    }
+    Ok(())
+}
+
+/**
+Checks the shape of an email address and lowercases it, addresses are compared without case
+local@domain with a dot in the domain, at most 254 characters as SMTP allows
+whether the mailbox exists is not checked
+**/
+pub fn normalize_email(email: &str) -> Result<String, MyError> {
+    let email = email.trim().to_lowercase();
+    let invalid = || MyError::InvalidInput(format!("{} is not an email address", email));
+    if email.len() > 254 || email.chars().any(char::is_whitespace) {
+        return Err(invalid());
+    }
+    match email.split_once('@') {
+        Some((local, domain))
+            if !local.is_empty()
+                && !domain.contains('@')
+                && domain.split('.').count() > 1
+                && domain.split('.').all(|label| !label.is_empty()) =>
+        {
+            Ok(email)
+        }
+        _ => Err(invalid()),
+    }
+}
+
+//POST /students
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateStudent {
+    pub name: String,
+    pub email: String,
+}
+
+impl CreateStudent {
+    //returns the student with its email normalized
+    pub fn validate(self) -> Result<CreateStudent, MyError> {
+        validate_name(&self.name)?;
+        Ok(CreateStudent {
+            email: normalize_email(&self.email)?,
+            name: self.name.trim().into(),
+        })
+    }
+}
+
+//PUT /students/{student_id}, missing fields keep their value
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateStudent {
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+impl UpdateStudent {
+    pub fn validate(self) -> Result<UpdateStudent, MyError> {
+        if let Some(name) = &self.name {
+            validate_name(name)?;
+        }
+        Ok(UpdateStudent {
+            name: self.name.map(|name| name.trim().into()),
+            email: self.email.as_deref().map(normalize_email).transpose()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emails_are_checked_and_lowercased() {
+        assert_eq!(normalize_email(" Ada@Example.org ").unwrap(), "ada@example.org");
+        for email in ["ada", "@example.org", "ada@", "ada@example", "ada@@example.org", "ada@example..org", "a da@example.org"] {
+            assert!(normalize_email(email).is_err(), "{} should be rejected", email);
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::errors::MyError;
+
+const MAX_CATEGORY_NAME: usize = 100;
+const MAX_TAG: usize = 50;
+const MAX_CATEGORIES_PER_COURSE: usize = 10;
+const MAX_TAGS_PER_COURSE: usize = 20;
+const DEFAULT_SUGGESTIONS: i64 = 10;
+const MAX_SUGGESTIONS: i64 = 50;
+
+//a row of category
+#[derive(Serialize, Debug, Clone)]
+pub struct Category {
+    pub id: i32,
+    //None for a root category
+    pub parent_id: Option<i32>,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+//GET /categories, a category with the categories under it
+#[derive(Serialize, Debug, Clone)]
+pub struct CategoryNode {
+    pub id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+    pub children: Vec<CategoryNode>,
+}
+
+impl CategoryNode {
+    //the roots of the tree, siblings keep the order of the rows
+    pub fn tree(categories: Vec<Category>) -> Vec<CategoryNode> {
+        let mut children: HashMap<Option<i32>, Vec<Category>> = HashMap::new();
+        for category in categories {
+            children.entry(category.parent_id).or_default().push(category);
+        }
+        Self::build(&mut children, None)
+    }
+
+    fn build(children: &mut HashMap<Option<i32>, Vec<Category>>, parent_id: Option<i32>) -> Vec<CategoryNode> {
+        children
+            .remove(&parent_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|category| CategoryNode {
+                id: category.id,
+                parent_id: category.parent_id,
+                name: category.name,
+                children: Self::build(children, Some(category.id)),
+            })
+            .collect()
+    }
+}
+
+fn category_name(name: &str) -> Result<String, MyError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_CATEGORY_NAME {
+        return Err(MyError::InvalidInput(format!("name must be 1 to {} characters", MAX_CATEGORY_NAME)));
+    }
+    Ok(name.to_string())
+}
+
+//POST /categories with X-Admin-Token, a category without parent_id is a root
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateCategory {
+    pub name: String,
+    pub parent_id: Option<i32>,
+}
+
+impl CreateCategory {
+    pub fn validate(self) -> Result<CreateCategory, MyError> {
+        Ok(CreateCategory {
+            name: category_name(&self.name)?,
+            ..self
+        })
+    }
+}
+
+//PUT /categories/{category_id} with X-Admin-Token, renames or moves a category with everything under it
+//the body is the whole category, a missing parent_id moves it to the root
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateCategory {
+    pub name: String,
+    pub parent_id: Option<i32>,
+}
+
+impl UpdateCategory {
+    pub fn validate(self) -> Result<UpdateCategory, MyError> {
+        Ok(UpdateCategory {
+            name: category_name(&self.name)?,
+            ..self
+        })
+    }
+}
+
+//trimmed, lowercased and with single spaces, so the same tag is written one way
+pub fn normalize_tag(tag: &str) -> Result<String, MyError> {
+    let tag = tag.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    if tag.is_empty() || tag.chars().count() > MAX_TAG {
+        return Err(MyError::InvalidInput(format!("tags must be 1 to {} characters", MAX_TAG)));
+    }
+    //room for tags such as c++, c# or node.js
+    if !tag.chars().all(|c| c.is_alphanumeric() || " -+#.".contains(c)) {
+        return Err(MyError::InvalidInput(format!(
+            "tag {} can only use letters, digits, spaces and - + # .",
+            tag
+        )));
+    }
+    Ok(tag)
+}
+
+//tags normalized, a tag given twice is kept once
+pub fn normalize_tags<'a>(tags: impl IntoIterator<Item = &'a str>) -> Result<Vec<String>, MyError> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = normalize_tag(tag)?;
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    Ok(normalized)
+}
+
+//PUT /courses/{teacher_id}/{course_id}/categories, replaces the course's categories
+#[derive(Deserialize, Debug, Clone)]
+pub struct CourseCategories {
+    pub category_ids: Vec<i32>,
+}
+
+impl CourseCategories {
+    pub fn validate(self) -> Result<Vec<i32>, MyError> {
+        let mut ids = self.category_ids;
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.len() > MAX_CATEGORIES_PER_COURSE {
+            return Err(MyError::InvalidInput(format!(
+                "a course can be in at most {} categories",
+                MAX_CATEGORIES_PER_COURSE
+            )));
+        }
+        Ok(ids)
+    }
+}
+
+//PUT /courses/{teacher_id}/{course_id}/tags, replaces the course's tags, new tags are created
+#[derive(Deserialize, Debug, Clone)]
+pub struct CourseTags {
+    pub tags: Vec<String>,
+}
+
+impl CourseTags {
+    pub fn validate(self) -> Result<Vec<String>, MyError> {
+        let tags = normalize_tags(self.tags.iter().map(String::as_str))?;
+        if tags.len() > MAX_TAGS_PER_COURSE {
+            return Err(MyError::InvalidInput(format!("a course can have at most {} tags", MAX_TAGS_PER_COURSE)));
+        }
+        Ok(tags)
+    }
+}
+
+//GET /tags?prefix=ru&limit=10, for autocompletion
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct TagQuery {
+    pub prefix: Option<String>,
+    pub limit: Option<i64>,
+}
+
+impl TagQuery {
+    //(LIKE pattern, limit), an empty prefix matches every tag
+    pub fn pattern(&self) -> Result<(String, i64), MyError> {
+        let limit = self.limit.unwrap_or(DEFAULT_SUGGESTIONS);
+        if !(1..=MAX_SUGGESTIONS).contains(&limit) {
+            return Err(MyError::InvalidInput(format!("limit must be 1 to {}", MAX_SUGGESTIONS)));
+        }
+        let prefix = self.prefix.as_deref().unwrap_or("").trim_start().to_lowercase();
+        let escaped: String = prefix
+            .chars()
+            .flat_map(|c| match c {
+                '%' | '_' | '\\' => vec!['\\', c],
+                c => vec![c],
+            })
+            .collect();
+        Ok((format!("{}%", escaped), limit))
+    }
+}
+
+//a tag with how many published courses use it
+#[derive(Serialize, Debug, Clone)]
+pub struct TagCount {
+    pub name: String,
+    pub course_count: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(id: i32, parent_id: Option<i32>, name: &str) -> Category {
+        Category { id, parent_id, name: name.into(), created_at: Utc::now(), updated_at: Utc::now() }
+    }
+
+    #[test]
+    fn categories_nest_under_their_parent() {
+        let tree = CategoryNode::tree(vec![
+            category(3, Some(1), "Backend"),
+            category(1, None, "Programming"),
+            category(4, Some(3), "Databases"),
+            category(2, None, "Design"),
+        ]);
+        let names: Vec<&str> = tree.iter().map(|node| node.name.as_str()).collect();
+        assert_eq!(names, ["Programming", "Design"]);
+        assert_eq!(tree[0].children[0].name, "Backend");
+        assert_eq!(tree[0].children[0].children[0].name, "Databases");
+        assert!(tree[1].children.is_empty());
+    }
+
+    #[test]
+    fn tags_are_written_one_way() {
+        assert_eq!(normalize_tags(["  Web   Dev ", "web dev", "C++"]).unwrap(), ["web dev", "c++"]);
+        assert!(normalize_tag(" ").is_err());
+        assert!(normalize_tag("50%").is_err());
+        assert!(CourseTags { tags: (0..21).map(|i| i.to_string()).collect() }.validate().is_err());
+
+        let query = TagQuery { prefix: Some("Web_".into()), limit: None };
+        assert_eq!(query.pattern().unwrap(), ("web\\_%".into(), 10));
+        assert!(TagQuery { limit: Some(0), ..Default::default() }.pattern().is_err());
+    }
+}
@@ -0,0 +1,224 @@
+use actix_web::{dev::Payload, http::header, web, FromRequest, HttpRequest};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::{ready, Ready};
+use crate::errors::MyError;
+use crate::models::course::Course;
+use crate::models::facet::LanguageTag;
+
+//a row of course_translation
+#[derive(Serialize, Debug, Clone)]
+pub struct CourseTranslation {
+    pub course_id: i32,
+    pub locale: LanguageTag,
+    pub name: String,
+    pub description: Option<String>,
+    pub structure: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl CourseTranslation {
+    //text the translation lacks stays in the course's own language
+    pub fn apply(&self, course: &mut Course) {
+        course.name = self.name.clone();
+        if self.description.is_some() {
+            course.description = self.description.clone();
+        }
+        if self.structure.is_some() {
+            course.structure = self.structure.clone();
+        }
+    }
+}
+
+//PUT /courses/{teacher_id}/{course_id}/translations/{locale}, adds or replaces the translation
+//a blank description or structure is left to the course's own
+#[derive(Deserialize, Debug, Clone)]
+pub struct SaveTranslation {
+    pub name: String,
+    pub description: Option<String>,
+    pub structure: Option<String>,
+}
+
+impl SaveTranslation {
+    pub fn validate(self) -> Result<SaveTranslation, MyError> {
+        let text = |field: &str, value: Option<String>, max: usize| {
+            let value = value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+            if value.as_ref().is_some_and(|value| value.chars().count() > max) {
+                return Err(MyError::InvalidInput(format!("{} must be at most {} characters", field, max)));
+            }
+            Ok(value)
+        };
+        let name = text("name", Some(self.name), 140)?
+            .ok_or_else(|| MyError::InvalidInput("name must not be empty".into()))?;
+        Ok(SaveTranslation {
+            name,
+            description: text("description", self.description, 2000)?,
+            structure: text("structure", self.structure, 200)?,
+        })
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct LocaleQuery {
+    locale: Option<String>,
+}
+
+//the tag and the shorter tags it falls back to, "zh-Hant-TW" gives zh-Hant-TW, zh-Hant, zh
+//a single letter subtag never ends a fallback, it only introduces the subtags after it (RFC 4647)
+fn fallbacks(tag: &str) -> Vec<String> {
+    let mut subtags: Vec<&str> = tag.split('-').collect();
+    let mut chain = Vec::new();
+    while !subtags.is_empty() {
+        chain.push(subtags.join("-"));
+        subtags.pop();
+        while subtags.last().is_some_and(|subtag| subtag.len() == 1) {
+            subtags.pop();
+        }
+    }
+    chain
+}
+
+//"de" stands for "de-AT" as well, a reader of German reads Austrian German
+fn covers(range: &str, tag: &str) -> bool {
+    tag.eq_ignore_ascii_case(range)
+        || (tag.len() > range.len() && tag.as_bytes()[range.len()] == b'-' && tag[..range.len()].eq_ignore_ascii_case(range))
+}
+
+/**
+The locales a reader asked for, best first
+?locale=de-AT replaces the Accept-Language header, whose entries are ranked by their q value
+an Accept-Language entry that is not a BCP 47 tag is skipped, a ?locale= that is not one is refused
+**/
+#[derive(Debug, Clone, Default)]
+pub struct LocalePreference {
+    ranked: Vec<String>,
+}
+
+impl LocalePreference {
+    pub fn from_accept_language(value: &str) -> Self {
+        let mut entries: Vec<(String, f32)> = value
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let tag = LanguageTag::parse(parts.next()?.trim()).ok()?;
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                (quality > 0.0).then(|| (String::from(tag), quality))
+            })
+            .collect();
+        //stable sort keeps listed order for equal q values
+        entries.sort_by(|a, b| b.1.total_cmp(&a.1));
+        LocalePreference { ranked: entries.into_iter().map(|(tag, _)| tag).collect() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranked.is_empty()
+    }
+
+    /**
+    The translation to serve, None to serve the course in its own language
+    every asked for locale is tried with its fallbacks before the next one,
+    the course's own language wins over a translation into the same language,
+    and the course's own language is served when nothing matches
+    **/
+    pub fn choose<'a>(&self, primary: Option<&LanguageTag>, translations: &'a [CourseTranslation]) -> Option<&'a CourseTranslation> {
+        for range in self.ranked.iter().flat_map(|tag| fallbacks(tag)) {
+            if primary.is_some_and(|primary| covers(&range, primary.as_str())) {
+                return None;
+            }
+            let translation = translations
+                .iter()
+                .find(|translation| translation.locale.as_str().eq_ignore_ascii_case(&range))
+                .or_else(|| translations.iter().find(|translation| covers(&range, translation.locale.as_str())));
+            if translation.is_some() {
+                return translation;
+            }
+        }
+        None
+    }
+
+    //serves the course in the chosen translation, and returns the locale it is in now, if known
+    pub fn translate(&self, course: &mut Course, translations: &[CourseTranslation]) -> Option<String> {
+        match self.choose(course.language.as_ref(), translations) {
+            Some(translation) => {
+                translation.apply(course);
+                Some(translation.locale.to_string())
+            }
+            None => course.language.as_ref().map(LanguageTag::to_string),
+        }
+    }
+}
+
+impl FromRequest for LocalePreference {
+    type Error = MyError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let locale = web::Query::<LocaleQuery>::from_query(req.query_string())
+            .ok()
+            .and_then(|query| query.into_inner().locale);
+        if let Some(locale) = locale {
+            return ready(
+                LanguageTag::parse(&locale)
+                    .map(|tag| LocalePreference { ranked: vec![tag.into()] })
+                    .map_err(MyError::InvalidInput),
+            );
+        }
+        let accept_language = req
+            .headers()
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("");
+        ready(Ok(LocalePreference::from_accept_language(accept_language)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translation(locale: &str) -> CourseTranslation {
+        CourseTranslation {
+            course_id: 1,
+            locale: LanguageTag::parse(locale).unwrap(),
+            name: locale.into(),
+            description: None,
+            structure: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn readers_get_the_closest_language() {
+        assert_eq!(fallbacks("zh-Hant-TW"), ["zh-Hant-TW", "zh-Hant", "zh"]);
+        assert_eq!(fallbacks("en-US-x-twain"), ["en-US-x-twain", "en-US", "en"]);
+
+        let translations = vec![translation("de-DE"), translation("fr"), translation("pt-BR")];
+        let primary = LanguageTag::parse("en").unwrap();
+        let served = |accept: &str| {
+            LocalePreference::from_accept_language(accept)
+                .choose(Some(&primary), &translations)
+                .map(|translation| translation.locale.to_string())
+        };
+        assert_eq!(served("fr-CA, de;q=0.9"), Some("fr".into()));
+        assert_eq!(served("de-AT"), Some("de-DE".into()));
+        assert_eq!(served("pt;q=0.5, de;q=0.8"), Some("de-DE".into()));
+        assert_eq!(served("en-GB, fr"), None);
+        assert_eq!(served("it, fr;q=0"), None);
+        assert_eq!(served(""), None);
+        assert_eq!(served("*, not a tag, pt"), Some("pt-BR".into()));
+    }
+
+    #[test]
+    fn translations_are_checked_before_they_are_stored() {
+        let saved = SaveTranslation { name: " Rust ".into(), description: Some(" ".into()), structure: None }
+            .validate()
+            .unwrap();
+        assert_eq!(saved.name, "Rust");
+        assert_eq!(saved.description, None);
+        assert!(SaveTranslation { name: "".into(), description: None, structure: None }.validate().is_err());
+    }
+}
@@ -0,0 +1,646 @@
+use crate::errors::MyError;
+use crate::models::course::{Course, CourseStatus, CourseView};
+use crate::models::duration::CourseDuration;
+use crate::models::facet::{CourseFormat, CourseLevel, LanguageTag};
+use crate::models::money::{Price, Rate};
+use chrono::{DateTime, Utc};
+use actix_web::{
+    dev::Payload,
+    http::header::{self, Header},
+    web, FromRequest, HttpMessage, HttpRequest, HttpResponse,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+
+//representations a course resource can be read or written as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaType {
+    Json,
+    Csv,
+    MsgPack,
+    Xml,
+}
+
+//in the order a wildcard in Accept picks them
+const SUPPORTED_MEDIA_TYPES: [MediaType; 4] = [MediaType::Json, MediaType::Csv, MediaType::MsgPack, MediaType::Xml];
+
+impl MediaType {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            MediaType::Json => "application/json",
+            MediaType::Csv => "text/csv; charset=utf-8",
+            MediaType::MsgPack => "application/msgpack",
+            MediaType::Xml => "application/xml",
+        }
+    }
+
+    //the mime essences (type/subtype, without parameters) naming this type
+    fn essences(&self) -> &'static [&'static str] {
+        match self {
+            MediaType::Json => &["application/json"],
+            MediaType::Csv => &["text/csv"],
+            MediaType::MsgPack => &["application/msgpack", "application/x-msgpack", "application/vnd.msgpack"],
+            MediaType::Xml => &["application/xml", "text/xml"],
+        }
+    }
+
+    fn from_essence(essence: &str) -> Option<MediaType> {
+        SUPPORTED_MEDIA_TYPES
+            .into_iter()
+            .find(|format| format.essences().contains(&essence))
+    }
+
+    //how closely a media range names an essence, type/subtype beats type/* beats */*
+    fn specificity(range: &str, essence: &str) -> Option<u8> {
+        if range == essence {
+            return Some(2);
+        }
+        if range == "*/*" {
+            return Some(0);
+        }
+        let family = range.strip_suffix("/*")?;
+        (essence.split('/').next() == Some(family)).then_some(1)
+    }
+
+    //the q-factor Accept gives this type, and the position of the range it comes from
+    //each essence takes it from the most specific range naming it, the type from its best essence
+    fn quality(&self, accept: &header::Accept) -> Option<(header::Quality, usize)> {
+        self.essences()
+            .iter()
+            .filter_map(|essence| {
+                let (_, position, quality) = accept
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(position, item)| {
+                        let specificity = MediaType::specificity(item.item.essence_str(), essence)?;
+                        Some((specificity, position, item.quality))
+                    })
+                    //min_by_key keeps the first of equals, so the range listed first wins a tie
+                    .min_by_key(|(specificity, position, _)| (std::cmp::Reverse(*specificity), *position))?;
+                Some((quality, position))
+            })
+            .min_by_key(|(quality, position)| (std::cmp::Reverse(*quality), *position))
+    }
+
+    //picks the representation with the highest q-factor the client accepts
+    //a type gets the q-factor of the most specific range naming it, so with
+    //"application/json;q=0, */*" JSON stays refused and the wildcard serves the next type
+    //equal q-factors go to the range listed first, then to the order of SUPPORTED_MEDIA_TYPES
+    //a missing Accept header means anything goes, so JSON is served
+    pub fn from_accept(req: &HttpRequest) -> Result<MediaType, MyError> {
+        if !req.headers().contains_key(header::ACCEPT) {
+            return Ok(MediaType::Json);
+        }
+        let accept = header::Accept::parse(req)
+            .map_err(|_err| MyError::NotAcceptable("Malformed Accept header".into()))?;
+
+        SUPPORTED_MEDIA_TYPES
+            .into_iter()
+            .filter_map(|format| {
+                let (quality, position) = format.quality(&accept)?;
+                (quality > header::Quality::ZERO).then_some((format, quality, position))
+            })
+            .min_by_key(|(_, quality, position)| (std::cmp::Reverse(*quality), *position))
+            .map(|(format, _, _)| format)
+            .ok_or_else(|| {
+                MyError::NotAcceptable(
+                    "Supported types are application/json, text/csv, application/msgpack and application/xml"
+                        .into(),
+                )
+            })
+    }
+
+    //a request body without Content-Type is treated as JSON
+    pub fn from_content_type(req: &HttpRequest) -> Result<MediaType, MyError> {
+        let mime = match req.mime_type() {
+            Ok(Some(mime)) => mime,
+            Ok(None) => return Ok(MediaType::Json),
+            Err(_err) => {
+                return Err(MyError::UnsupportedMediaType("Malformed Content-Type header".into()))
+            }
+        };
+        match MediaType::from_essence(mime.essence_str()) {
+            Some(format) => Ok(format),
+            None => Err(MyError::UnsupportedMediaType(format!(
+                "Content-Type {} is not supported",
+                mime.essence_str()
+            ))),
+        }
+    }
+
+    pub fn encode<T: Negotiable>(&self, value: &T) -> Result<Vec<u8>, MyError> {
+        match self {
+            MediaType::Json => serde_json::to_vec(value).map_err(|err| MyError::ActixError(err.to_string())),
+            MediaType::Csv => {
+                let mut writer = csv::Writer::from_writer(vec![]);
+                for record in value.records() {
+                    writer
+                        .serialize(&record)
+                        .map_err(|err| MyError::ActixError(err.to_string()))?;
+                }
+                writer
+                    .into_inner()
+                    .map_err(|err| MyError::ActixError(err.to_string()))
+            }
+            MediaType::MsgPack => {
+                rmp_serde::to_vec_named(value).map_err(|err| MyError::ActixError(err.to_string()))
+            }
+            MediaType::Xml => value
+                .to_xml()
+                .map(String::into_bytes)
+                .map_err(|err| MyError::ActixError(err.to_string())),
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> Result<T, MyError> {
+        match self {
+            MediaType::Json => {
+                serde_json::from_slice(body).map_err(|err| MyError::InvalidInput(err.to_string()))
+            }
+            MediaType::Csv => {
+                let mut reader = csv::Reader::from_reader(body);
+                let mut records = reader.deserialize();
+                let record = records
+                    .next()
+                    .ok_or_else(|| MyError::InvalidInput("CSV body has no record".into()))?
+                    .map_err(|err| MyError::InvalidInput(err.to_string()))?;
+                if records.next().is_some() {
+                    return Err(MyError::InvalidInput(
+                        "CSV body must contain exactly one record".into(),
+                    ));
+                }
+                Ok(record)
+            }
+            MediaType::MsgPack => {
+                rmp_serde::from_slice(body).map_err(|err| MyError::InvalidInput(err.to_string()))
+            }
+            MediaType::Xml => {
+                let body = std::str::from_utf8(body)
+                    .map_err(|err| MyError::InvalidInput(err.to_string()))?;
+                quick_xml::de::from_str(body).map_err(|err| MyError::InvalidInput(err.to_string()))
+            }
+        }
+    }
+
+    pub fn respond<T: Negotiable>(&self, value: &T) -> Result<HttpResponse, MyError> {
+        Ok(HttpResponse::Ok()
+            .content_type(self.content_type())
+            .insert_header((header::VARY, "Accept"))
+            .body(self.encode(value)?))
+    }
+}
+
+//formats a streamed export is written in, one record per line
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamFormat {
+    Ndjson,
+    Csv,
+}
+
+impl StreamFormat {
+    //text/csv in Accept selects CSV, anything else gets JSON lines
+    pub fn from_accept(req: &HttpRequest) -> StreamFormat {
+        match MediaType::from_accept(req) {
+            Ok(MediaType::Csv) => StreamFormat::Csv,
+            _ => StreamFormat::Ndjson,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            StreamFormat::Ndjson => "application/x-ndjson",
+            StreamFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            StreamFormat::Ndjson => "ndjson",
+            StreamFormat::Csv => "csv",
+        }
+    }
+
+    //encodes one batch of a stream, the CSV header goes only in front of the first one
+    pub fn encode_batch<T: Negotiable>(&self, items: &[T], with_header: bool) -> Result<Vec<u8>, MyError> {
+        match self {
+            StreamFormat::Ndjson => {
+                let mut body = vec![];
+                for record in items {
+                    serde_json::to_writer(&mut body, record)
+                        .map_err(|err| MyError::ActixError(err.to_string()))?;
+                    body.push(b'\n');
+                }
+                Ok(body)
+            }
+            StreamFormat::Csv => {
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(with_header)
+                    .from_writer(vec![]);
+                for record in items.iter().flat_map(Negotiable::records) {
+                    writer
+                        .serialize(&record)
+                        .map_err(|err| MyError::ActixError(err.to_string()))?;
+                }
+                writer
+                    .into_inner()
+                    .map_err(|err| MyError::ActixError(err.to_string()))
+            }
+        }
+    }
+}
+
+//extracting a MediaType reads the Accept header, so the 406 is
+//returned before the handler does any work
+impl FromRequest for MediaType {
+    type Error = MyError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(MediaType::from_accept(req))
+    }
+}
+
+//a numbered record of a bulk body, decoded or with the reason it could not be
+pub type DecodedRow<T> = (usize, Result<T, MyError>);
+
+/**
+Bulk request body, one record per CSV row or per NDJSON line
+each record is decoded on its own, so one bad row does not fail the others
+records are numbered from 1, not counting the CSV header or blank lines
+**/
+pub fn decode_rows<T: DeserializeOwned>(
+    req: &HttpRequest,
+    body: &[u8],
+) -> Result<Vec<DecodedRow<T>>, MyError> {
+    let essence = match req.mime_type() {
+        Ok(Some(mime)) => mime.essence_str().to_string(),
+        Ok(None) => "application/x-ndjson".to_string(),
+        Err(_err) => {
+            return Err(MyError::UnsupportedMediaType("Malformed Content-Type header".into()))
+        }
+    };
+
+    match essence.as_str() {
+        "text/csv" => Ok(decode_csv_rows(body)),
+        "application/x-ndjson" | "application/ndjson" | "application/jsonl" => {
+            let body = std::str::from_utf8(body)
+                .map_err(|err| MyError::InvalidInput(err.to_string()))?;
+            Ok(body
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .enumerate()
+                .map(|(index, line)| {
+                    (index + 1, serde_json::from_str(line).map_err(|err| MyError::InvalidInput(err.to_string())))
+                })
+                .collect())
+        }
+        other => Err(MyError::UnsupportedMediaType(format!(
+            "Content-Type {} is not supported, use text/csv or application/x-ndjson",
+            other
+        ))),
+    }
+}
+
+//CSV records with a header row, numbered like decode_rows
+pub fn decode_csv_rows<T: DeserializeOwned>(body: &[u8]) -> Vec<DecodedRow<T>> {
+    let mut reader = csv::Reader::from_reader(body);
+    reader
+        .deserialize()
+        .enumerate()
+        .map(|(index, record)| {
+            (index + 1, record.map_err(|err| MyError::InvalidInput(err.to_string())))
+        })
+        .collect()
+}
+
+/**
+Request body decoded according to its Content-Type
+works like web::Json, but also takes CSV, MessagePack and XML
+**/
+#[derive(Debug)]
+pub struct Negotiated<T>(pub T);
+
+impl<T> Negotiated<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Negotiated<T> {
+    type Error = MyError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let format = MediaType::from_content_type(req);
+        let body = web::Bytes::from_request(req, payload);
+        Box::pin(async move {
+            let format = format?;
+            let body = body
+                .await
+                .map_err(|err| MyError::InvalidInput(err.to_string()))?;
+            format.decode(&body).map(Negotiated)
+        })
+    }
+}
+
+//resources that can be written in every MediaType
+//CSV needs the flat rows, XML needs named root elements
+pub trait Negotiable: Serialize {
+    type Record: Serialize;
+
+    fn records(&self) -> Vec<Self::Record>;
+    fn to_xml(&self) -> Result<String, quick_xml::DeError>;
+}
+
+//a course as one CSV row, the price and duration objects are spread over several columns
+//the converted_* columns are only written for reads with ?currency=, modules and lessons with ?embed=outline
+#[derive(Serialize)]
+pub struct CourseRecord {
+    teacher_id: i32,
+    id: i32,
+    name: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    description: Option<String>,
+    format: Option<CourseFormat>,
+    structure: Option<String>,
+    duration: Option<String>,
+    duration_minutes: Option<i32>,
+    duration_weeks: Option<i32>,
+    duration_sessions: Option<i32>,
+    price: Option<String>,
+    price_minor: Option<i64>,
+    currency: Option<&'static str>,
+    language: Option<LanguageTag>,
+    level: Option<CourseLevel>,
+    capacity: Option<i32>,
+    status: CourseStatus,
+    published_at: Option<DateTime<Utc>>,
+    publish_at: Option<DateTime<Utc>>,
+    unpublish_at: Option<DateTime<Utc>>,
+    review_count: i32,
+    average_rating: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converted_price: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converted_price_minor: Option<Option<i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converted_currency: Option<Option<&'static str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exchange_rate: Option<Option<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rate_as_of: Option<Option<DateTime<Utc>>>,
+    //counts of the embedded outline, only written for reads with ?embed=outline
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modules: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lessons: Option<usize>,
+}
+
+impl From<&Course> for CourseRecord {
+    fn from(course: &Course) -> Self {
+        let (price_minor, currency) = Price::columns(course.price.as_ref());
+        let (duration_minutes, duration_weeks, duration_sessions) = CourseDuration::columns(course.duration.as_ref());
+        CourseRecord {
+            teacher_id: course.teacher_id,
+            id: course.id,
+            name: course.name.clone(),
+            created_at: course.created_at,
+            updated_at: course.updated_at,
+            description: course.description.clone(),
+            format: course.format,
+            structure: course.structure.clone(),
+            duration: course.duration.as_ref().map(CourseDuration::iso),
+            duration_minutes,
+            duration_weeks,
+            duration_sessions,
+            price: course.price.as_ref().map(Price::formatted),
+            price_minor,
+            currency,
+            language: course.language.clone(),
+            level: course.level,
+            capacity: course.capacity,
+            status: course.status,
+            published_at: course.published_at,
+            publish_at: course.publish_at,
+            unpublish_at: course.unpublish_at,
+            review_count: course.review_count,
+            average_rating: course.average_rating,
+            converted_price: None,
+            converted_price_minor: None,
+            converted_currency: None,
+            exchange_rate: None,
+            rate_as_of: None,
+            modules: None,
+            lessons: None,
+        }
+    }
+}
+
+impl From<&CourseView> for CourseRecord {
+    fn from(view: &CourseView) -> Self {
+        let record = CourseRecord {
+            modules: view.outline.as_ref().map(Vec::len),
+            lessons: view.outline.as_ref().map(|outline| outline.iter().map(|entry| entry.lessons.len()).sum()),
+            ..CourseRecord::from(&view.course)
+        };
+        match view.converted_price.as_ref() {
+            Some(converted) => {
+                let converted = converted.as_ref();
+                let (price_minor, currency) = Price::columns(converted.map(|converted| &converted.price));
+                CourseRecord {
+                    converted_price: Some(converted.map(|converted| converted.price.formatted())),
+                    converted_price_minor: Some(price_minor),
+                    converted_currency: Some(currency),
+                    exchange_rate: Some(converted.and_then(|converted| converted.rate.map(Rate::to_f64))),
+                    rate_as_of: Some(converted.and_then(|converted| converted.rate_as_of)),
+                    ..record
+                }
+            }
+            None => record,
+        }
+    }
+}
+
+impl Negotiable for Course {
+    type Record = CourseRecord;
+
+    fn records(&self) -> Vec<CourseRecord> {
+        vec![self.into()]
+    }
+
+    fn to_xml(&self) -> Result<String, quick_xml::DeError> {
+        quick_xml::se::to_string_with_root("course", self)
+    }
+}
+
+#[derive(Serialize)]
+struct CourseList<'a> {
+    course: &'a [Course],
+}
+
+impl Negotiable for Vec<Course> {
+    type Record = CourseRecord;
+
+    fn records(&self) -> Vec<CourseRecord> {
+        self.iter().map(CourseRecord::from).collect()
+    }
+
+    fn to_xml(&self) -> Result<String, quick_xml::DeError> {
+        quick_xml::se::to_string_with_root("courses", &CourseList { course: self })
+    }
+}
+
+impl Negotiable for CourseView {
+    type Record = CourseRecord;
+
+    fn records(&self) -> Vec<CourseRecord> {
+        vec![self.into()]
+    }
+
+    fn to_xml(&self) -> Result<String, quick_xml::DeError> {
+        quick_xml::se::to_string_with_root("course", self)
+    }
+}
+
+#[derive(Serialize)]
+struct CourseViewList<'a> {
+    course: &'a [CourseView],
+}
+
+impl Negotiable for Vec<CourseView> {
+    type Record = CourseRecord;
+
+    fn records(&self) -> Vec<CourseRecord> {
+        self.iter().map(CourseRecord::from).collect()
+    }
+
+    fn to_xml(&self) -> Result<String, quick_xml::DeError> {
+        quick_xml::se::to_string_with_root("courses", &CourseViewList { course: self })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::course::CreateCourse;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn accept_picks_highest_quality() {
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT, "application/json;q=0.5, text/csv"))
+            .to_http_request();
+        assert_eq!(MediaType::from_accept(&req).unwrap(), MediaType::Csv);
+
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT, "image/png, application/msgpack;q=0.1"))
+            .to_http_request();
+        assert_eq!(MediaType::from_accept(&req).unwrap(), MediaType::MsgPack);
+    }
+
+    #[test]
+    fn accept_without_supported_type_is_rejected() {
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT, "text/html, application/json;q=0"))
+            .to_http_request();
+        let err = MediaType::from_accept(&req).unwrap_err();
+        assert!(matches!(err, MyError::NotAcceptable(_)));
+    }
+
+    #[test]
+    fn accept_refusals_hold_against_wildcards() {
+        let accepted = |accept: &str| {
+            let req = TestRequest::default()
+                .insert_header((header::ACCEPT, accept))
+                .to_http_request();
+            MediaType::from_accept(&req).ok()
+        };
+        assert_eq!(accepted("*/*"), Some(MediaType::Json));
+        assert_eq!(accepted("application/json;q=0, */*"), Some(MediaType::Csv));
+        assert_eq!(accepted("application/*, application/json;q=0"), Some(MediaType::MsgPack));
+        assert_eq!(accepted("text/*;q=0, application/json;q=0, */*;q=0.5"), Some(MediaType::MsgPack));
+        assert_eq!(accepted("text/xml;q=0, application/xml"), Some(MediaType::Xml));
+        assert_eq!(accepted("application/json;q=0.2, */*"), Some(MediaType::Csv));
+        assert_eq!(accepted("text/*, text/csv;q=0"), Some(MediaType::Xml));
+        assert_eq!(accepted("*/*;q=0"), None);
+    }
+
+    #[test]
+    fn create_course_decodes_from_csv_and_xml() {
+        let csv_body = "teacher_id,name,description,format,structure,duration,price,language,level\n\
+                        1,csv course,,self_paced,,PT1H30M,19.99 EUR,en-gb,\n";
+        let course: CreateCourse = MediaType::Csv.decode(csv_body.as_bytes()).unwrap();
+        assert_eq!(course.name, "csv course");
+        assert_eq!(course.price, Some(Price::parse("19.99 EUR").unwrap()));
+        assert_eq!(course.description, None);
+        assert_eq!(course.format, Some(CourseFormat::SelfPaced));
+        assert_eq!(course.duration, CourseDuration::parse_iso("PT90M").ok());
+        assert_eq!(course.language.unwrap().as_str(), "en-GB");
+
+        let xml_body = "<course><teacher_id>2</teacher_id><name>xml course</name></course>";
+        let course: CreateCourse = MediaType::Xml.decode(xml_body.as_bytes()).unwrap();
+        assert_eq!(course.teacher_id, 2);
+        assert_eq!(course.name, "xml course");
+    }
+
+    #[test]
+    fn bulk_rows_are_decoded_independently() {
+        let req = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/x-ndjson"))
+            .to_http_request();
+        let body = "{\"teacher_id\": 1, \"name\": \"a\"}\n\n{\"name\": \"no teacher\"}\n";
+        let rows = decode_rows::<CreateCourse>(&req, body.as_bytes()).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].1.is_ok());
+        assert_eq!(rows[1].0, 2);
+        assert!(rows[1].1.is_err());
+
+        let req = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "text/csv"))
+            .to_http_request();
+        let body = "teacher_id,name\n1,a\nx,b\n3,c\n";
+        let rows = decode_rows::<CreateCourse>(&req, body.as_bytes()).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[1].1.is_err());
+        assert_eq!(rows[2].1.as_ref().unwrap().teacher_id, 3);
+    }
+
+    #[test]
+    fn course_list_encodes_one_csv_row_per_course() {
+        let course = Course {
+            teacher_id: 1,
+            id: 7,
+            name: "c1".into(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            description: None,
+            format: None,
+            structure: None,
+            duration: None,
+            price: Some(Price::parse("10 USD").unwrap()),
+            language: None,
+            level: None,
+            capacity: None,
+            status: CourseStatus::Draft,
+            published_at: None,
+            publish_at: None,
+            unpublish_at: None,
+            review_count: 0,
+            average_rating: None,
+        };
+        let body = MediaType::Csv.encode(&vec![course.clone(), course.clone()]).unwrap();
+        let body = String::from_utf8(body).unwrap();
+        assert_eq!(body.lines().count(), 3);
+        assert!(body.starts_with("teacher_id,id,name,"));
+        assert!(body.contains(",10.00 USD,1000,USD,"));
+
+        //later batches of a streamed export carry no header
+        let batch = StreamFormat::Csv.encode_batch(std::slice::from_ref(&course), false).unwrap();
+        assert_eq!(String::from_utf8(batch).unwrap().lines().count(), 1);
+        let batch = StreamFormat::Ndjson.encode_batch(&[course.clone(), course], true).unwrap();
+        assert_eq!(String::from_utf8(batch).unwrap().lines().count(), 2);
+    }
+}
@@ -1,25 +1,165 @@
-use crate::handlers::{course::*, general::*};
-use actix_web::web;
+use crate::handlers::{certificate::*, course::*, enrollment::*, exchange_rate::*, general::*, outline::*, prerequisite::*, progress::*, quiz::*, review::*, student::*, taxonomy::*, translation::*};
+use actix_web::{middleware::DefaultHeaders, web};
+
+//unversioned paths are kept as aliases of v1 until this date (RFC 8594 HTTP-date)
+pub const LEGACY_SUNSET: &str = "Sat, 01 May 2027 00:00:00 GMT";
+pub const LEGACY_SUCCESSOR: &str = "</v1/courses/>; rel=\"successor-version\"";
 
 pub fn general_routes(cfg: &mut web::ServiceConfig) {
     cfg.route("/health", web::get().to(health_check_handler));
 }
 
 /**
-Creates a function that configures routes for course-related endpoints
-POST localhost:3000/courses/
-The scope /courses groups all course-related endpoints
-add more routes like:
-GET /courses/ (list courses)
-GET /courses/{id} (get specific course)
-PUT /courses/{id} (update course)
+Builds the /courses scope shared by every API version
+POST localhost:3000/v1/courses/
+A new version (e.g. /v2) can reuse this scope as-is, or build its own
+scope and point the routes whose contract did not change at the same handlers
 **/
-pub fn course_routes(cfg: &mut web::ServiceConfig) {
-    cfg
-    .service(web::scope("/courses")
+pub fn course_scope() -> actix_web::Scope {
+    web::scope("/courses")
     .route("/", web::post().to(post_new_course))
+    .route("/import", web::post().to(import_courses))
+    //registered before /{teacher_id} so "export" and "facets" are not read as a teacher id
+    .route("/export", web::get().to(export_courses))
+    .route("/facets", web::get().to(get_course_facets))
     .route("/{teacher_id}", web::get().to(get_courses_for_teacher))
+    //registered before /{teacher_id}/{course_id} so "trash" is not read as a course id
+    .route("/{teacher_id}/trash", web::get().to(get_trash_for_teacher))
+    .route("/{teacher_id}/{course_id}/restore", web::post().to(restore_course))
+    .route("/{teacher_id}/{course_id}/clone", web::post().to(clone_course))
+    .route("/{teacher_id}/{course_id}/publish", web::post().to(publish_course))
+    .route("/{teacher_id}/{course_id}/unpublish", web::post().to(unpublish_course))
+    .route("/{teacher_id}/{course_id}/status", web::post().to(change_course_status))
+    .route("/{teacher_id}/{course_id}/schedule", web::put().to(set_course_schedule))
+    .route("/{teacher_id}/{course_id}/history", web::get().to(get_course_history))
+    .route("/{teacher_id}/{course_id}/revisions", web::get().to(get_course_revisions))
+    .route("/{teacher_id}/{course_id}/revisions/diff", web::get().to(diff_course_revisions))
+    .route("/{teacher_id}/{course_id}/revisions/{revision}/rollback", web::post().to(rollback_course_revision))
+    //modules and lessons of the course outline, "reorder" is registered before the {module_id} and {lesson_id} routes
+    .route("/{teacher_id}/{course_id}/modules", web::get().to(get_course_modules))
+    .route("/{teacher_id}/{course_id}/modules", web::post().to(post_new_module))
+    .route("/{teacher_id}/{course_id}/modules/reorder", web::put().to(reorder_modules))
+    .route("/{teacher_id}/{course_id}/modules/{module_id}", web::get().to(get_module_detail))
+    .route("/{teacher_id}/{course_id}/modules/{module_id}", web::put().to(update_module))
+    .route("/{teacher_id}/{course_id}/modules/{module_id}", web::delete().to(delete_module))
+    .route("/{teacher_id}/{course_id}/modules/{module_id}/lessons", web::post().to(post_new_lesson))
+    .route("/{teacher_id}/{course_id}/modules/{module_id}/lessons/reorder", web::put().to(reorder_lessons))
+    .route("/{teacher_id}/{course_id}/modules/{module_id}/lessons/{lesson_id}", web::get().to(get_lesson_detail))
+    .route("/{teacher_id}/{course_id}/modules/{module_id}/lessons/{lesson_id}", web::put().to(update_lesson))
+    .route("/{teacher_id}/{course_id}/modules/{module_id}/lessons/{lesson_id}", web::delete().to(delete_lesson))
+    .route("/{teacher_id}/{course_id}/enrollments", web::get().to(get_course_roster))
+    .route("/{teacher_id}/{course_id}/enrollments", web::post().to(enroll_in_course))
+    .route("/{teacher_id}/{course_id}/enrollments/{student_id}/withdraw", web::post().to(withdraw_from_course))
+    .route("/{teacher_id}/{course_id}/enrollments/{student_id}/progress", web::get().to(get_student_progress))
+    .route("/{teacher_id}/{course_id}/enrollments/{student_id}/certificate", web::get().to(get_certificate_pdf))
+    .route("/{teacher_id}/{course_id}/enrollments/{student_id}/lessons/{lesson_id}/start", web::post().to(start_lesson))
+    .route("/{teacher_id}/{course_id}/enrollments/{student_id}/lessons/{lesson_id}/complete", web::post().to(complete_lesson))
+    .route("/{teacher_id}/{course_id}/progress", web::get().to(get_course_progress))
+    .route("/{teacher_id}/{course_id}/quizzes", web::get().to(get_course_quizzes))
+    .route("/{teacher_id}/{course_id}/quizzes", web::post().to(post_new_quiz))
+    .route("/{teacher_id}/{course_id}/quizzes/{quiz_id}", web::get().to(get_quiz_detail))
+    .route("/{teacher_id}/{course_id}/quizzes/{quiz_id}", web::put().to(update_quiz))
+    .route("/{teacher_id}/{course_id}/quizzes/{quiz_id}", web::delete().to(delete_quiz))
+    .route("/{teacher_id}/{course_id}/quizzes/{quiz_id}/stats", web::get().to(get_quiz_stats))
+    .route("/{teacher_id}/{course_id}/quizzes/{quiz_id}/attempts", web::get().to(get_quiz_attempts))
+    .route("/{teacher_id}/{course_id}/quizzes/{quiz_id}/attempts", web::post().to(start_quiz_attempt))
+    .route("/{teacher_id}/{course_id}/quizzes/{quiz_id}/attempts/{attempt_id}", web::get().to(get_attempt_detail))
+    .route("/{teacher_id}/{course_id}/quizzes/{quiz_id}/attempts/{attempt_id}/submit", web::post().to(submit_quiz_attempt))
+    .route("/{teacher_id}/{course_id}/quizzes/{quiz_id}/attempts/{attempt_id}/answers/{question_id}", web::put().to(grade_quiz_answer))
+    .route("/{teacher_id}/{course_id}/reviews", web::get().to(get_course_reviews))
+    .route("/{teacher_id}/{course_id}/reviews", web::post().to(post_new_review))
+    .route("/{teacher_id}/{course_id}/reviews/{review_id}", web::get().to(get_review_detail))
+    .route("/{teacher_id}/{course_id}/reviews/{review_id}", web::put().to(update_review))
+    .route("/{teacher_id}/{course_id}/reviews/{review_id}/reply", web::put().to(reply_to_review))
+    .route("/{teacher_id}/{course_id}/reviews/{review_id}/helpful", web::post().to(mark_review_helpful))
+    .route("/{teacher_id}/{course_id}/reviews/{review_id}/helpful/{student_id}", web::delete().to(unmark_review_helpful))
+    .route("/{teacher_id}/{course_id}/categories", web::get().to(get_course_categories))
+    .route("/{teacher_id}/{course_id}/categories", web::put().to(set_course_categories))
+    .route("/{teacher_id}/{course_id}/tags", web::get().to(get_course_tags))
+    .route("/{teacher_id}/{course_id}/tags", web::put().to(set_course_tags))
+    .route("/{teacher_id}/{course_id}/prerequisites", web::get().to(get_course_prerequisites))
+    .route("/{teacher_id}/{course_id}/prerequisites", web::put().to(set_course_prerequisites))
+    .route("/{teacher_id}/{course_id}/prerequisites/chain", web::get().to(get_prerequisite_chain))
+    .route("/{teacher_id}/{course_id}/prerequisites/overrides", web::get().to(get_prerequisite_overrides))
+    .route("/{teacher_id}/{course_id}/prerequisites/overrides", web::post().to(grant_prerequisite_override))
+    .route("/{teacher_id}/{course_id}/prerequisites/overrides/{student_id}", web::delete().to(revoke_prerequisite_override))
+    .route("/{teacher_id}/{course_id}/translations", web::get().to(get_course_translations))
+    .route("/{teacher_id}/{course_id}/translations/{locale}", web::get().to(get_course_translation))
+    .route("/{teacher_id}/{course_id}/translations/{locale}", web::put().to(save_course_translation))
+    .route("/{teacher_id}/{course_id}/translations/{locale}", web::delete().to(delete_course_translation))
     .route("/{teacher_id}/{course_id}", web::get().to(get_course_detail))
     .route("/{teacher_id}/{course_id}", web::delete().to(delete_course))
-    .route("/{teacher_id}/{course_id}", web::put().to(update_course_details)));
-}
\ No newline at end of file
+    .route("/{teacher_id}/{course_id}", web::put().to(update_course_details))
+}
+
+//rates used to show course prices in other currencies, replaced by an admin
+pub fn exchange_rate_scope() -> actix_web::Scope {
+    web::scope("/exchange-rates")
+    .route("", web::get().to(get_exchange_rates))
+    .route("", web::put().to(replace_exchange_rates))
+}
+
+//the category tree is public, changing it needs an admin
+pub fn category_scope() -> actix_web::Scope {
+    web::scope("/categories")
+    .route("", web::get().to(get_category_tree))
+    .route("", web::post().to(post_new_category))
+    .route("/{category_id}", web::get().to(get_category_detail))
+    .route("/{category_id}", web::put().to(update_category))
+    .route("/{category_id}", web::delete().to(delete_category))
+}
+
+//tags in use, for autocompletion
+pub fn tag_scope() -> actix_web::Scope {
+    web::scope("/tags")
+    .route("", web::get().to(get_tag_suggestions))
+}
+
+//students and the courses they enrolled in
+pub fn student_scope() -> actix_web::Scope {
+    web::scope("/students")
+    .route("", web::post().to(post_new_student))
+    .route("/{student_id}", web::get().to(get_student_detail))
+    .route("/{student_id}", web::put().to(update_student))
+    .route("/{student_id}/enrollments", web::get().to(get_student_enrollments))
+    .route("/{student_id}/notifications", web::get().to(get_student_notifications))
+}
+
+//verification is public, anyone holding a certificate can check its code
+pub fn certificate_scope() -> actix_web::Scope {
+    web::scope("/certificates")
+    .route("/verify/{code}", web::get().to(verify_certificate))
+}
+
+/**
+Mounts the current API under /v1
+GET localhost:3000/v1/courses/1
+A /v2 scope can be added next to it with
+cfg.service(web::scope("/v2").service(...))
+**/
+pub fn v1_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/v1")
+            .service(course_scope())
+            .service(exchange_rate_scope())
+            .service(student_scope())
+            .service(certificate_scope())
+            .service(category_scope())
+            .service(tag_scope()),
+    );
+}
+
+/**
+Unversioned /courses paths, kept as aliases of /v1 for old clients
+Every response carries Deprecation, Sunset and a Link to the successor version
+**/
+pub fn course_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        course_scope().wrap(
+            DefaultHeaders::new()
+                .add(("Deprecation", "true"))
+                .add(("Sunset", LEGACY_SUNSET))
+                .add(("Link", LEGACY_SUCCESSOR)),
+        ),
+    );
+}
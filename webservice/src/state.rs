@@ -1,6 +1,7 @@
 use std::sync::Mutex;
 //use super::models::Course;
 use sqlx::postgres::PgPool;
+use crate::models::certificate::CertificateSettings;
 
 //can be used in multi threaded env
 pub struct AppState {
@@ -9,4 +10,6 @@ pub struct AppState {
     pub visit_count: Mutex<u32>,
     //pub courses: Mutex<Vec<Course>>
     pub db: PgPool,
+    //where completion certificates are written and their template
+    pub certificates: CertificateSettings,
 }